@@ -1,12 +1,14 @@
 mod config;
 mod perps;
+mod stats;
 mod types;
 mod utils;
 
 pub use config::BinancePerpsClientConfig;
 pub use perps::BinancePerpsClient;
+pub use stats::{BinanceClientStats, EndpointStats};
 pub use types::{
-    OrderResponse, OrderType, Orderbook, PlaceOrderRequest, Position, PositionSide, Side,
-    TimeInForce,
+    BinanceApiError, Income, MarkPrice, OrderInfo, OrderResponse, OrderType, Orderbook,
+    PlaceOrderRequest, Position, PositionSide, Side, TimeInForce,
 };
 pub use utils::fapi_signed_request;
@@ -1,20 +1,28 @@
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
 
 use anyhow::Result;
+use reqwest::StatusCode;
+use serde::de::DeserializeOwned;
 
 use crate::config::BinancePerpsClientConfig;
+use crate::stats::BinanceClientStats;
 use crate::types::{
-    OrderResponse, OrderType, Orderbook, PlaceOrderRequest, Position, PositionSide, Side,
-    TimeInForce,
+    BinanceApiError, Income, MarkPrice, OrderInfo, OrderResponse, OrderType, Orderbook,
+    PlaceOrderRequest, Position, PositionSide, Side, TimeInForce,
 };
 use crate::utils;
 
 /// Client for Binance perpetual futures (USDT-M) API.
+///
+/// Cloning is cheap and shares the underlying HTTP connection pool and request statistics.
+#[derive(Clone)]
 pub struct BinancePerpsClient {
     client: Arc<reqwest::Client>,
     api_key: String,
     api_secret: String,
     base_url: String,
+    stats: Arc<Mutex<BinanceClientStats>>,
 }
 
 impl BinancePerpsClient {
@@ -32,9 +40,43 @@ impl BinancePerpsClient {
             api_key: config.api_key,
             api_secret: config.api_secret,
             base_url: config.base_url,
+            stats: Arc::default(),
         }
     }
 
+    /// Returns the request statistics collected since the client was created
+    ///
+    /// # Returns
+    /// A copy of the per-endpoint request counts and latencies and the last rate limit counters
+    pub fn stats(&self) -> BinanceClientStats {
+        self.stats.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    /// Sends a request and records it in the client's statistics
+    ///
+    /// # Arguments
+    /// * `endpoint` - `"<METHOD> <path>"` label of the request
+    /// * `request` - Request to send
+    async fn send(
+        &self,
+        endpoint: &'static str,
+        request: reqwest::RequestBuilder,
+    ) -> Result<reqwest::Response> {
+        let start = Instant::now();
+        let result = request.send().await;
+        let mut stats = self.stats.lock().unwrap_or_else(|e| e.into_inner());
+        match &result {
+            Ok(resp) => stats.record_response(
+                endpoint,
+                start.elapsed(),
+                resp.headers(),
+                resp.status().is_success(),
+            ),
+            Err(_) => stats.record_transport_error(endpoint),
+        }
+        Ok(result?)
+    }
+
     pub async fn get_position(&self, pair: &str) -> Result<Vec<Position>> {
         let params: Vec<(&str, String)> = vec![
             ("symbol", pair.to_string()),
@@ -43,14 +85,14 @@ impl BinancePerpsClient {
         let signed_query = utils::sign_params(&self.api_secret, &params);
         let url = format!("{}/fapi/v3/positionRisk?{}", self.base_url, signed_query);
         let resp = self
-            .client
-            .get(&url)
-            .header("X-MBX-APIKEY", &self.api_key)
-            .send()
-            .await?
-            .json::<Vec<Position>>()
+            .send(
+                "GET /fapi/v3/positionRisk",
+                self.client.get(&url).header("X-MBX-APIKEY", &self.api_key),
+            )
             .await?;
-        Ok(resp)
+        let status = resp.status();
+        let body = resp.text().await?;
+        parse_response("position risk", status, &body)
     }
 
     /// Fetches the order book (market depth) for the given symbol.
@@ -67,15 +109,93 @@ impl BinancePerpsClient {
         }
         let url = format!("{}/fapi/v1/depth?{}", self.base_url, query);
         let resp = self
-            .client
-            .get(&url)
-            .send()
+            .send("GET /fapi/v1/depth", self.client.get(&url))
             .await?
             .json::<Orderbook>()
             .await?;
         Ok(resp)
     }
 
+    /// Fetches the mark price of the given symbol.
+    ///
+    /// Calls GET `/fapi/v1/premiumIndex`. This is a public endpoint; no API key or signature is required.
+    ///
+    /// # Arguments
+    /// * `symbol` - Trading pair symbol (e.g. `CAKEUSDT`)
+    pub async fn get_mark_price(&self, symbol: &str) -> Result<MarkPrice> {
+        let url = format!("{}/fapi/v1/premiumIndex?symbol={}", self.base_url, symbol);
+        let resp = self
+            .send("GET /fapi/v1/premiumIndex", self.client.get(&url))
+            .await?
+            .json::<MarkPrice>()
+            .await?;
+        Ok(resp)
+    }
+
+    /// Fetches the income history (realized PnL, funding fees, commissions, ...) of a symbol.
+    ///
+    /// Calls GET `/fapi/v1/income`, following pages of 1000 entries until `end_time_ms`.
+    /// Binance only keeps the most recent three months of income history.
+    ///
+    /// # Arguments
+    /// * `symbol` - Trading pair symbol (e.g. `BTCUSDT`)
+    /// * `start_time_ms` - Start of the window in milliseconds since Unix epoch (inclusive)
+    /// * `end_time_ms` - End of the window in milliseconds since Unix epoch (inclusive)
+    pub async fn get_income_history(
+        &self,
+        symbol: &str,
+        start_time_ms: i64,
+        end_time_ms: i64,
+    ) -> Result<Vec<Income>> {
+        const PAGE_LIMIT: usize = 1000;
+        let mut incomes: Vec<Income> = Vec::new();
+        let mut start = start_time_ms;
+        while start <= end_time_ms {
+            let params: Vec<(&str, String)> = vec![
+                ("symbol", symbol.to_string()),
+                ("startTime", start.to_string()),
+                ("endTime", end_time_ms.to_string()),
+                ("limit", PAGE_LIMIT.to_string()),
+                ("timestamp", utils::binance_fapi_timestamp_ms()),
+            ];
+            let signed_query = utils::sign_params(&self.api_secret, &params);
+            let url = format!("{}/fapi/v1/income?{}", self.base_url, signed_query);
+            let resp = self
+                .send(
+                    "GET /fapi/v1/income",
+                    self.client.get(&url).header("X-MBX-APIKEY", &self.api_key),
+                )
+                .await?;
+            let status = resp.status();
+            let body = resp.text().await?;
+            let page: Vec<Income> = parse_response("income history", status, &body)?;
+
+            let page_len = page.len();
+            let Some(last_time) = page.last().map(|income| income.time) else {
+                break;
+            };
+            // Entries sharing the boundary timestamp are returned again on the next page
+            let fresh: Vec<Income> = page
+                .into_iter()
+                .filter(|income| {
+                    !incomes.iter().any(|seen| {
+                        seen.tran_id == income.tran_id && seen.income_type == income.income_type
+                    })
+                })
+                .collect();
+            incomes.extend(fresh);
+            if page_len < PAGE_LIMIT {
+                break;
+            }
+            start = if last_time > start {
+                last_time
+            } else {
+                last_time + 1
+            };
+        }
+        Ok(incomes)
+    }
+
     /// Submits a single order to Binance POST `/fapi/v1/order`.
     pub async fn place_order(
         &self,
@@ -94,6 +214,9 @@ impl BinancePerpsClient {
         if let Some(v) = req.reduce_only {
             params.push(("reduceOnly", v.to_string()));
         }
+        if let Some(ref id) = req.client_order_id {
+            params.push(("newClientOrderId", id.clone()));
+        }
         if req.order_type == OrderType::Limit {
             if let Some(ref price) = req.price {
                 params.push(("price", price.clone()));
@@ -102,26 +225,130 @@ impl BinancePerpsClient {
         let signed_query = utils::sign_params(&self.api_secret, &params);
         let url = format!("{}/fapi/v1/order", self.base_url);
         let resp = self
-            .client
-            .post(&url)
-            .header("X-MBX-APIKEY", &self.api_key)
-            .header(
-                reqwest::header::CONTENT_TYPE,
-                "application/x-www-form-urlencoded",
+            .send(
+                "POST /fapi/v1/order",
+                self.client
+                    .post(&url)
+                    .header("X-MBX-APIKEY", &self.api_key)
+                    .header(
+                        reqwest::header::CONTENT_TYPE,
+                        "application/x-www-form-urlencoded",
+                    )
+                    .body(signed_query),
             )
-            .body(signed_query)
-            .send()
             .await?;
         let status = resp.status();
         let body = resp.text().await?;
         println!("place_order: http status={} body={}", status, body);
-        let order_response: OrderResponse = serde_json::from_str(&body)
-            .map_err(|e| anyhow::anyhow!("parse order response: {} body={}", e, body))?;
-        Ok(order_response)
+        parse_response("order response", status, &body)
     }
 
-    /// Places a limit sell at best ask (asks0) to open a short position.
-    pub async fn open_sell(&self, symbol: &str, amount: &str) -> Result<OrderResponse> {
+    /// Fetches the open orders of a symbol via GET `/fapi/v1/openOrders`.
+    ///
+    /// # Arguments
+    /// * `symbol` - Futures symbol (e.g. "BNBUSDT")
+    pub async fn get_open_orders(&self, symbol: &str) -> Result<Vec<OrderInfo>> {
+        let params: Vec<(&str, String)> = vec![
+            ("symbol", symbol.to_string()),
+            ("timestamp", utils::binance_fapi_timestamp_ms()),
+        ];
+        let signed_query = utils::sign_params(&self.api_secret, &params);
+        let url = format!("{}/fapi/v1/openOrders?{}", self.base_url, signed_query);
+        let resp = self
+            .send(
+                "GET /fapi/v1/openOrders",
+                self.client.get(&url).header("X-MBX-APIKEY", &self.api_key),
+            )
+            .await?;
+        let status = resp.status();
+        let body = resp.text().await?;
+        parse_response("open orders", status, &body)
+    }
+
+    /// Fetches one order by client order ID via GET `/fapi/v1/order`.
+    ///
+    /// # Arguments
+    /// * `symbol` - Futures symbol (e.g. "BNBUSDT")
+    /// * `client_order_id` - Client order ID the order was placed with
+    ///
+    /// # Returns
+    /// `None` if Binance does not know the order: it was never accepted, or it was cancelled
+    /// or expired without fills more than 3 days ago
+    pub async fn get_order(
+        &self,
+        symbol: &str,
+        client_order_id: &str,
+    ) -> Result<Option<OrderInfo>> {
+        let params: Vec<(&str, String)> = vec![
+            ("symbol", symbol.to_string()),
+            ("origClientOrderId", client_order_id.to_string()),
+            ("timestamp", utils::binance_fapi_timestamp_ms()),
+        ];
+        let signed_query = utils::sign_params(&self.api_secret, &params);
+        let url = format!("{}/fapi/v1/order?{}", self.base_url, signed_query);
+        let resp = self
+            .send(
+                "GET /fapi/v1/order",
+                self.client.get(&url).header("X-MBX-APIKEY", &self.api_key),
+            )
+            .await?;
+        let status = resp.status();
+        let body = resp.text().await?;
+        match parse_response("order", status, &body) {
+            Ok(order) => Ok(Some(order)),
+            Err(e)
+                if e.downcast_ref::<BinanceApiError>()
+                    .is_some_and(BinanceApiError::is_unknown_order) =>
+            {
+                Ok(None)
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Cancels every open order on a symbol via DELETE `/fapi/v1/allOpenOrders`.
+    ///
+    /// # Arguments
+    /// * `symbol` - Futures symbol (e.g. "BNBUSDT")
+    ///
+    /// # Returns
+    /// An error if the request fails or Binance rejects it
+    pub async fn cancel_all_open_orders(&self, symbol: &str) -> Result<()> {
+        let params: Vec<(&str, String)> = vec![
+            ("symbol", symbol.to_string()),
+            ("timestamp", utils::binance_fapi_timestamp_ms()),
+        ];
+        let signed_query = utils::sign_params(&self.api_secret, &params);
+        let url = format!("{}/fapi/v1/allOpenOrders?{}", self.base_url, signed_query);
+        let resp = self
+            .send(
+                "DELETE /fapi/v1/allOpenOrders",
+                self.client
+                    .delete(&url)
+                    .header("X-MBX-APIKEY", &self.api_key),
+            )
+            .await?;
+        let status = resp.status();
+        let body = resp.text().await?;
+        println!(
+            "cancel_all_open_orders: symbol={} http status={} body={}",
+            symbol, status, body
+        );
+        if !status.is_success() {
+            anyhow::bail!(
+                "cancel all open orders on {} failed: {} body={}",
+                symbol,
+                status,
+                body
+            );
+        }
+        Ok(())
+    }
+
+    /// Builds the limit sell at best ask (asks0) that opens a short position, without placing it.
+    ///
+    /// Lets callers check the order (e.g. its price against the mark price) before `place_order`.
+    pub async fn open_sell_request(&self, symbol: &str, amount: &str) -> Result<PlaceOrderRequest> {
         println!(
             "open_sell: symbol={} amount={} fetching orderbook",
             symbol, amount
@@ -131,30 +358,26 @@ impl BinancePerpsClient {
             .asks
             .first()
             .ok_or_else(|| anyhow::anyhow!("orderbook asks empty"))?;
-        let price = ask[0].clone();
-        println!(
-            "open_sell: symbol={} amount={} price={} placing limit sell at best ask",
-            symbol, amount, price
-        );
-        let req = PlaceOrderRequest {
+        Ok(PlaceOrderRequest {
             side: Side::Sell,
             position_side: PositionSide::Short,
             order_type: OrderType::Limit,
             quantity: amount.to_string(),
-            price: Some(price),
+            price: Some(ask[0].clone()),
             reduce_only: None,
             time_in_force: TimeInForce::Gtc,
-        };
-        let resp = self.place_order(symbol, &req).await?;
-        println!(
-            "open_sell: symbol={} order_id={} order placed",
-            symbol, resp.order_id
-        );
-        Ok(resp)
+            client_order_id: None,
+        })
     }
 
-    /// Places a limit buy at best bid (bids0), reduce-only, to close a short position.
-    pub async fn close_sell(&self, symbol: &str, amount: &str) -> Result<OrderResponse> {
+    /// Builds the limit buy at best bid (bids0) that closes a short position, without placing it.
+    ///
+    /// Lets callers check the order (e.g. its price against the mark price) before `place_order`.
+    pub async fn close_sell_request(
+        &self,
+        symbol: &str,
+        amount: &str,
+    ) -> Result<PlaceOrderRequest> {
         println!(
             "close_sell: symbol={} amount={} fetching orderbook",
             symbol, amount
@@ -164,20 +387,44 @@ impl BinancePerpsClient {
             .bids
             .first()
             .ok_or_else(|| anyhow::anyhow!("orderbook bids empty"))?;
-        let price = bid[0].clone();
-        println!(
-            "close_sell: symbol={} amount={} price={} placing limit buy at best bid (reduce-only)",
-            symbol, amount, price
-        );
-        let req = PlaceOrderRequest {
+        Ok(PlaceOrderRequest {
             side: Side::Buy,
             position_side: PositionSide::Short,
             order_type: OrderType::Limit,
             quantity: amount.to_string(),
-            price: Some(price),
+            price: Some(bid[0].clone()),
             reduce_only: None,
             time_in_force: TimeInForce::Gtc,
-        };
+            client_order_id: None,
+        })
+    }
+
+    /// Places a limit sell at best ask (asks0) to open a short position.
+    pub async fn open_sell(&self, symbol: &str, amount: &str) -> Result<OrderResponse> {
+        let req = self.open_sell_request(symbol, amount).await?;
+        println!(
+            "open_sell: symbol={} amount={} price={} placing limit sell at best ask",
+            symbol,
+            amount,
+            req.price.as_deref().unwrap_or_default()
+        );
+        let resp = self.place_order(symbol, &req).await?;
+        println!(
+            "open_sell: symbol={} order_id={} order placed",
+            symbol, resp.order_id
+        );
+        Ok(resp)
+    }
+
+    /// Places a limit buy at best bid (bids0), reduce-only, to close a short position.
+    pub async fn close_sell(&self, symbol: &str, amount: &str) -> Result<OrderResponse> {
+        let req = self.close_sell_request(symbol, amount).await?;
+        println!(
+            "close_sell: symbol={} amount={} price={} placing limit buy at best bid (reduce-only)",
+            symbol,
+            amount,
+            req.price.as_deref().unwrap_or_default()
+        );
         let resp = self.place_order(symbol, &req).await?;
         println!(
             "close_sell: symbol={} order_id={} order placed",
@@ -186,3 +433,20 @@ impl BinancePerpsClient {
         Ok(resp)
     }
 }
+
+/// Parses a response body, returning a `BinanceApiError` when Binance rejected the request
+///
+/// # Arguments
+/// * `what` - Description of the response used in parse errors
+/// * `status` - HTTP status of the response
+/// * `body` - Response body
+fn parse_response<T: DeserializeOwned>(what: &str, status: StatusCode, body: &str) -> Result<T> {
+    if !status.is_success() {
+        if let Ok(mut api_error) = serde_json::from_str::<BinanceApiError>(body) {
+            api_error.status = status.as_u16();
+            return Err(api_error.into());
+        }
+        anyhow::bail!("{} failed: http status={} body={}", what, status, body);
+    }
+    serde_json::from_str(body).map_err(|e| anyhow::anyhow!("parse {}: {} body={}", what, e, body))
+}
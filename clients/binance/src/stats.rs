@@ -0,0 +1,67 @@
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use ::utils::LatencyHistogram;
+
+/// Requests sent to one REST endpoint
+#[derive(Debug, Clone, Default)]
+pub struct EndpointStats {
+    /// Number of requests sent
+    pub requests: u64,
+    /// Requests that failed in transport or returned a non-2xx HTTP status
+    pub errors: u64,
+    /// Latency of the requests that got a response
+    pub latency: LatencyHistogram,
+}
+
+/// Request statistics of a `BinancePerpsClient`, shared by its clones
+#[derive(Debug, Clone, Default)]
+pub struct BinanceClientStats {
+    /// Per endpoint, keyed by `"<METHOD> <path>"` (e.g. `"GET /fapi/v1/depth"`)
+    pub endpoints: BTreeMap<&'static str, EndpointStats>,
+    /// Request weight used in the current minute, from the last `X-MBX-USED-WEIGHT-1M` header
+    pub used_weight_1m: Option<u64>,
+    /// Orders placed in the current minute, from the last `X-MBX-ORDER-COUNT-1M` header
+    pub order_count_1m: Option<u64>,
+}
+
+impl BinanceClientStats {
+    /// Records a request that got a response
+    ///
+    /// # Arguments
+    /// * `endpoint` - `"<METHOD> <path>"` of the request
+    /// * `latency` - Time until the response headers arrived
+    /// * `headers` - Response headers, read for the rate limit counters
+    /// * `success` - Whether the HTTP status is 2xx
+    pub(crate) fn record_response(
+        &mut self,
+        endpoint: &'static str,
+        latency: Duration,
+        headers: &reqwest::header::HeaderMap,
+        success: bool,
+    ) {
+        let stats = self.endpoints.entry(endpoint).or_default();
+        stats.requests += 1;
+        stats.latency.observe(latency);
+        if !success {
+            stats.errors += 1;
+        }
+        if let Some(weight) = header_u64(headers, "x-mbx-used-weight-1m") {
+            self.used_weight_1m = Some(weight);
+        }
+        if let Some(count) = header_u64(headers, "x-mbx-order-count-1m") {
+            self.order_count_1m = Some(count);
+        }
+    }
+
+    /// Records a request that failed before a response arrived
+    pub(crate) fn record_transport_error(&mut self, endpoint: &'static str) {
+        let stats = self.endpoints.entry(endpoint).or_default();
+        stats.requests += 1;
+        stats.errors += 1;
+    }
+}
+
+fn header_u64(headers: &reqwest::header::HeaderMap, name: &str) -> Option<u64> {
+    headers.get(name)?.to_str().ok()?.trim().parse().ok()
+}
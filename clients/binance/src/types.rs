@@ -78,8 +78,64 @@ pub struct PlaceOrderRequest {
     pub price: Option<String>,
     pub reduce_only: Option<bool>,
     pub time_in_force: TimeInForce,
+    /// Client order ID sent as `newClientOrderId` (at most 36 characters of
+    /// `[.A-Z:/a-z0-9_-]`); `None` lets Binance generate one
+    pub client_order_id: Option<String>,
 }
 
+/// Error payload Binance returns for a rejected request (e.g. `{"code":-2019,"msg":"Margin is insufficient."}`)
+#[derive(Debug, Clone, Deserialize)]
+pub struct BinanceApiError {
+    /// HTTP status of the response
+    #[serde(skip)]
+    pub status: u16,
+    /// Binance error code (negative)
+    pub code: i64,
+    /// Error message
+    pub msg: String,
+}
+
+impl BinanceApiError {
+    /// Whether the API key, signature or permissions were rejected; retrying cannot succeed
+    pub fn is_auth_error(&self) -> bool {
+        matches!(self.status, 401 | 403) || matches!(self.code, -1002 | -1022 | -2014 | -2015)
+    }
+
+    /// Whether the request was malformed (bad symbol, quantity precision, parameters); retrying
+    /// the same request cannot succeed
+    pub fn is_invalid_request(&self) -> bool {
+        (-1199..=-1100).contains(&self.code)
+    }
+
+    /// Whether the request was rejected by rate limiting or an overloaded server
+    pub fn is_rate_limited(&self) -> bool {
+        matches!(self.status, 418 | 429) || matches!(self.code, -1003 | -1008 | -1015)
+    }
+
+    /// Whether Binance timed out waiting for its backend (-1007): the request may still have
+    /// been executed, so e.g. an order may exist
+    pub fn is_execution_unknown(&self) -> bool {
+        self.code == -1007
+    }
+
+    /// Whether the queried order does not exist (never accepted, or archived by Binance)
+    pub fn is_unknown_order(&self) -> bool {
+        self.code == -2013
+    }
+}
+
+impl std::fmt::Display for BinanceApiError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Binance API error {} (http {}): {}",
+            self.code, self.status, self.msg
+        )
+    }
+}
+
+impl std::error::Error for BinanceApiError {}
+
 /// Response from Binance POST `/fapi/v1/order` (New Order).
 #[derive(Debug, Clone, Deserialize)]
 pub struct OrderResponse {
@@ -129,6 +185,30 @@ pub struct OrderResponse {
     pub good_till_date: Option<i64>,
 }
 
+/// Order state from Binance GET `/fapi/v1/order` and GET `/fapi/v1/openOrders`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct OrderInfo {
+    pub symbol: String,
+    #[serde(rename = "orderId")]
+    pub order_id: i64,
+    #[serde(rename = "clientOrderId")]
+    pub client_order_id: String,
+    pub side: Side,
+    /// Order status, e.g. `NEW`, `PARTIALLY_FILLED`, `FILLED`, `CANCELED`, `EXPIRED`
+    pub status: String,
+    pub price: String,
+    #[serde(rename = "origQty")]
+    pub orig_qty: String,
+    #[serde(rename = "executedQty")]
+    pub executed_qty: String,
+    /// Average fill price; `0` before the first fill
+    #[serde(rename = "avgPrice")]
+    pub avg_price: String,
+    /// Time of the last update in milliseconds since Unix epoch
+    #[serde(rename = "updateTime")]
+    pub update_time: i64,
+}
+
 /// Position information from Binance perpetual futures API.
 #[derive(Debug, Clone, Deserialize)]
 pub struct Position {
@@ -171,6 +251,42 @@ pub struct Position {
     pub update_time: i64,
 }
 
+/// Income history entry from Binance GET `/fapi/v1/income`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Income {
+    pub symbol: String,
+    /// Income type, e.g. `REALIZED_PNL`, `FUNDING_FEE`, `COMMISSION`, `TRANSFER`
+    #[serde(rename = "incomeType")]
+    pub income_type: String,
+    /// Signed amount in `asset`
+    pub income: String,
+    pub asset: String,
+    pub info: String,
+    /// Time of the entry in milliseconds since Unix epoch
+    pub time: i64,
+    #[serde(rename = "tranId")]
+    pub tran_id: i64,
+    #[serde(rename = "tradeId")]
+    pub trade_id: String,
+}
+
+/// Mark price and funding rate from Binance GET `/fapi/v1/premiumIndex`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct MarkPrice {
+    pub symbol: String,
+    #[serde(rename = "markPrice")]
+    pub mark_price: String,
+    #[serde(rename = "indexPrice")]
+    pub index_price: String,
+    #[serde(rename = "lastFundingRate")]
+    pub last_funding_rate: String,
+    /// Next funding time in milliseconds since Unix epoch
+    #[serde(rename = "nextFundingTime")]
+    pub next_funding_time: i64,
+    /// Time of the quote in milliseconds since Unix epoch
+    pub time: i64,
+}
+
 /// Order book (market depth) from Binance perpetual futures API.
 #[derive(Debug, Clone, Deserialize)]
 pub struct Orderbook {
@@ -0,0 +1,62 @@
+//! Configuration types for the RPC client.
+
+use serde::{Deserialize, Serialize};
+
+/// Configuration for FailoverTransport
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RpcPoolConfig {
+    /// HTTP JSON-RPC endpoint URLs, in order of preference
+    pub urls: Vec<String>,
+    /// Number of endpoints that must have reached a block before `eth_blockNumber` reports it (1 disables quorum reads)
+    #[serde(default = "default_quorum")]
+    pub quorum: usize,
+    /// Blocks an endpoint may trail the highest known head before it is deprioritized
+    #[serde(default = "default_max_head_lag")]
+    pub max_head_lag: u64,
+    /// Timeout of a single HTTP request, in milliseconds
+    #[serde(default = "default_request_timeout_ms")]
+    pub request_timeout_ms: u64,
+    /// Consecutive failures after which an endpoint is put on cooldown
+    #[serde(default = "default_max_consecutive_failures")]
+    pub max_consecutive_failures: u32,
+    /// Seconds an endpoint stays on cooldown before it is tried first again
+    #[serde(default = "default_failure_cooldown_secs")]
+    pub failure_cooldown_secs: u64,
+}
+
+fn default_quorum() -> usize {
+    1
+}
+
+fn default_max_head_lag() -> u64 {
+    3
+}
+
+fn default_request_timeout_ms() -> u64 {
+    10_000
+}
+
+fn default_max_consecutive_failures() -> u32 {
+    3
+}
+
+fn default_failure_cooldown_secs() -> u64 {
+    30
+}
+
+impl RpcPoolConfig {
+    /// Builds a configuration with default health settings for the given endpoints
+    ///
+    /// # Arguments
+    /// * `urls` - HTTP JSON-RPC endpoint URLs, in order of preference
+    pub fn new(urls: Vec<String>) -> Self {
+        Self {
+            urls,
+            quorum: default_quorum(),
+            max_head_lag: default_max_head_lag(),
+            request_timeout_ms: default_request_timeout_ms(),
+            max_consecutive_failures: default_max_consecutive_failures(),
+            failure_cooldown_secs: default_failure_cooldown_secs(),
+        }
+    }
+}
@@ -0,0 +1,5 @@
+mod config;
+mod transport;
+
+pub use config::RpcPoolConfig;
+pub use transport::{EndpointStatus, FailoverTransport};
@@ -0,0 +1,401 @@
+//! JSON-RPC transport that spreads requests over several HTTP endpoints.
+//!
+//! Endpoints are ranked by health (cooldown after repeated failures, head lag,
+//! average latency) and a request is retried on the next endpoint when one fails.
+//! With a quorum above one, `eth_blockNumber` returns the highest block that at
+//! least `quorum` endpoints have reached instead of a single endpoint's head.
+
+use crate::config::RpcPoolConfig;
+use alloy::network::Ethereum;
+use alloy::primitives::U64;
+use alloy::providers::{DynProvider, Provider, RootProvider};
+use alloy::rpc::client::RpcClient;
+use alloy::rpc::json_rpc::{
+    RequestPacket, Response, ResponsePacket, ResponsePayload, SerializedRequest,
+};
+use alloy::transports::http::{reqwest, Http};
+use alloy::transports::{TransportError, TransportErrorKind, TransportFut, TransportResult};
+use anyhow::{bail, Context, Result};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::{Context as TaskContext, Poll};
+use std::time::{Duration, Instant};
+use tokio::task::JoinSet;
+use tower::Service;
+use utils::LatencyHistogram;
+
+/// Weight of the latest sample in the latency moving average
+const LATENCY_EWMA_ALPHA: f64 = 0.2;
+
+/// Health of one endpoint as seen by the transport
+#[derive(Debug, Clone)]
+pub struct EndpointStatus {
+    /// Endpoint URL
+    pub url: String,
+    /// Whether the endpoint is neither on cooldown nor lagging
+    pub healthy: bool,
+    /// Moving average of successful request latency, in milliseconds (`None` before the first success)
+    pub latency_ms: Option<f64>,
+    /// Distribution of successful request latency
+    pub latency: LatencyHistogram,
+    /// Number of successful requests
+    pub successes: u64,
+    /// Number of failed requests
+    pub failures: u64,
+    /// Failures since the last success
+    pub consecutive_failures: u32,
+    /// Last block number reported by the endpoint
+    pub head: Option<u64>,
+    /// Blocks the endpoint trails the highest known head
+    pub head_lag: u64,
+}
+
+#[derive(Debug, Default)]
+struct EndpointHealth {
+    latency_ms: Option<f64>,
+    latency: LatencyHistogram,
+    successes: u64,
+    failures: u64,
+    consecutive_failures: u32,
+    cooldown_until: Option<Instant>,
+    head: Option<u64>,
+}
+
+struct Endpoint {
+    url: String,
+    transport: Http<reqwest::Client>,
+    health: Mutex<EndpointHealth>,
+}
+
+impl Endpoint {
+    fn health(&self) -> std::sync::MutexGuard<'_, EndpointHealth> {
+        self.health.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn record_success(&self, latency: Duration) {
+        let latency_ms = latency.as_secs_f64() * 1000.0;
+        let mut health = self.health();
+        health.successes += 1;
+        health.consecutive_failures = 0;
+        health.cooldown_until = None;
+        health.latency.observe(latency);
+        health.latency_ms = Some(match health.latency_ms {
+            Some(avg) => avg + LATENCY_EWMA_ALPHA * (latency_ms - avg),
+            None => latency_ms,
+        });
+    }
+
+    fn record_failure(&self, config: &RpcPoolConfig, reason: &str) {
+        let mut health = self.health();
+        health.failures += 1;
+        health.consecutive_failures += 1;
+        println!("RPC endpoint {} failed: {}", self.url, reason);
+        if health.consecutive_failures >= config.max_consecutive_failures {
+            health.cooldown_until =
+                Some(Instant::now() + Duration::from_secs(config.failure_cooldown_secs));
+            println!(
+                "RPC endpoint {} on cooldown for {}s after {} consecutive failures",
+                self.url, config.failure_cooldown_secs, health.consecutive_failures
+            );
+        }
+    }
+}
+
+struct Inner {
+    config: RpcPoolConfig,
+    endpoints: Vec<Endpoint>,
+    /// Highest head reported by any endpoint
+    best_head: AtomicU64,
+}
+
+impl Inner {
+    fn record_head(&self, index: usize, head: u64) {
+        self.endpoints[index].health().head = Some(head);
+        self.best_head.fetch_max(head, Ordering::Relaxed);
+    }
+
+    fn head_lag(&self, head: Option<u64>) -> u64 {
+        let best = self.best_head.load(Ordering::Relaxed);
+        head.map(|h| best.saturating_sub(h)).unwrap_or(0)
+    }
+
+    /// Endpoint indices, best first: not on cooldown, then not lagging, then lowest latency
+    fn ranked(&self) -> Vec<usize> {
+        let now = Instant::now();
+        let mut ranked: Vec<(bool, bool, f64, usize)> = self
+            .endpoints
+            .iter()
+            .enumerate()
+            .map(|(i, ep)| {
+                let health = ep.health();
+                let cooling = health.cooldown_until.is_some_and(|until| until > now);
+                let lagging = self.head_lag(health.head) > self.config.max_head_lag;
+                (cooling, lagging, health.latency_ms.unwrap_or(0.0), i)
+            })
+            .collect();
+        ranked.sort_by(|a, b| {
+            (a.0, a.1)
+                .cmp(&(b.0, b.1))
+                .then(a.2.total_cmp(&b.2))
+                .then(a.3.cmp(&b.3))
+        });
+        ranked.into_iter().map(|(_, _, _, i)| i).collect()
+    }
+}
+
+/// Failover JSON-RPC transport over several HTTP endpoints
+///
+/// Cloning is cheap and clones share endpoint health.
+#[derive(Clone)]
+pub struct FailoverTransport {
+    inner: Arc<Inner>,
+}
+
+impl FailoverTransport {
+    /// Creates a transport over the configured endpoints
+    ///
+    /// # Arguments
+    /// * `config` - Endpoint URLs, quorum and health settings
+    ///
+    /// # Returns
+    /// An error if no URL is configured, a URL is invalid or the quorum exceeds the number of endpoints
+    pub fn new(config: RpcPoolConfig) -> Result<Self> {
+        if config.urls.is_empty() {
+            bail!("At least one RPC URL is required");
+        }
+        if config.quorum == 0 || config.quorum > config.urls.len() {
+            bail!(
+                "RPC quorum {} must be between 1 and the number of endpoints ({})",
+                config.quorum,
+                config.urls.len()
+            );
+        }
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_millis(config.request_timeout_ms))
+            .build()?;
+        let endpoints = config
+            .urls
+            .iter()
+            .map(|url| {
+                let parsed = url
+                    .trim()
+                    .parse()
+                    .with_context(|| format!("Invalid RPC URL {}", url))?;
+                Ok(Endpoint {
+                    url: url.trim().to_string(),
+                    transport: Http::with_client(client.clone(), parsed),
+                    health: Mutex::new(EndpointHealth::default()),
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self {
+            inner: Arc::new(Inner {
+                config,
+                endpoints,
+                best_head: AtomicU64::new(0),
+            }),
+        })
+    }
+
+    /// Wraps the transport in a provider usable by the chain clients
+    pub fn provider(&self) -> Arc<DynProvider> {
+        let client = RpcClient::new(self.clone(), false);
+        Arc::new(RootProvider::<Ethereum>::new(client).erased())
+    }
+
+    /// Returns the health of every endpoint, in configuration order
+    pub fn endpoint_status(&self) -> Vec<EndpointStatus> {
+        let now = Instant::now();
+        self.inner
+            .endpoints
+            .iter()
+            .map(|ep| {
+                let health = ep.health();
+                let head_lag = self.inner.head_lag(health.head);
+                let cooling = health.cooldown_until.is_some_and(|until| until > now);
+                EndpointStatus {
+                    url: ep.url.clone(),
+                    healthy: !cooling && head_lag <= self.inner.config.max_head_lag,
+                    latency_ms: health.latency_ms,
+                    latency: health.latency.clone(),
+                    successes: health.successes,
+                    failures: health.failures,
+                    consecutive_failures: health.consecutive_failures,
+                    head: health.head,
+                    head_lag,
+                }
+            })
+            .collect()
+    }
+
+    /// Queries `eth_blockNumber` on every endpoint concurrently and records the heads
+    ///
+    /// # Returns
+    /// The head of each endpoint in configuration order, `None` where the request failed
+    pub async fn refresh_heads(&self) -> Vec<Option<u64>> {
+        let mut set = JoinSet::new();
+        for index in 0..self.inner.endpoints.len() {
+            let inner = Arc::clone(&self.inner);
+            set.spawn(async move {
+                let endpoint = &inner.endpoints[index];
+                let client = RpcClient::new(endpoint.transport.clone(), false);
+                let start = Instant::now();
+                let head = match client.request_noparams::<U64>("eth_blockNumber").await {
+                    Ok(head) => {
+                        endpoint.record_success(start.elapsed());
+                        inner.record_head(index, head.to::<u64>());
+                        Some(head.to::<u64>())
+                    }
+                    Err(e) => {
+                        endpoint.record_failure(&inner.config, &e.to_string());
+                        None
+                    }
+                };
+                (index, head)
+            });
+        }
+        let mut heads = vec![None; self.inner.endpoints.len()];
+        while let Some(result) = set.join_next().await {
+            if let Ok((index, head)) = result {
+                heads[index] = head;
+            }
+        }
+        heads
+    }
+
+    /// Returns the highest block number that at least `quorum` endpoints have reached
+    ///
+    /// # Returns
+    /// An error if fewer than `quorum` endpoints answered
+    pub async fn quorum_block_number(&self) -> Result<u64> {
+        let mut heads: Vec<u64> = self.refresh_heads().await.into_iter().flatten().collect();
+        let quorum = self.inner.config.quorum;
+        if heads.len() < quorum {
+            bail!(
+                "Only {} of {} RPC endpoints reported a block number, quorum is {}",
+                heads.len(),
+                self.inner.endpoints.len(),
+                quorum
+            );
+        }
+        heads.sort_unstable_by(|a, b| b.cmp(a));
+        Ok(heads[quorum - 1])
+    }
+
+    /// Answers a single `eth_blockNumber` request with the quorum block number
+    async fn quorum_response(&self, req: &SerializedRequest) -> TransportResult<ResponsePacket> {
+        let number = self
+            .quorum_block_number()
+            .await
+            .map_err(|e| TransportErrorKind::custom_str(&e.to_string()))?;
+        let payload = serde_json::value::to_raw_value(&U64::from(number))
+            .map_err(TransportErrorKind::custom)?;
+        Ok(ResponsePacket::Single(Response {
+            id: req.id().clone(),
+            payload: ResponsePayload::Success(payload),
+        }))
+    }
+
+    /// Sends the request to the endpoints in rank order until one succeeds
+    async fn dispatch(self, req: RequestPacket) -> TransportResult<ResponsePacket> {
+        if let RequestPacket::Single(single) = &req {
+            if single.method() == "eth_blockNumber" && self.inner.config.quorum > 1 {
+                return self.quorum_response(single).await;
+            }
+        }
+
+        let mut last_error: Option<TransportError> = None;
+        let mut last_response: Option<ResponsePacket> = None;
+        for index in self.inner.ranked() {
+            let endpoint = &self.inner.endpoints[index];
+            let start = Instant::now();
+            match endpoint.transport.clone().call(req.clone()).await {
+                Ok(resp) => {
+                    if let Some(reason) = retryable_error(&resp) {
+                        endpoint.record_failure(&self.inner.config, &reason);
+                        last_response = Some(resp);
+                        continue;
+                    }
+                    endpoint.record_success(start.elapsed());
+                    if let Some(head) = block_number_response(&req, &resp) {
+                        self.inner.record_head(index, head);
+                    }
+                    return Ok(resp);
+                }
+                Err(e) => {
+                    endpoint.record_failure(&self.inner.config, &e.to_string());
+                    last_error = Some(e);
+                }
+            }
+        }
+
+        // Every endpoint failed: prefer a JSON-RPC error answer over a transport error
+        match (last_response, last_error) {
+            (Some(resp), _) => Ok(resp),
+            (None, Some(e)) => Err(e),
+            (None, None) => Err(TransportErrorKind::custom_str("No RPC endpoint configured")),
+        }
+    }
+}
+
+/// Returns the reason if a response carries an error another endpoint may not return
+///
+/// Reverts are deterministic and returned as they are; node errors such as rate
+/// limits or unknown blocks are retried on the next endpoint.
+fn retryable_error(resp: &ResponsePacket) -> Option<String> {
+    let ResponsePacket::Single(single) = resp else {
+        return None;
+    };
+    let ResponsePayload::Failure(err) = &single.payload else {
+        return None;
+    };
+    if err.code == 3 || err.message.contains("revert") {
+        return None;
+    }
+    Some(format!("JSON-RPC error {}: {}", err.code, err.message))
+}
+
+/// Extracts the head from a successful `eth_blockNumber` response
+fn block_number_response(req: &RequestPacket, resp: &ResponsePacket) -> Option<u64> {
+    let (RequestPacket::Single(single), ResponsePacket::Single(response)) = (req, resp) else {
+        return None;
+    };
+    if single.method() != "eth_blockNumber" {
+        return None;
+    }
+    let ResponsePayload::Success(raw) = &response.payload else {
+        return None;
+    };
+    serde_json::from_str::<U64>(raw.get())
+        .ok()
+        .map(|n| n.to::<u64>())
+}
+
+impl Service<RequestPacket> for FailoverTransport {
+    type Response = ResponsePacket;
+    type Error = TransportError;
+    type Future = TransportFut<'static>;
+
+    fn poll_ready(&mut self, _cx: &mut TaskContext<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, req: RequestPacket) -> Self::Future {
+        Box::pin(self.clone().dispatch(req))
+    }
+}
+
+impl std::fmt::Debug for FailoverTransport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let urls: Vec<&str> = self
+            .inner
+            .endpoints
+            .iter()
+            .map(|ep| ep.url.as_str())
+            .collect();
+        f.debug_struct("FailoverTransport")
+            .field("endpoints", &urls)
+            .field("quorum", &self.inner.config.quorum)
+            .finish()
+    }
+}
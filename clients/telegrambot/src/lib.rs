@@ -1,6 +1,6 @@
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use reqwest::Client;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
 const TELEGRAM_API_BASE: &str = "https://api.telegram.org";
 
@@ -17,6 +17,49 @@ struct SendMessageRequest {
     text: String,
 }
 
+#[derive(Serialize)]
+struct GetUpdatesRequest {
+    offset: i64,
+    timeout: u64,
+    allowed_updates: Vec<&'static str>,
+}
+
+#[derive(Deserialize)]
+struct GetUpdatesResponse {
+    ok: bool,
+    #[serde(default)]
+    result: Vec<Update>,
+    #[serde(default)]
+    description: Option<String>,
+}
+
+/// Incoming update returned by `getUpdates`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Update {
+    /// Identifier of the update; the next poll passes `update_id + 1` as offset
+    pub update_id: i64,
+    /// New message, if the update is one
+    #[serde(default)]
+    pub message: Option<Message>,
+}
+
+/// Message of an update.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Message {
+    /// Chat the message was sent in
+    pub chat: Chat,
+    /// Text of the message; `None` for stickers, photos, etc.
+    #[serde(default)]
+    pub text: Option<String>,
+}
+
+/// Chat of a message.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Chat {
+    /// Chat identifier, compared against the configured chat ID
+    pub id: i64,
+}
+
 impl TelegramBot {
     /// Creates a new `TelegramBot` with the given API key and chat ID.
     pub fn new(api_key: String, chat_id: String) -> Self {
@@ -42,4 +85,44 @@ impl TelegramBot {
             .error_for_status()?;
         Ok(())
     }
+
+    /// Chat ID the bot reports to.
+    pub fn chat_id(&self) -> &str {
+        &self.chat_id
+    }
+
+    /// Long-polls incoming messages.
+    ///
+    /// # Arguments
+    /// * `offset` - Identifier of the first update to return; earlier updates are confirmed
+    ///   and not returned again
+    /// * `timeout_secs` - Seconds Telegram holds the request open when no update is pending;
+    ///   `0` returns immediately
+    ///
+    /// # Returns
+    /// The pending message updates, oldest first
+    pub async fn get_updates(&self, offset: i64, timeout_secs: u64) -> Result<Vec<Update>> {
+        let url = format!("{}/bot{}/getUpdates", TELEGRAM_API_BASE, self.api_key);
+        let body = GetUpdatesRequest {
+            offset,
+            timeout: timeout_secs,
+            allowed_updates: vec!["message"],
+        };
+        let response: GetUpdatesResponse = self
+            .client
+            .post(&url)
+            .json(&body)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        if !response.ok {
+            return Err(anyhow!(
+                "getUpdates failed: {}",
+                response.description.unwrap_or_default()
+            ));
+        }
+        Ok(response.result)
+    }
 }
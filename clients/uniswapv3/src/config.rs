@@ -1,11 +1,171 @@
 //! Configuration types for Uniswap V3 clients.
 
-use alloy::primitives::Address;
+use alloy::primitives::{address, b256, Address, B256};
 use serde::{Deserialize, Serialize};
 
+/// Init code hash of Uniswap V3 pools
+const UNISWAP_V3_POOL_INIT_CODE_HASH: B256 =
+    b256!("e34f199b19b2b4f47f68442619d555527d244f78a3297ea89325f843f87b8b54");
+/// Init code hash of PancakeSwap V3 pools
+const PANCAKESWAP_V3_POOL_INIT_CODE_HASH: B256 =
+    b256!("6ce8eb472fa82df5469c6ab6d485f17c3ad13c8cd7af59b3d4a8026c5ce0f7e2");
+
 /// Configuration for UniswapV3PositionManager
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UniswapV3PositionManagerConfig {
     /// The contract address of the Uniswap V3 PositionManager contract
     pub address: Address,
+    /// The contract address of the SwapRouter used to swap between position tokens (optional)
+    #[serde(default)]
+    pub swap_router: Option<Address>,
+    /// Number of blocks behind the latest block that reads are pinned to (`latest - confirmations`)
+    #[serde(default)]
+    pub confirmations: u64,
+    /// AMM fork the PositionManager belongs to and its deployment addresses
+    #[serde(default)]
+    pub amm: AmmConfig,
+}
+
+/// Uniswap V3-compatible AMM a PositionManager belongs to
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AmmKind {
+    /// Uniswap V3: pools are deployed by the factory
+    #[default]
+    #[serde(rename = "uniswap_v3")]
+    UniswapV3,
+    /// PancakeSwap V3: pools are deployed by a separate PoolDeployer and positions can be
+    /// staked in MasterChefV3 for CAKE rewards
+    #[serde(rename = "pancakeswap_v3")]
+    PancakeSwapV3,
+    /// Algebra V1 (e.g. QuickSwap V3, THENA Fusion, Camelot V3): one pool per token pair
+    /// with a dynamic fee, price state in `globalState`
+    #[serde(rename = "algebra")]
+    Algebra,
+    /// Aerodrome / Velodrome Slipstream: pools are keyed by tick spacing and positions can
+    /// be staked in per-pool gauges for emissions
+    #[serde(rename = "slipstream")]
+    Slipstream,
+}
+
+impl AmmKind {
+    /// Returns true if the PositionManager and SwapRouter use the Uniswap V3 mint and swap ABI
+    pub fn uniswap_abi(&self) -> bool {
+        matches!(self, AmmKind::UniswapV3 | AmmKind::PancakeSwapV3)
+    }
+}
+
+/// Deployment of a Uniswap V3-compatible AMM
+///
+/// Unset addresses are read from the PositionManager. When the init code hash is known,
+/// pool addresses are derived locally with CREATE2 instead of calling `getPool`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AmmConfig {
+    /// AMM fork
+    #[serde(default)]
+    pub kind: AmmKind,
+    /// Factory address; `None` reads `factory()` from the PositionManager
+    #[serde(default)]
+    pub factory: Option<Address>,
+    /// Contract that deploys the pools via CREATE2; `None` uses the factory (Uniswap,
+    /// Slipstream) or reads it from the PositionManager (`deployer()` on PancakeSwap,
+    /// `poolDeployer()` on Algebra)
+    #[serde(default)]
+    pub pool_deployer: Option<Address>,
+    /// Keccak-256 hash of the pool init code (for Slipstream, of the EIP-1167 clone of the
+    /// pool implementation); `None` resolves pools through the factory
+    #[serde(default)]
+    pub init_code_hash: Option<B256>,
+    /// MasterChefV3 farm whose staked positions are synced together with the owner's wallet
+    #[serde(default)]
+    pub masterchef: Option<Address>,
+    /// Slipstream gauges whose staked positions are synced together with the owner's wallet
+    #[serde(default)]
+    pub gauges: Vec<Address>,
+}
+
+impl AmmConfig {
+    /// Uniswap V3 with the canonical pool init code hash; factory read from the PositionManager
+    pub fn uniswap_v3() -> Self {
+        Self {
+            kind: AmmKind::UniswapV3,
+            init_code_hash: Some(UNISWAP_V3_POOL_INIT_CODE_HASH),
+            ..Self::default()
+        }
+    }
+
+    /// PancakeSwap V3 on BNB Smart Chain, including the MasterChefV3 farm
+    pub fn pancakeswap_v3_bsc() -> Self {
+        Self {
+            kind: AmmKind::PancakeSwapV3,
+            factory: Some(address!("0BFbCF9fa4f9C56B0F40a671Ad40E0805A091865")),
+            pool_deployer: Some(address!("41ff9AA7e16B8B1a8a8dc4f0eFacd93D02d071c9")),
+            init_code_hash: Some(PANCAKESWAP_V3_POOL_INIT_CODE_HASH),
+            masterchef: Some(address!("556B9306565093C855AEA9AE92A594704c2Cd59e")),
+            gauges: Vec::new(),
+        }
+    }
+
+    /// Returns the known deployment a PositionManager belongs to
+    ///
+    /// # Arguments
+    /// * `chain_id` - Chain the PositionManager is deployed on
+    /// * `position_manager` - Address of the PositionManager
+    ///
+    /// # Returns
+    /// `None` if the PositionManager is not a known deployment
+    pub fn known(chain_id: u64, position_manager: Address) -> Option<Self> {
+        const UNISWAP_V3_ETHEREUM: Address = address!("C36442b4a4522E871399CD717aBDD847Ab11FE88");
+        const UNISWAP_V3_BSC: Address = address!("7b8A01B39D58278b5DE7e48c8449c9f4F5170613");
+        const PANCAKESWAP_V3: Address = address!("46A15B0b27311cedF172AB29E4f4766fbE7F4364");
+        match (chain_id, position_manager) {
+            (1, UNISWAP_V3_ETHEREUM) => Some(Self {
+                factory: Some(address!("1F98431c8aD98523631AE4a59f267346ea31F984")),
+                ..Self::uniswap_v3()
+            }),
+            (56, UNISWAP_V3_BSC) => Some(Self {
+                factory: Some(address!("dB1d10011AD0Ff90774D0C6Bb92e5C5c8b4461F7")),
+                ..Self::uniswap_v3()
+            }),
+            (56, PANCAKESWAP_V3) => Some(Self::pancakeswap_v3_bsc()),
+            _ => None,
+        }
+    }
+}
+
+/// Slippage and deadline settings for liquidity transactions
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LiquidityTxConfig {
+    /// Maximum accepted shortfall versus the simulated amounts, in basis points
+    pub slippage_bps: u32,
+    /// Seconds after the latest block timestamp before the transaction expires
+    pub deadline_secs: u64,
+}
+
+impl Default for LiquidityTxConfig {
+    fn default() -> Self {
+        Self {
+            slippage_bps: 50,
+            deadline_secs: 600,
+        }
+    }
+}
+
+/// Settings for event-driven position tracking
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PositionEventTrackerConfig {
+    /// Relative pool price move since the last trigger, in basis points, that requests a re-evaluation
+    pub price_move_bps: u32,
+    /// Maximum number of blocks covered by one `eth_getLogs` request
+    pub max_block_range: u64,
+}
+
+impl Default for PositionEventTrackerConfig {
+    fn default() -> Self {
+        Self {
+            price_move_bps: 50,
+            max_block_range: 2_000,
+        }
+    }
 }
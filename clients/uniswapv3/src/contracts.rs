@@ -7,8 +7,10 @@ sol! {
     // PositionManager contract interface
     #[sol(rpc)]
     interface IPositionManager {
+        function factory() external view returns (address);
         function balanceOf(address owner) external view returns (uint256);
         function tokenOfOwnerByIndex(address owner, uint256 index) external view returns (uint256);
+        function ownerOf(uint256 tokenId) external view returns (address);
         function positions(uint256 tokenId) external view returns (
             uint96 nonce,
             address operator,
@@ -25,6 +27,181 @@ sol! {
         );
         function decreaseLiquidity(DecreaseLiquidityParams calldata params) external returns (uint256 amount0, uint256 amount1);
         function collect(CollectParams calldata params) external returns (uint256 amount0, uint256 amount1);
+        function multicall(bytes[] calldata data) external payable returns (bytes[] memory results);
+        function burn(uint256 tokenId) external payable;
+        function mint(MintParams calldata params) external payable returns (uint256 tokenId, uint128 liquidity, uint256 amount0, uint256 amount1);
+        function increaseLiquidity(IncreaseLiquidityParams calldata params) external payable returns (uint128 liquidity, uint256 amount0, uint256 amount1);
+
+        event IncreaseLiquidity(uint256 indexed tokenId, uint128 liquidity, uint256 amount0, uint256 amount1);
+        event DecreaseLiquidity(uint256 indexed tokenId, uint128 liquidity, uint256 amount0, uint256 amount1);
+        event Collect(uint256 indexed tokenId, address recipient, uint256 amount0, uint256 amount1);
+        event Transfer(address indexed from, address indexed to, uint256 indexed tokenId);
+    }
+
+    // PancakeSwap V3 PositionManager: pools are deployed by a separate PoolDeployer
+    #[sol(rpc)]
+    interface IPancakePositionManager {
+        function deployer() external view returns (address);
+    }
+
+    // PancakeSwap MasterChefV3 farm holding staked position NFTs
+    //
+    // Staked positions are enumerated per user with the same ERC-721 style
+    // `balanceOf` / `tokenOfOwnerByIndex` calls as the PositionManager.
+    #[sol(rpc)]
+    interface IMasterChefV3 {
+        function CAKE() external view returns (address);
+        function balanceOf(address user) external view returns (uint256);
+        function tokenOfOwnerByIndex(address owner, uint256 index) external view returns (uint256);
+        function pendingCake(uint256 tokenId) external view returns (uint256 reward);
+    }
+
+    // Algebra (V1) NonfungiblePositionManager: positions have no fee tier, since each
+    // token pair has a single pool with a dynamic fee
+    #[sol(rpc)]
+    interface IAlgebraPositionManager {
+        function poolDeployer() external view returns (address);
+        function positions(uint256 tokenId) external view returns (
+            uint96 nonce,
+            address operator,
+            address token0,
+            address token1,
+            int24 tickLower,
+            int24 tickUpper,
+            uint128 liquidity,
+            uint256 feeGrowthInside0LastX128,
+            uint256 feeGrowthInside1LastX128,
+            uint128 tokensOwed0,
+            uint128 tokensOwed1
+        );
+
+        event IncreaseLiquidity(uint256 indexed tokenId, uint128 liquidity, uint128 actualLiquidity, uint256 amount0, uint256 amount1, address pool);
+    }
+
+    // Algebra factory: one pool per token pair
+    #[sol(rpc)]
+    interface IAlgebraFactory {
+        function poolByPair(address tokenA, address tokenB) external view returns (address pool);
+    }
+
+    // Algebra pool price state
+    //
+    // `globalState` returns more fields, which differ between Algebra versions and
+    // forks; only the leading price, tick and current fee are decoded.
+    #[sol(rpc)]
+    interface IAlgebraPool {
+        function globalState() external view returns (uint160 price, int24 tick, uint16 fee);
+    }
+
+    // Slipstream (Aerodrome / Velodrome CL) NonfungiblePositionManager: pools are keyed
+    // by tick spacing instead of fee tier
+    #[sol(rpc)]
+    interface ISlipstreamPositionManager {
+        function positions(uint256 tokenId) external view returns (
+            uint96 nonce,
+            address operator,
+            address token0,
+            address token1,
+            int24 tickSpacing,
+            int24 tickLower,
+            int24 tickUpper,
+            uint128 liquidity,
+            uint256 feeGrowthInside0LastX128,
+            uint256 feeGrowthInside1LastX128,
+            uint128 tokensOwed0,
+            uint128 tokensOwed1
+        );
+    }
+
+    // Slipstream pool factory
+    #[sol(rpc)]
+    interface ICLFactory {
+        function getPool(address tokenA, address tokenB, int24 tickSpacing) external view returns (address pool);
+    }
+
+    // Slipstream pool: `slot0` has no `feeProtocol`, the fee is read separately
+    #[sol(rpc)]
+    interface ICLPool {
+        function slot0() external view returns (
+            uint160 sqrtPriceX96,
+            int24 tick,
+            uint16 observationIndex,
+            uint16 observationCardinality,
+            uint16 observationCardinalityNext,
+            bool unlocked
+        );
+        function fee() external view returns (uint24);
+    }
+
+    // Slipstream gauge holding staked position NFTs and streaming emissions to them
+    #[sol(rpc)]
+    interface ICLGauge {
+        function rewardToken() external view returns (address);
+        function stakedValues(address depositor) external view returns (uint256[] memory);
+        function earned(address account, uint256 tokenId) external view returns (uint256);
+    }
+
+    // Factory contract interface (pool lookup)
+    #[sol(rpc)]
+    interface IUniswapV3Factory {
+        function getPool(address tokenA, address tokenB, uint24 fee) external view returns (address pool);
+    }
+
+    // Pool contract interface (price state)
+    //
+    // `feeProtocol` is declared as uint32 so the same binding decodes both
+    // Uniswap (uint8) and PancakeSwap (uint32) pools. Algebra and Slipstream pools
+    // emit `Swap` with the same signature.
+    #[sol(rpc)]
+    interface IUniswapV3Pool {
+        function slot0() external view returns (
+            uint160 sqrtPriceX96,
+            int24 tick,
+            uint16 observationIndex,
+            uint16 observationCardinality,
+            uint16 observationCardinalityNext,
+            uint32 feeProtocol,
+            bool unlocked
+        );
+        function tickSpacing() external view returns (int24);
+
+        event Swap(address indexed sender, address indexed recipient, int256 amount0, int256 amount1, uint160 sqrtPriceX96, uint128 liquidity, int24 tick);
+    }
+
+    // PancakeSwap V3 pools emit Swap with the protocol fees appended, which changes the event signature.
+    interface IPancakeV3Pool {
+        event Swap(address indexed sender, address indexed recipient, int256 amount0, int256 amount1, uint160 sqrtPriceX96, uint128 liquidity, int24 tick, uint128 protocolFeesToken0, uint128 protocolFeesToken1);
+    }
+
+    // ERC-20 balance and allowance interface
+    #[sol(rpc)]
+    interface IERC20 {
+        function balanceOf(address account) external view returns (uint256);
+        function allowance(address owner, address spender) external view returns (uint256);
+        function approve(address spender, uint256 amount) external returns (bool);
+
+        event Transfer(address indexed from, address indexed to, uint256 value);
+    }
+
+    // SwapRouter interface (single-hop exact input swaps)
+    #[sol(rpc)]
+    interface ISwapRouter {
+        function exactInputSingle(ExactInputSingleParams calldata params) external payable returns (uint256 amountOut);
+    }
+
+    // ERC-20 metadata interface
+    #[sol(rpc)]
+    interface IERC20Metadata {
+        function decimals() external view returns (uint8);
+        function symbol() external view returns (string);
+        function name() external view returns (string);
+    }
+
+    // Legacy ERC-20 metadata returning bytes32 (e.g. MKR)
+    #[sol(rpc)]
+    interface IERC20MetadataBytes32 {
+        function symbol() external view returns (bytes32);
+        function name() external view returns (bytes32);
     }
 
     struct DecreaseLiquidityParams {
@@ -35,6 +212,40 @@ sol! {
         uint256 deadline;
     }
 
+    struct MintParams {
+        address token0;
+        address token1;
+        uint24 fee;
+        int24 tickLower;
+        int24 tickUpper;
+        uint256 amount0Desired;
+        uint256 amount1Desired;
+        uint256 amount0Min;
+        uint256 amount1Min;
+        address recipient;
+        uint256 deadline;
+    }
+
+    struct IncreaseLiquidityParams {
+        uint256 tokenId;
+        uint256 amount0Desired;
+        uint256 amount1Desired;
+        uint256 amount0Min;
+        uint256 amount1Min;
+        uint256 deadline;
+    }
+
+    struct ExactInputSingleParams {
+        address tokenIn;
+        address tokenOut;
+        uint24 fee;
+        address recipient;
+        uint256 deadline;
+        uint256 amountIn;
+        uint256 amountOutMinimum;
+        uint160 sqrtPriceLimitX96;
+    }
+
     struct CollectParams {
         uint256 tokenId;
         address recipient;
@@ -0,0 +1,156 @@
+//! ERC-20 token metadata client with a per-token cache.
+
+use alloy::eips::BlockId;
+use alloy::primitives::{Address, FixedBytes};
+use alloy::providers::{DynProvider, MulticallItem, Provider};
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use crate::contracts::{IERC20Metadata, IERC20MetadataBytes32};
+
+/// Metadata of an ERC-20 token
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TokenMetadata {
+    /// Token contract address
+    pub address: Address,
+    /// Number of decimals used by token amounts
+    pub decimals: u8,
+    /// Token symbol (empty if the token does not expose one)
+    pub symbol: String,
+    /// Token name (empty if the token does not expose one)
+    pub name: String,
+}
+
+/// Erc20MetadataClient reads `decimals()`, `symbol()` and `name()` once per token and caches them
+pub struct Erc20MetadataClient {
+    /// Provider used for the metadata calls
+    provider: Arc<DynProvider>,
+    /// Cached metadata keyed by token address
+    tokens: BTreeMap<Address, TokenMetadata>,
+}
+
+impl Erc20MetadataClient {
+    /// Creates a new `Erc20MetadataClient` with an empty cache
+    ///
+    /// # Arguments
+    /// * `provider` - Provider instance for making RPC calls to the blockchain
+    pub fn new(provider: Arc<DynProvider>) -> Self {
+        Self {
+            provider,
+            tokens: BTreeMap::new(),
+        }
+    }
+
+    /// Returns the cached metadata of a token, if it was loaded before.
+    pub fn cached(&self, token: Address) -> Option<&TokenMetadata> {
+        self.tokens.get(&token)
+    }
+
+    /// Caches metadata that cannot be read from an ERC-20 contract, such as the native
+    /// currency of Uniswap V4 pools (address zero).
+    pub fn insert(&mut self, metadata: TokenMetadata) {
+        self.tokens.insert(metadata.address, metadata);
+    }
+
+    /// Returns the metadata of a token, reading it from the chain on first use.
+    pub async fn get(&mut self, token: Address) -> Result<TokenMetadata> {
+        self.load([token]).await?;
+        self.tokens
+            .get(&token)
+            .cloned()
+            .ok_or_else(|| anyhow!("metadata of token {:?} not loaded", token))
+    }
+
+    /// Loads the metadata of every token that is not cached yet, batched through Multicall3.
+    ///
+    /// `decimals()` is required; a token without it is an error. `symbol()` and `name()`
+    /// fall back to the legacy `bytes32` encoding and then to an empty string.
+    pub async fn load(&mut self, tokens: impl IntoIterator<Item = Address>) -> Result<()> {
+        let mut missing: Vec<Address> = tokens
+            .into_iter()
+            .filter(|token| !self.tokens.contains_key(token))
+            .collect();
+        missing.sort();
+        missing.dedup();
+        if missing.is_empty() {
+            return Ok(());
+        }
+
+        let provider = &self.provider;
+        let erc20 = |token: Address| IERC20Metadata::new(token, provider.clone());
+        let decimals = provider
+            .multicall()
+            .dynamic()
+            .extend_calls(missing.iter().map(|&t| erc20(t).decimals().into_call(true)))
+            .block(BlockId::latest())
+            .aggregate3()
+            .await?;
+        let symbols = provider
+            .multicall()
+            .dynamic()
+            .extend_calls(missing.iter().map(|&t| erc20(t).symbol().into_call(true)))
+            .block(BlockId::latest())
+            .aggregate3()
+            .await?;
+        let names = provider
+            .multicall()
+            .dynamic()
+            .extend_calls(missing.iter().map(|&t| erc20(t).name().into_call(true)))
+            .block(BlockId::latest())
+            .aggregate3()
+            .await?;
+
+        for (((token, decimals), symbol), name) in
+            missing.into_iter().zip(decimals).zip(symbols).zip(names)
+        {
+            let decimals =
+                decimals.map_err(|_| anyhow!("token {:?} does not expose decimals()", token))?;
+            let symbol = match symbol {
+                Ok(symbol) => symbol,
+                Err(_) => self.bytes32_symbol(token).await,
+            };
+            let name = match name {
+                Ok(name) => name,
+                Err(_) => self.bytes32_name(token).await,
+            };
+            self.tokens.insert(
+                token,
+                TokenMetadata {
+                    address: token,
+                    decimals,
+                    symbol,
+                    name,
+                },
+            );
+        }
+        Ok(())
+    }
+
+    /// Reads a legacy `bytes32` symbol, returning an empty string if unavailable.
+    async fn bytes32_symbol(&self, token: Address) -> String {
+        IERC20MetadataBytes32::new(token, self.provider.clone())
+            .symbol()
+            .call()
+            .await
+            .map(bytes32_to_string)
+            .unwrap_or_default()
+    }
+
+    /// Reads a legacy `bytes32` name, returning an empty string if unavailable.
+    async fn bytes32_name(&self, token: Address) -> String {
+        IERC20MetadataBytes32::new(token, self.provider.clone())
+            .name()
+            .call()
+            .await
+            .map(bytes32_to_string)
+            .unwrap_or_default()
+    }
+}
+
+/// Decodes a NUL-padded `bytes32` string.
+fn bytes32_to_string(value: FixedBytes<32>) -> String {
+    let end = value.iter().position(|&b| b == 0).unwrap_or(value.len());
+    String::from_utf8_lossy(&value[..end]).into_owned()
+}
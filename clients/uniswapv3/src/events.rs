@@ -0,0 +1,393 @@
+//! Event-driven tracking of LP positions through `eth_getLogs`.
+//!
+//! Instead of re-reading every position on a fixed interval, the tracker polls the
+//! PositionManager and pool logs since the last poll, applies them to the cached
+//! `PositionData` and reports whether the pool price moved enough to re-evaluate.
+
+use alloy::primitives::{Address, B256, U256};
+use alloy::providers::Provider;
+use alloy::rpc::types::{Filter, Log};
+use alloy::sol_types::SolEvent;
+use anyhow::{anyhow, Result};
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::config::PositionEventTrackerConfig;
+use crate::contracts::{IAlgebraPositionManager, IPancakeV3Pool, IPositionManager, IUniswapV3Pool};
+use crate::math::sqrt_price_x96_to_f64;
+use crate::position_manager::{SyncedBlock, UniswapV3PositionManager};
+
+/// Position or pool event observed by `PositionEventTracker`
+#[derive(Debug, Clone)]
+pub enum PositionEvent {
+    /// Liquidity was added to a tracked position
+    IncreaseLiquidity {
+        /// The position NFT token ID
+        token_id: U256,
+        /// Liquidity added
+        liquidity: u128,
+    },
+    /// Liquidity was removed from a tracked position
+    DecreaseLiquidity {
+        /// The position NFT token ID
+        token_id: U256,
+        /// Liquidity removed
+        liquidity: u128,
+    },
+    /// Tokens were collected from a tracked position
+    Collect {
+        /// The position NFT token ID
+        token_id: U256,
+        /// Amount of token0 collected
+        amount0: U256,
+        /// Amount of token1 collected
+        amount1: U256,
+    },
+    /// A position NFT was minted, burned or transferred to or from the owner
+    Transfer {
+        /// The position NFT token ID
+        token_id: U256,
+        /// Previous owner (zero address for a mint)
+        from: Address,
+        /// New owner (zero address for a burn)
+        to: Address,
+    },
+    /// A swap moved the price of a pool holding a tracked position
+    Swap {
+        /// Pool address
+        pool: Address,
+        /// Pool price after the swap
+        sqrt_price_x96: U256,
+        /// Pool tick after the swap
+        tick: i32,
+    },
+}
+
+/// Result of one `PositionEventTracker::poll`
+#[derive(Debug, Clone, Default)]
+pub struct EventPoll {
+    /// First block covered by the poll
+    pub from_block: u64,
+    /// Last block covered by the poll
+    pub to_block: u64,
+    /// Observed events in block and log order
+    pub events: Vec<PositionEvent>,
+    /// The price of at least one pool moved by `price_move_bps` or more since the last trigger
+    pub price_moved: bool,
+    /// Positions were added, removed or had their liquidity changed
+    pub positions_changed: bool,
+    /// The previously polled block was reorged out and the cache was re-synced
+    pub reorged: bool,
+}
+
+impl EventPoll {
+    /// Returns true if the strategy should re-evaluate before its next fixed interval
+    pub fn should_reevaluate(&self) -> bool {
+        self.price_moved || self.positions_changed || self.reorged
+    }
+}
+
+/// PositionEventTracker follows the owner's positions through logs and updates the
+/// position cache of a `UniswapV3PositionManager` incrementally
+pub struct PositionEventTracker {
+    /// Owner whose positions are tracked
+    owner: Address,
+    /// Trigger threshold and log range settings
+    config: PositionEventTrackerConfig,
+    /// Last block covered by a poll; `None` until the initial sync
+    last_block: Option<SyncedBlock>,
+    /// Pool price at the last trigger, keyed by pool address
+    reference_prices: BTreeMap<Address, U256>,
+}
+
+impl PositionEventTracker {
+    /// Creates a new `PositionEventTracker`
+    ///
+    /// # Arguments
+    /// * `owner` - The Ethereum address whose positions are tracked
+    /// * `config` - Trigger threshold and log range settings
+    pub fn new(owner: Address, config: PositionEventTrackerConfig) -> Self {
+        Self {
+            owner,
+            config,
+            last_block: None,
+            reference_prices: BTreeMap::new(),
+        }
+    }
+
+    /// Reads the logs since the last poll and applies them to the manager's cache
+    ///
+    /// The first poll runs a full `sync_lp` and starts following logs from the next block.
+    /// Logs are only read up to the manager's confirmed block (`latest - confirmations`).
+    /// If the last polled block was reorged out, the cache is re-synced with `sync_lp`.
+    /// Afterwards:
+    /// - `IncreaseLiquidity` / `DecreaseLiquidity` adjust the cached liquidity,
+    /// - `Swap` updates the cached pool price and tick,
+    /// - affected positions are re-simulated at the last polled block,
+    /// - any `Transfer` to or from the owner (mint, burn, transfer) triggers a full `sync_lp`.
+    ///
+    /// At most `max_block_range` blocks are read per poll; a lagging tracker catches up
+    /// over several polls.
+    ///
+    /// # Arguments
+    /// * `manager` - Client whose position cache is updated
+    pub async fn poll(&mut self, manager: &mut UniswapV3PositionManager) -> Result<EventPoll> {
+        let confirmed = manager.confirmed_block().await?;
+        let last_block = match self.last_block {
+            Some(last) if manager.is_canonical(&last).await? => last,
+            last => {
+                let synced = manager.sync_lp(self.owner).await?;
+                self.last_block = Some(synced);
+                self.reset_reference_prices(manager);
+                return Ok(EventPoll {
+                    from_block: synced.number,
+                    to_block: synced.number,
+                    positions_changed: true,
+                    reorged: last.is_some(),
+                    ..Default::default()
+                });
+            }
+        };
+        let from_block = last_block.number + 1;
+        if from_block > confirmed.number {
+            return Ok(EventPoll {
+                from_block,
+                to_block: last_block.number,
+                ..Default::default()
+            });
+        }
+        let to_block = confirmed
+            .number
+            .min(from_block + self.config.max_block_range.max(1) - 1);
+        let to = if to_block == confirmed.number {
+            confirmed
+        } else {
+            manager.block_at(to_block).await?
+        };
+
+        let token_ids: Vec<B256> = manager
+            .positions_of(self.owner)
+            .map(|pos| B256::from(pos.token_id))
+            .collect();
+        let pools: BTreeSet<Address> = manager
+            .positions_of(self.owner)
+            .map(|pos| pos.pool)
+            .collect();
+        let position_manager = *manager.position_manager.address();
+        let provider = manager.position_manager.provider().clone();
+        let range = |filter: Filter| filter.from_block(from_block).to_block(to_block);
+
+        let mut logs: Vec<Log> = Vec::new();
+        if !token_ids.is_empty() {
+            logs.extend(
+                provider
+                    .get_logs(&range(
+                        Filter::new()
+                            .address(position_manager)
+                            .event_signature(vec![
+                                IPositionManager::IncreaseLiquidity::SIGNATURE_HASH,
+                                IAlgebraPositionManager::IncreaseLiquidity::SIGNATURE_HASH,
+                                IPositionManager::DecreaseLiquidity::SIGNATURE_HASH,
+                                IPositionManager::Collect::SIGNATURE_HASH,
+                            ])
+                            .topic1(token_ids),
+                    ))
+                    .await?,
+            );
+        }
+        let owner_topic = self.owner.into_word();
+        for transfer in [
+            Filter::new().topic1(owner_topic),
+            Filter::new().topic2(owner_topic),
+        ] {
+            logs.extend(
+                provider
+                    .get_logs(&range(
+                        transfer
+                            .address(position_manager)
+                            .event_signature(IPositionManager::Transfer::SIGNATURE_HASH),
+                    ))
+                    .await?,
+            );
+        }
+        if !pools.is_empty() {
+            logs.extend(
+                provider
+                    .get_logs(&range(
+                        Filter::new()
+                            .address(pools.into_iter().collect::<Vec<_>>())
+                            .event_signature(vec![
+                                IUniswapV3Pool::Swap::SIGNATURE_HASH,
+                                IPancakeV3Pool::Swap::SIGNATURE_HASH,
+                            ]),
+                    ))
+                    .await?,
+            );
+        }
+        logs.sort_by_key(|log| (log.block_number, log.log_index));
+        logs.dedup_by_key(|log| (log.block_number, log.log_index));
+
+        let events: Vec<PositionEvent> = logs.iter().filter_map(decode_event).collect();
+        let mut liquidity_deltas: BTreeMap<U256, i128> = BTreeMap::new();
+        let mut prices: BTreeMap<Address, (U256, i32)> = BTreeMap::new();
+        let mut transferred = false;
+        for event in &events {
+            match *event {
+                PositionEvent::IncreaseLiquidity {
+                    token_id,
+                    liquidity,
+                } => *liquidity_deltas.entry(token_id).or_default() += liquidity as i128,
+                PositionEvent::DecreaseLiquidity {
+                    token_id,
+                    liquidity,
+                } => *liquidity_deltas.entry(token_id).or_default() -= liquidity as i128,
+                PositionEvent::Transfer { .. } => transferred = true,
+                PositionEvent::Swap {
+                    pool,
+                    sqrt_price_x96,
+                    tick,
+                } => {
+                    prices.insert(pool, (sqrt_price_x96, tick));
+                }
+                PositionEvent::Collect { .. } => {}
+            }
+        }
+
+        if transferred {
+            // New or removed NFTs need their pool and token metadata resolved: re-read everything
+            self.last_block = Some(manager.sync_lp(self.owner).await?);
+        } else {
+            // Collected positions only change their owed amounts; re-simulate them too
+            for event in &events {
+                if let PositionEvent::Collect { token_id, .. } = *event {
+                    liquidity_deltas.entry(token_id).or_default();
+                }
+            }
+            if !liquidity_deltas.is_empty() || !prices.is_empty() {
+                manager
+                    .apply_position_events(self.owner, to, &liquidity_deltas, &prices)
+                    .await?;
+            }
+            self.last_block = Some(to);
+        }
+
+        let price_moved = prices
+            .iter()
+            .any(|(&pool, &(sqrt_price_x96, _))| self.price_moved(pool, sqrt_price_x96));
+        if price_moved {
+            self.reset_reference_prices(manager);
+        }
+        let positions_changed = transferred || liquidity_deltas.values().any(|&delta| delta != 0);
+        Ok(EventPoll {
+            from_block,
+            to_block,
+            events,
+            price_moved,
+            positions_changed,
+            reorged: false,
+        })
+    }
+
+    /// Brings the manager's cache up to the confirmed block for a snapshot
+    ///
+    /// While the tracker follows the chain, the logs since the last poll are applied with
+    /// `poll`, so unchanged positions cost no RPC calls. A full `sync_lp` runs instead on the
+    /// first sync, after a reorg of the last polled block, and when the tracker fell more than
+    /// `max_block_range` blocks behind, since one poll could not close that gap.
+    ///
+    /// # Arguments
+    /// * `manager` - Client whose position cache is updated
+    ///
+    /// # Returns
+    /// The block the cached positions reflect
+    pub async fn sync(&mut self, manager: &mut UniswapV3PositionManager) -> Result<SyncedBlock> {
+        let confirmed = manager.confirmed_block().await?;
+        let behind = match self.last_block {
+            Some(last) => confirmed.number.saturating_sub(last.number),
+            None => u64::MAX,
+        };
+        if behind > self.config.max_block_range.max(1) {
+            let synced = manager.sync_lp(self.owner).await?;
+            self.last_block = Some(synced);
+            self.reset_reference_prices(manager);
+            return Ok(synced);
+        }
+        // `poll` re-syncs on its own if the last polled block was reorged out
+        self.poll(manager).await?;
+        self.last_block
+            .ok_or_else(|| anyhow!("Event tracker has no synced block after a poll"))
+    }
+
+    /// Returns true if `sqrt_price_x96` is at least `price_move_bps` away from the
+    /// reference price of `pool`
+    fn price_moved(&self, pool: Address, sqrt_price_x96: U256) -> bool {
+        let Some(&reference) = self.reference_prices.get(&pool) else {
+            return true;
+        };
+        let ratio = sqrt_price_x96_to_f64(sqrt_price_x96) / sqrt_price_x96_to_f64(reference);
+        (ratio * ratio - 1.0).abs() * 10_000.0 >= self.config.price_move_bps as f64
+    }
+
+    /// Takes the cached pool prices of the owner's positions as the new trigger reference
+    fn reset_reference_prices(&mut self, manager: &UniswapV3PositionManager) {
+        self.reference_prices = manager
+            .positions_of(self.owner)
+            .map(|pos| (pos.pool, pos.sqrt_price_x96))
+            .collect();
+    }
+}
+
+/// Decodes a PositionManager or pool log into a `PositionEvent`
+pub(crate) fn decode_event(log: &Log) -> Option<PositionEvent> {
+    if let Ok(event) = log.log_decode::<IPositionManager::IncreaseLiquidity>() {
+        let event = event.inner.data;
+        return Some(PositionEvent::IncreaseLiquidity {
+            token_id: event.tokenId,
+            liquidity: event.liquidity,
+        });
+    }
+    if let Ok(event) = log.log_decode::<IAlgebraPositionManager::IncreaseLiquidity>() {
+        let event = event.inner.data;
+        return Some(PositionEvent::IncreaseLiquidity {
+            token_id: event.tokenId,
+            liquidity: event.actualLiquidity,
+        });
+    }
+    if let Ok(event) = log.log_decode::<IPositionManager::DecreaseLiquidity>() {
+        let event = event.inner.data;
+        return Some(PositionEvent::DecreaseLiquidity {
+            token_id: event.tokenId,
+            liquidity: event.liquidity,
+        });
+    }
+    if let Ok(event) = log.log_decode::<IPositionManager::Collect>() {
+        let event = event.inner.data;
+        return Some(PositionEvent::Collect {
+            token_id: event.tokenId,
+            amount0: event.amount0,
+            amount1: event.amount1,
+        });
+    }
+    if let Ok(event) = log.log_decode::<IPositionManager::Transfer>() {
+        let event = event.inner.data;
+        return Some(PositionEvent::Transfer {
+            token_id: event.tokenId,
+            from: event.from,
+            to: event.to,
+        });
+    }
+    if let Ok(event) = log.log_decode::<IUniswapV3Pool::Swap>() {
+        return Some(PositionEvent::Swap {
+            pool: log.address(),
+            sqrt_price_x96: U256::from(event.inner.data.sqrtPriceX96),
+            tick: event.inner.data.tick.as_i32(),
+        });
+    }
+    if let Ok(event) = log.log_decode::<IPancakeV3Pool::Swap>() {
+        return Some(PositionEvent::Swap {
+            pool: log.address(),
+            sqrt_price_x96: U256::from(event.inner.data.sqrtPriceX96),
+            tick: event.inner.data.tick.as_i32(),
+        });
+    }
+    None
+}
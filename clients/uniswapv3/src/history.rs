@@ -0,0 +1,538 @@
+//! Historical reconstruction of a single LP position from its PositionManager events.
+//!
+//! Reading pool prices and position state at past blocks requires an archive node.
+
+use alloy::eips::BlockId;
+use alloy::primitives::{Address, B256, U256};
+use alloy::providers::{DynProvider, Provider};
+use alloy::rpc::types::{Filter, Log};
+use alloy::sol_types::{Revert, SolEvent};
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::contracts::{IAlgebraPositionManager, IPancakeV3Pool, IPositionManager, IUniswapV3Pool};
+use crate::erc20::TokenMetadata;
+use crate::events::{decode_event, PositionEvent};
+use crate::position_manager::{PoolState, UniswapV3PositionManager};
+
+/// Kind of a position history event
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum HistoryEventKind {
+    /// Liquidity added (`mint` or `increaseLiquidity`)
+    IncreaseLiquidity,
+    /// Liquidity removed; the amounts are credited to the position's owed tokens
+    DecreaseLiquidity,
+    /// Owed tokens (fees and removed liquidity) transferred out
+    Collect,
+}
+
+/// One PositionManager event of a position with the pool price at its block
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryEvent {
+    /// Block the event was emitted in
+    pub block_number: u64,
+    /// Timestamp of the block, in seconds since Unix epoch
+    pub timestamp: u64,
+    /// Event kind
+    pub kind: HistoryEventKind,
+    /// Liquidity added or removed (zero for `Collect`)
+    pub liquidity: u128,
+    /// Amount of token0 of the event
+    pub amount0: U256,
+    /// Amount of token1 of the event
+    pub amount1: U256,
+    /// Pool price at the end of the event's block
+    pub sqrt_price_x96: U256,
+}
+
+/// Replayed history of a position from its mint block
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PositionHistory {
+    /// The position NFT token ID
+    pub token_id: U256,
+    /// Metadata of token0
+    pub token0_metadata: TokenMetadata,
+    /// Metadata of token1
+    pub token1_metadata: TokenMetadata,
+    /// Pool fee in hundredths of a bip (the fee at `end_block` for dynamic-fee pools)
+    pub fee: u32,
+    /// Lower tick of the position range
+    pub tick_lower: i32,
+    /// Upper tick of the position range
+    pub tick_upper: i32,
+    /// Address of the pool
+    pub pool: Address,
+    /// Block the position was minted in
+    pub mint_block: u64,
+    /// Last block of the history (the burn block, or the latest block)
+    pub end_block: u64,
+    /// Timestamp of `mint_block`, in seconds since Unix epoch
+    pub start_timestamp: u64,
+    /// Timestamp of `end_block`, in seconds since Unix epoch
+    pub end_timestamp: u64,
+    /// Pool price at `end_block`
+    pub end_sqrt_price_x96: U256,
+    /// Whether the position NFT was burned
+    pub burned: bool,
+    /// Amount of token0 withdrawable at `end_block` (zero once burned)
+    pub withdrawable_amount0: U256,
+    /// Amount of token1 withdrawable at `end_block` (zero once burned)
+    pub withdrawable_amount1: U256,
+    /// Amount of token0 collectable at `end_block`
+    pub collectable_amount0: U256,
+    /// Amount of token1 collectable at `end_block`
+    pub collectable_amount1: U256,
+    /// Liquidity events in block and log order
+    pub events: Vec<HistoryEvent>,
+}
+
+/// Pool price after the last swap of a block
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct PoolPricePoint {
+    /// Block of the swap
+    pub block_number: u64,
+    /// Timestamp of the block, in seconds since Unix epoch (interpolated, see `pool_price_history`)
+    pub timestamp: u64,
+    /// Pool price after the swap
+    pub sqrt_price_x96: U256,
+    /// Pool tick after the swap
+    pub tick: i32,
+}
+
+impl UniswapV3PositionManager {
+    /// Replays a position's `IncreaseLiquidity`, `DecreaseLiquidity` and `Collect` events
+    /// from its mint block, with the pool price and timestamp at every event block
+    ///
+    /// Logs are read in windows of `max_block_range` blocks. When `mint_block` is not given
+    /// it is found by binary search over `positions(token_id)`, which requires the position
+    /// to still exist; pass the mint block for burned positions.
+    ///
+    /// # Arguments
+    /// * `token_id` - The position NFT token ID
+    /// * `mint_block` - Block the position was minted in, if known
+    /// * `max_block_range` - Maximum number of blocks covered by one `eth_getLogs` request
+    pub async fn position_history(
+        &mut self,
+        token_id: U256,
+        mint_block: Option<u64>,
+        max_block_range: u64,
+    ) -> Result<PositionHistory> {
+        let provider = self.position_manager.provider().clone();
+        let latest = self.get_block_number().await?;
+        let mint_block = match mint_block {
+            Some(block) => block,
+            None => self.find_mint_block(token_id, latest).await?,
+        };
+
+        // Token pair, fee and range never change, so read them at the mint block
+        let info = self
+            .position_info(token_id, BlockId::number(mint_block))
+            .await
+            .map_err(|e| {
+                anyhow!(
+                    "position {} not found at block {}: {}",
+                    token_id,
+                    mint_block,
+                    e
+                )
+            })?;
+        let pool = self
+            .resolve_pools(
+                &[(info.token0, info.token1, info.pool_param)],
+                BlockId::number(mint_block),
+            )
+            .await?
+            .pop()
+            .flatten()
+            .ok_or_else(|| anyhow!("pool of position {} not found", token_id))?;
+        let token0_metadata = self.tokens.get(info.token0).await?;
+        let token1_metadata = self.tokens.get(info.token1).await?;
+
+        // Replay the position's events up to the latest block or its burn
+        let position_manager = *self.position_manager.address();
+        let token_topic = B256::from(token_id);
+        let mut logs: Vec<Log> = Vec::new();
+        let mut burn_block = None;
+        let mut from = mint_block;
+        while from <= latest && burn_block.is_none() {
+            let to = latest.min(from + max_block_range.max(1) - 1);
+            logs.extend(
+                provider
+                    .get_logs(
+                        &Filter::new()
+                            .address(position_manager)
+                            .event_signature(vec![
+                                IPositionManager::IncreaseLiquidity::SIGNATURE_HASH,
+                                IAlgebraPositionManager::IncreaseLiquidity::SIGNATURE_HASH,
+                                IPositionManager::DecreaseLiquidity::SIGNATURE_HASH,
+                                IPositionManager::Collect::SIGNATURE_HASH,
+                            ])
+                            .topic1(token_topic)
+                            .from_block(from)
+                            .to_block(to),
+                    )
+                    .await?,
+            );
+            burn_block = provider
+                .get_logs(
+                    &Filter::new()
+                        .address(position_manager)
+                        .event_signature(IPositionManager::Transfer::SIGNATURE_HASH)
+                        .topic2(Address::ZERO.into_word())
+                        .topic3(token_topic)
+                        .from_block(from)
+                        .to_block(to),
+                )
+                .await?
+                .first()
+                .and_then(|log| log.block_number);
+            from = to + 1;
+        }
+        logs.sort_by_key(|log| (log.block_number, log.log_index));
+        let end_block = burn_block.unwrap_or(latest);
+
+        // Pool state and timestamp of every block with an event, plus the mint and end blocks
+        let mut blocks: BTreeMap<u64, (u64, PoolState)> = BTreeMap::new();
+        for block_number in logs
+            .iter()
+            .filter_map(|log| log.block_number)
+            .chain([mint_block, end_block])
+        {
+            if blocks.contains_key(&block_number) {
+                continue;
+            }
+            let timestamp = provider
+                .get_block(BlockId::number(block_number))
+                .await?
+                .ok_or_else(|| anyhow!("block {} not found", block_number))?
+                .header
+                .timestamp;
+            let state = self.pool_state(pool, BlockId::number(block_number)).await?;
+            blocks.insert(block_number, (timestamp, state));
+        }
+
+        let mut events = Vec::with_capacity(logs.len());
+        for log in &logs {
+            let block_number = log
+                .block_number
+                .ok_or_else(|| anyhow!("log without block number"))?;
+            let (timestamp, state) = blocks[&block_number];
+            let (kind, liquidity, amount0, amount1) = if let Ok(event) =
+                log.log_decode::<IPositionManager::IncreaseLiquidity>()
+            {
+                let event = event.inner.data;
+                (
+                    HistoryEventKind::IncreaseLiquidity,
+                    event.liquidity,
+                    event.amount0,
+                    event.amount1,
+                )
+            } else if let Ok(event) = log.log_decode::<IAlgebraPositionManager::IncreaseLiquidity>()
+            {
+                let event = event.inner.data;
+                (
+                    HistoryEventKind::IncreaseLiquidity,
+                    event.actualLiquidity,
+                    event.amount0,
+                    event.amount1,
+                )
+            } else if let Ok(event) = log.log_decode::<IPositionManager::DecreaseLiquidity>() {
+                let event = event.inner.data;
+                (
+                    HistoryEventKind::DecreaseLiquidity,
+                    event.liquidity,
+                    event.amount0,
+                    event.amount1,
+                )
+            } else if let Ok(event) = log.log_decode::<IPositionManager::Collect>() {
+                let event = event.inner.data;
+                (HistoryEventKind::Collect, 0, event.amount0, event.amount1)
+            } else {
+                continue;
+            };
+            events.push(HistoryEvent {
+                block_number,
+                timestamp,
+                kind,
+                liquidity,
+                amount0,
+                amount1,
+                sqrt_price_x96: state.sqrt_price_x96,
+            });
+        }
+
+        // Value what is still in the position at the end block
+        let mut end_state = (U256::ZERO, U256::ZERO, U256::ZERO, U256::ZERO);
+        if burn_block.is_none() {
+            let block_id = BlockId::number(end_block);
+            let owner = self
+                .position_manager
+                .ownerOf(token_id)
+                .block(block_id)
+                .call()
+                .await?;
+            let liquidity = self.position_info(token_id, block_id).await?.liquidity;
+            let simulation = self
+                .simulate(block_id, [(token_id, liquidity, owner)].into_iter())
+                .await?
+                .pop()
+                .ok_or_else(|| anyhow!("missing simulation of position {}", token_id))?;
+            if !simulation.sync_status.is_ok() {
+                return Err(anyhow!(
+                    "position {} could not be valued at block {}: {}",
+                    token_id,
+                    end_block,
+                    simulation.sync_status
+                ));
+            }
+            end_state = (
+                simulation.withdrawable_amount0,
+                simulation.withdrawable_amount1,
+                simulation.collectable_amount0,
+                simulation.collectable_amount1,
+            );
+        }
+
+        let (start_timestamp, _) = blocks[&mint_block];
+        let (end_timestamp, end_pool) = blocks[&end_block];
+        Ok(PositionHistory {
+            token_id,
+            token0_metadata,
+            token1_metadata,
+            fee: end_pool.fee.unwrap_or(info.pool_param),
+            tick_lower: info.tick_lower,
+            tick_upper: info.tick_upper,
+            pool,
+            mint_block,
+            end_block,
+            start_timestamp,
+            end_timestamp,
+            end_sqrt_price_x96: end_pool.sqrt_price_x96,
+            burned: burn_block.is_some(),
+            withdrawable_amount0: end_state.0,
+            withdrawable_amount1: end_state.1,
+            collectable_amount0: end_state.2,
+            collectable_amount1: end_state.3,
+            events,
+        })
+    }
+
+    /// Finds the first block at which `positions(token_id)` succeeds by binary search
+    ///
+    /// Only the PositionManager's invalid-token revert counts as "not minted yet"; any other
+    /// error is returned, since guessing would move the search to the wrong block.
+    async fn find_mint_block(&self, token_id: U256, latest: u64) -> Result<u64> {
+        let exists = |block: u64| async move {
+            match self.position_info(token_id, BlockId::number(block)).await {
+                Ok(_) => Ok(true),
+                Err(e) if is_nonexistent_token(&e) => Ok(false),
+                Err(e) => Err(e.context(format!(
+                    "failed to read position {} at block {}",
+                    token_id, block
+                ))),
+            }
+        };
+        if !exists(latest).await? {
+            return Err(anyhow!(
+                "position {} does not exist at the latest block; pass its mint block",
+                token_id
+            ));
+        }
+        let (mut low, mut high) = (0u64, latest);
+        while low < high {
+            let mid = low + (high - low) / 2;
+            if exists(mid).await? {
+                high = mid;
+            } else {
+                low = mid + 1;
+            }
+        }
+        Ok(low)
+    }
+}
+
+/// Revert reasons of `positions(token_id)` for a token that is not minted or already burned:
+/// Uniswap V3, PancakeSwap V3 and Algebra use `Invalid token ID`, Slipstream `ID`
+const NONEXISTENT_TOKEN_REASONS: [&str; 2] = ["Invalid token ID", "ID"];
+
+/// Returns true if a failed `positions(token_id)` call means the token did not exist at the
+/// called block: the PositionManager reverted with its invalid-token reason, or it was not
+/// deployed yet and returned no data
+fn is_nonexistent_token(error: &anyhow::Error) -> bool {
+    let Some(error) = error.downcast_ref::<alloy::contract::Error>() else {
+        return false;
+    };
+    match error {
+        alloy::contract::Error::ZeroData(..) => true,
+        alloy::contract::Error::TransportError(e) => {
+            let Some(resp) = e.as_error_resp() else {
+                return false;
+            };
+            // Some nodes return the reason only in the message
+            let reason = match resp.as_decoded_error::<Revert>() {
+                Some(revert) => revert.reason().to_string(),
+                None => match resp.message.strip_prefix("execution reverted: ") {
+                    Some(reason) => reason.to_string(),
+                    None => return false,
+                },
+            };
+            NONEXISTENT_TOKEN_REASONS.contains(&reason.as_str())
+        }
+        _ => false,
+    }
+}
+
+/// Reads the price path of a pool from its `Swap` events
+///
+/// Keeps the last swap of every block. Logs are read in windows of `max_block_range`
+/// blocks; the timestamps of each window's first and last block are read and the
+/// timestamps in between interpolated linearly, instead of one request per block.
+/// Uniswap V3 and PancakeSwap V3 pools are supported.
+///
+/// # Arguments
+/// * `provider` - RPC provider of the pool's chain
+/// * `pool` - Pool address
+/// * `from_block` - First block to read
+/// * `to_block` - Last block to read
+/// * `max_block_range` - Maximum number of blocks covered by one `eth_getLogs` request
+///
+/// # Returns
+/// One point per block with a swap, in block order
+pub async fn pool_price_history(
+    provider: &DynProvider,
+    pool: Address,
+    from_block: u64,
+    to_block: u64,
+    max_block_range: u64,
+) -> Result<Vec<PoolPricePoint>> {
+    let mut points: Vec<PoolPricePoint> = Vec::new();
+    let mut from = from_block;
+    while from <= to_block {
+        let to = to_block.min(from + max_block_range.max(1) - 1);
+        let mut logs = provider
+            .get_logs(
+                &Filter::new()
+                    .address(pool)
+                    .event_signature(vec![
+                        IUniswapV3Pool::Swap::SIGNATURE_HASH,
+                        IPancakeV3Pool::Swap::SIGNATURE_HASH,
+                    ])
+                    .from_block(from)
+                    .to_block(to),
+            )
+            .await?;
+        logs.sort_by_key(|log| (log.block_number, log.log_index));
+        if !logs.is_empty() {
+            let mut timestamps = [0u64; 2];
+            for (timestamp, block_number) in timestamps.iter_mut().zip([from, to]) {
+                *timestamp = provider
+                    .get_block(BlockId::number(block_number))
+                    .await?
+                    .ok_or_else(|| anyhow!("block {} not found", block_number))?
+                    .header
+                    .timestamp;
+            }
+            let seconds_per_block = if to > from {
+                timestamps[1].saturating_sub(timestamps[0]) as f64 / (to - from) as f64
+            } else {
+                0.0
+            };
+            for log in &logs {
+                let (
+                    Some(block_number),
+                    Some(PositionEvent::Swap {
+                        sqrt_price_x96,
+                        tick,
+                        ..
+                    }),
+                ) = (log.block_number, decode_event(log))
+                else {
+                    continue;
+                };
+                let point = PoolPricePoint {
+                    block_number,
+                    timestamp: timestamps[0]
+                        + ((block_number - from) as f64 * seconds_per_block).round() as u64,
+                    sqrt_price_x96,
+                    tick,
+                };
+                match points.last_mut() {
+                    Some(last) if last.block_number == block_number => *last = point,
+                    _ => points.push(point),
+                }
+            }
+        }
+        from = to + 1;
+    }
+    Ok(points)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy::rpc::json_rpc::ErrorPayload;
+    use alloy::sol_types::SolError;
+    use alloy::transports::{RpcError, TransportErrorKind};
+
+    fn revert(message: &str, reason: Option<&str>) -> anyhow::Error {
+        let data = reason.map(|reason| {
+            let data = alloy::primitives::Bytes::from(Revert::from(reason).abi_encode());
+            serde_json::value::to_raw_value(&data.to_string()).unwrap()
+        });
+        alloy::contract::Error::TransportError(RpcError::ErrorResp(ErrorPayload {
+            code: 3,
+            message: message.to_string().into(),
+            data,
+        }))
+        .into()
+    }
+
+    #[test]
+    fn invalid_token_reverts_mean_not_minted() {
+        assert!(is_nonexistent_token(&revert(
+            "execution reverted",
+            Some("Invalid token ID")
+        )));
+        assert!(is_nonexistent_token(&revert(
+            "execution reverted",
+            Some("ID")
+        )));
+        assert!(is_nonexistent_token(&revert(
+            "execution reverted: Invalid token ID",
+            None
+        )));
+    }
+
+    #[test]
+    fn missing_contract_means_not_minted() {
+        let error = alloy::contract::Error::ZeroData(
+            "positions".to_string(),
+            alloy::dyn_abi::Error::SolTypes(alloy::sol_types::Error::Overrun),
+        );
+        assert!(is_nonexistent_token(&error.into()));
+    }
+
+    #[test]
+    fn other_errors_are_not_treated_as_not_minted() {
+        assert!(!is_nonexistent_token(&revert(
+            "execution reverted",
+            Some("Not approved")
+        )));
+        assert!(!is_nonexistent_token(&revert("execution reverted", None)));
+        let rate_limited: anyhow::Error =
+            alloy::contract::Error::TransportError(RpcError::ErrorResp(ErrorPayload {
+                code: -32005,
+                message: "limit exceeded".into(),
+                data: None,
+            }))
+            .into();
+        assert!(!is_nonexistent_token(&rate_limited));
+        let timeout: anyhow::Error = alloy::contract::Error::TransportError(
+            TransportErrorKind::custom_str("request timed out"),
+        )
+        .into();
+        assert!(!is_nonexistent_token(&timeout));
+        assert!(!is_nonexistent_token(&anyhow!("Invalid token ID")));
+    }
+}
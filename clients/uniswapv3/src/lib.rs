@@ -1,6 +1,28 @@
 mod config;
 mod contracts;
+mod erc20;
+mod events;
+mod history;
+mod liquidity;
+pub mod liquidity_math;
+pub mod math;
 mod position_manager;
+mod source;
 
-pub use config::UniswapV3PositionManagerConfig;
-pub use position_manager::{PositionData, UniswapV3PositionManager};
+pub use config::{
+    AmmConfig, AmmKind, LiquidityTxConfig, PositionEventTrackerConfig,
+    UniswapV3PositionManagerConfig,
+};
+pub use erc20::{Erc20MetadataClient, TokenMetadata};
+pub use events::{EventPoll, PositionEvent, PositionEventTracker};
+pub use history::{
+    pool_price_history, HistoryEvent, HistoryEventKind, PoolPricePoint, PositionHistory,
+};
+pub use liquidity::{
+    CollectEvent, DecreaseLiquidityEvent, IncreaseLiquidityEvent, LiquidityReceipt, MintRequest,
+    SwapReceipt, SwapRequest, TxSummary,
+};
+pub use position_manager::{
+    BlockReorged, PositionData, SyncStatus, SyncedBlock, UniswapV3PositionManager,
+};
+pub use source::PositionSource;
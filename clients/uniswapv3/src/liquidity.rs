@@ -0,0 +1,644 @@
+//! On-chain liquidity management for Uniswap V3 positions.
+//!
+//! Every PositionManager write is sent as a single `multicall` transaction from the
+//! attached wallet, so several steps (e.g. decrease, collect and burn) settle atomically.
+//! ERC-20 approvals and SwapRouter swaps are separate transactions.
+
+use alloy::eips::BlockId;
+use alloy::network::{Ethereum, EthereumWallet, NetworkWallet};
+use alloy::primitives::aliases::{I24, U160, U24};
+use alloy::primitives::{Address, Bytes, B256, U256};
+use alloy::providers::{DynProvider, Provider, ProviderBuilder};
+use alloy::rpc::types::TransactionReceipt;
+use alloy::sol_types::SolCall;
+use anyhow::{anyhow, Result};
+use std::sync::Arc;
+
+use crate::config::LiquidityTxConfig;
+use crate::contracts::{
+    DecreaseLiquidityParams, ExactInputSingleParams, IPositionManager, ISwapRouter, IUniswapV3Pool,
+    IncreaseLiquidityParams, MintParams, IERC20,
+};
+use crate::position_manager::{collect_call, decrease_call, UniswapV3PositionManager};
+
+/// Transaction-sending side of `UniswapV3PositionManager`
+pub(crate) struct Signer {
+    /// Address of the wallet's default signer
+    pub(crate) address: Address,
+    /// PositionManager contract instance bound to the signing provider
+    pub(crate) position_manager: IPositionManager::IPositionManagerInstance<Arc<DynProvider>>,
+}
+
+/// Summary of a mined transaction
+#[derive(Debug, Clone)]
+pub struct TxSummary {
+    /// Transaction hash
+    pub tx_hash: B256,
+    /// Block the transaction was mined in
+    pub block_number: u64,
+    /// Gas used by the transaction
+    pub gas_used: u64,
+    /// Effective gas price paid, in wei
+    pub effective_gas_price: u128,
+}
+
+impl TxSummary {
+    fn from_receipt(receipt: &TransactionReceipt) -> Self {
+        Self {
+            tx_hash: receipt.transaction_hash,
+            block_number: receipt.block_number.unwrap_or_default(),
+            gas_used: receipt.gas_used,
+            effective_gas_price: receipt.effective_gas_price,
+        }
+    }
+
+    /// Returns the gas cost of the transaction in wei
+    pub fn gas_cost(&self) -> U256 {
+        U256::from(self.gas_used) * U256::from(self.effective_gas_price)
+    }
+}
+
+/// Decoded `DecreaseLiquidity` event
+#[derive(Debug, Clone)]
+pub struct DecreaseLiquidityEvent {
+    /// The position NFT token ID
+    pub token_id: U256,
+    /// Liquidity removed from the position
+    pub liquidity: u128,
+    /// Amount of token0 credited to the position
+    pub amount0: U256,
+    /// Amount of token1 credited to the position
+    pub amount1: U256,
+}
+
+/// Decoded `Collect` event
+#[derive(Debug, Clone)]
+pub struct CollectEvent {
+    /// The position NFT token ID
+    pub token_id: U256,
+    /// Address that received the tokens
+    pub recipient: Address,
+    /// Amount of token0 transferred
+    pub amount0: U256,
+    /// Amount of token1 transferred
+    pub amount1: U256,
+}
+
+/// Decoded `IncreaseLiquidity` event (emitted by both `mint` and `increaseLiquidity`)
+#[derive(Debug, Clone)]
+pub struct IncreaseLiquidityEvent {
+    /// The position NFT token ID
+    pub token_id: U256,
+    /// Liquidity added to the position
+    pub liquidity: u128,
+    /// Amount of token0 paid into the position
+    pub amount0: U256,
+    /// Amount of token1 paid into the position
+    pub amount1: U256,
+}
+
+/// Parameters of a new position to mint
+#[derive(Debug, Clone)]
+pub struct MintRequest {
+    /// Address of token0 of the pool (must sort below token1)
+    pub token0: Address,
+    /// Address of token1 of the pool
+    pub token1: Address,
+    /// Pool fee tier in hundredths of a bip
+    pub fee: u32,
+    /// Lower tick of the range, aligned to the pool tick spacing
+    pub tick_lower: i32,
+    /// Upper tick of the range, aligned to the pool tick spacing
+    pub tick_upper: i32,
+    /// Maximum amount of token0 to deposit
+    pub amount0_desired: U256,
+    /// Maximum amount of token1 to deposit
+    pub amount1_desired: U256,
+    /// Address that receives the position NFT
+    pub recipient: Address,
+}
+
+/// Parameters of a single-hop exact input swap
+#[derive(Debug, Clone)]
+pub struct SwapRequest {
+    /// Token sold
+    pub token_in: Address,
+    /// Token bought
+    pub token_out: Address,
+    /// Fee tier of the pool to swap through
+    pub fee: u32,
+    /// Exact amount of `token_in` to sell
+    pub amount_in: U256,
+}
+
+/// Result of a SwapRouter swap
+#[derive(Debug, Clone)]
+pub struct SwapReceipt {
+    /// Mined transaction summary
+    pub tx: TxSummary,
+    /// Amount of `token_in` sold
+    pub amount_in: U256,
+    /// Amount of `token_out` received by the signer
+    pub amount_out: U256,
+}
+
+/// Result of a liquidity transaction with the PositionManager events it emitted
+#[derive(Debug, Clone)]
+pub struct LiquidityReceipt {
+    /// Mined transaction summary
+    pub tx: TxSummary,
+    /// `IncreaseLiquidity` events, in log order
+    pub increased: Vec<IncreaseLiquidityEvent>,
+    /// `DecreaseLiquidity` events, in log order
+    pub decreased: Vec<DecreaseLiquidityEvent>,
+    /// `Collect` events, in log order
+    pub collected: Vec<CollectEvent>,
+    /// Token IDs burned by the transaction
+    pub burned: Vec<U256>,
+    /// Token IDs minted by the transaction
+    pub minted: Vec<U256>,
+}
+
+impl UniswapV3PositionManager {
+    /// Attaches a wallet so the client can send liquidity transactions
+    ///
+    /// # Arguments
+    /// * `wallet` - Wallet whose default signer must own (or be approved for) the positions
+    ///
+    /// # Returns
+    /// The client with a signing PositionManager instance on top of the existing provider
+    pub fn with_wallet(mut self, wallet: EthereumWallet) -> Self {
+        let address = NetworkWallet::<Ethereum>::default_signer_address(&wallet);
+        let provider = ProviderBuilder::new()
+            .wallet(wallet)
+            .connect_provider(self.position_manager.provider().clone())
+            .erased();
+        self.signer = Some(Signer {
+            address,
+            position_manager: IPositionManager::new(
+                *self.position_manager.address(),
+                Arc::new(provider),
+            ),
+        });
+        self
+    }
+
+    /// Returns an error if a cached position is staked in a farm, which holds the NFT and
+    /// rejects PositionManager writes from the staker
+    fn ensure_unstaked(&self, token_id: U256) -> Result<()> {
+        if let Some(farm) = self
+            .positions()
+            .get(&token_id)
+            .and_then(|position| position.staked_in)
+        {
+            return Err(anyhow!(
+                "position {} is staked in {:?}; unstake it before changing it",
+                token_id,
+                farm
+            ));
+        }
+        Ok(())
+    }
+
+    /// Returns an error unless the AMM uses the Uniswap V3 `mint` and SwapRouter ABI;
+    /// Algebra and Slipstream take different parameters
+    fn ensure_uniswap_abi(&self, action: &str) -> Result<()> {
+        let kind = self.amm().kind;
+        if !kind.uniswap_abi() {
+            return Err(anyhow!("{} is not supported for {:?} pools", action, kind));
+        }
+        Ok(())
+    }
+
+    /// Returns the address of the attached signer, if any
+    pub fn signer_address(&self) -> Option<Address> {
+        self.signer.as_ref().map(|signer| signer.address)
+    }
+
+    /// Removes `liquidity` from a position, crediting the tokens to the position's owed amounts
+    ///
+    /// The minimum amounts are derived from a simulation at the latest block minus
+    /// `config.slippage_bps`. The tokens stay in the position until `collect` is called.
+    ///
+    /// # Arguments
+    /// * `token_id` - The position NFT token ID
+    /// * `liquidity` - Amount of liquidity to remove
+    /// * `config` - Slippage and deadline settings
+    pub async fn decrease_liquidity(
+        &self,
+        token_id: U256,
+        liquidity: u128,
+        config: &LiquidityTxConfig,
+    ) -> Result<LiquidityReceipt> {
+        self.ensure_unstaked(token_id)?;
+        let call = self
+            .decrease_call_with_limits(token_id, liquidity, config)
+            .await?;
+        self.execute(vec![call.abi_encode().into()]).await
+    }
+
+    /// Collects all owed tokens (fees and decreased liquidity) of a position to `recipient`
+    ///
+    /// # Arguments
+    /// * `token_id` - The position NFT token ID
+    /// * `recipient` - Address that receives the tokens
+    pub async fn collect(&self, token_id: U256, recipient: Address) -> Result<LiquidityReceipt> {
+        self.ensure_unstaked(token_id)?;
+        self.execute(vec![collect_call(token_id, recipient).abi_encode().into()])
+            .await
+    }
+
+    /// Burns an empty position NFT (no liquidity and nothing owed)
+    ///
+    /// # Arguments
+    /// * `token_id` - The position NFT token ID
+    pub async fn burn(&self, token_id: U256) -> Result<LiquidityReceipt> {
+        self.ensure_unstaked(token_id)?;
+        let call = IPositionManager::burnCall { tokenId: token_id };
+        self.execute(vec![call.abi_encode().into()]).await
+    }
+
+    /// Unwinds a position in one transaction: removes all liquidity, collects all tokens
+    /// to `recipient` and burns the NFT
+    ///
+    /// # Arguments
+    /// * `token_id` - The position NFT token ID
+    /// * `recipient` - Address that receives the tokens
+    /// * `config` - Slippage and deadline settings for the liquidity removal
+    pub async fn close_position(
+        &self,
+        token_id: U256,
+        recipient: Address,
+        config: &LiquidityTxConfig,
+    ) -> Result<LiquidityReceipt> {
+        self.ensure_unstaked(token_id)?;
+        let liquidity = self
+            .position_info(token_id, BlockId::latest())
+            .await?
+            .liquidity;
+        let mut calls: Vec<Bytes> = Vec::with_capacity(3);
+        if liquidity > 0 {
+            let decrease = self
+                .decrease_call_with_limits(token_id, liquidity, config)
+                .await?;
+            calls.push(decrease.abi_encode().into());
+        }
+        calls.push(collect_call(token_id, recipient).abi_encode().into());
+        calls.push(
+            IPositionManager::burnCall { tokenId: token_id }
+                .abi_encode()
+                .into(),
+        );
+        self.execute(calls).await
+    }
+
+    /// Mints a new position, approving the PositionManager for both tokens first if needed
+    ///
+    /// The minimum amounts are derived from a simulation of the mint from the signer minus
+    /// `config.slippage_bps`. The new token ID is reported in `LiquidityReceipt::minted`.
+    ///
+    /// # Arguments
+    /// * `request` - Pool, range, desired amounts and NFT recipient
+    /// * `config` - Slippage and deadline settings
+    pub async fn mint(
+        &self,
+        request: &MintRequest,
+        config: &LiquidityTxConfig,
+    ) -> Result<LiquidityReceipt> {
+        self.ensure_uniswap_abi("mint")?;
+        let signer = self.signer()?;
+        let position_manager = *self.position_manager.address();
+        self.ensure_allowance(request.token0, position_manager, request.amount0_desired)
+            .await?;
+        self.ensure_allowance(request.token1, position_manager, request.amount1_desired)
+            .await?;
+
+        let mut params = MintParams {
+            token0: request.token0,
+            token1: request.token1,
+            fee: U24::from(request.fee),
+            tickLower: I24::try_from(request.tick_lower)?,
+            tickUpper: I24::try_from(request.tick_upper)?,
+            amount0Desired: request.amount0_desired,
+            amount1Desired: request.amount1_desired,
+            amount0Min: U256::ZERO,
+            amount1Min: U256::ZERO,
+            recipient: request.recipient,
+            deadline: U256::from(u64::MAX),
+        };
+        let expected = self
+            .position_manager
+            .call_builder(&IPositionManager::mintCall {
+                params: params.clone(),
+            })
+            .from(signer.address)
+            .call()
+            .await?;
+        params.amount0Min = apply_slippage(expected.amount0, config.slippage_bps);
+        params.amount1Min = apply_slippage(expected.amount1, config.slippage_bps);
+        params.deadline = self.deadline(config).await?;
+
+        let call = IPositionManager::mintCall { params };
+        self.execute(vec![call.abi_encode().into()]).await
+    }
+
+    /// Adds liquidity to an existing position, approving the PositionManager first if needed
+    ///
+    /// # Arguments
+    /// * `token_id` - The position NFT token ID
+    /// * `amount0_desired` - Maximum amount of token0 to deposit
+    /// * `amount1_desired` - Maximum amount of token1 to deposit
+    /// * `config` - Slippage and deadline settings
+    pub async fn increase_liquidity(
+        &self,
+        token_id: U256,
+        amount0_desired: U256,
+        amount1_desired: U256,
+        config: &LiquidityTxConfig,
+    ) -> Result<LiquidityReceipt> {
+        self.ensure_unstaked(token_id)?;
+        let signer = self.signer()?;
+        let position = self.position_info(token_id, BlockId::latest()).await?;
+        let position_manager = *self.position_manager.address();
+        self.ensure_allowance(position.token0, position_manager, amount0_desired)
+            .await?;
+        self.ensure_allowance(position.token1, position_manager, amount1_desired)
+            .await?;
+
+        let mut params = IncreaseLiquidityParams {
+            tokenId: token_id,
+            amount0Desired: amount0_desired,
+            amount1Desired: amount1_desired,
+            amount0Min: U256::ZERO,
+            amount1Min: U256::ZERO,
+            deadline: U256::from(u64::MAX),
+        };
+        let expected = self
+            .position_manager
+            .call_builder(&IPositionManager::increaseLiquidityCall {
+                params: params.clone(),
+            })
+            .from(signer.address)
+            .call()
+            .await?;
+        params.amount0Min = apply_slippage(expected.amount0, config.slippage_bps);
+        params.amount1Min = apply_slippage(expected.amount1, config.slippage_bps);
+        params.deadline = self.deadline(config).await?;
+
+        let call = IPositionManager::increaseLiquidityCall { params };
+        self.execute(vec![call.abi_encode().into()]).await
+    }
+
+    /// Swaps an exact amount of one token for another through the configured SwapRouter
+    ///
+    /// The router is approved for `amount_in` if needed. The minimum output is derived from
+    /// a simulation of the swap minus `config.slippage_bps`.
+    ///
+    /// # Arguments
+    /// * `request` - Tokens, fee tier and input amount
+    /// * `config` - Slippage and deadline settings
+    pub async fn swap_exact_input_single(
+        &self,
+        request: &SwapRequest,
+        config: &LiquidityTxConfig,
+    ) -> Result<SwapReceipt> {
+        self.ensure_uniswap_abi("swap_exact_input_single")?;
+        let signer = self.signer()?;
+        let router = self
+            .swap_router
+            .ok_or_else(|| anyhow!("no swap router configured"))?;
+        self.ensure_allowance(request.token_in, router, request.amount_in)
+            .await?;
+
+        let mut params = ExactInputSingleParams {
+            tokenIn: request.token_in,
+            tokenOut: request.token_out,
+            fee: U24::from(request.fee),
+            recipient: signer.address,
+            deadline: U256::from(u64::MAX),
+            amountIn: request.amount_in,
+            amountOutMinimum: U256::ZERO,
+            sqrtPriceLimitX96: U160::ZERO,
+        };
+        let expected = ISwapRouter::new(router, self.position_manager.provider().clone())
+            .exactInputSingle(params.clone())
+            .from(signer.address)
+            .call()
+            .await?;
+        params.amountOutMinimum = apply_slippage(expected, config.slippage_bps);
+        params.deadline = self.deadline(config).await?;
+
+        let receipt = ISwapRouter::new(router, signer.position_manager.provider().clone())
+            .exactInputSingle(params)
+            .send()
+            .await?
+            .get_receipt()
+            .await?;
+        if !receipt.status() {
+            return Err(anyhow!(
+                "SwapRouter transaction {} reverted",
+                receipt.transaction_hash
+            ));
+        }
+
+        let amount_out = receipt
+            .logs()
+            .iter()
+            .filter(|log| log.address() == request.token_out)
+            .filter_map(|log| log.log_decode::<IERC20::Transfer>().ok())
+            .filter(|event| event.inner.data.to == signer.address)
+            .fold(U256::ZERO, |sum, event| sum + event.inner.data.value);
+        Ok(SwapReceipt {
+            tx: TxSummary::from_receipt(&receipt),
+            amount_in: request.amount_in,
+            amount_out,
+        })
+    }
+
+    /// Approves `spender` for `amount` of `token` if the current allowance is lower
+    ///
+    /// A non-zero allowance is reset to zero first, as required by tokens such as USDT.
+    ///
+    /// # Returns
+    /// The approval transaction, or `None` if the allowance already covers `amount`
+    pub async fn ensure_allowance(
+        &self,
+        token: Address,
+        spender: Address,
+        amount: U256,
+    ) -> Result<Option<TxSummary>> {
+        let signer = self.signer()?;
+        let erc20 = IERC20::new(token, signer.position_manager.provider().clone());
+        let allowance = erc20.allowance(signer.address, spender).call().await?;
+        if allowance >= amount {
+            return Ok(None);
+        }
+
+        if !allowance.is_zero() {
+            let receipt = erc20
+                .approve(spender, U256::ZERO)
+                .send()
+                .await?
+                .get_receipt()
+                .await?;
+            if !receipt.status() {
+                return Err(anyhow!(
+                    "approval reset of token {:?} reverted in {}",
+                    token,
+                    receipt.transaction_hash
+                ));
+            }
+        }
+        let receipt = erc20
+            .approve(spender, amount)
+            .send()
+            .await?
+            .get_receipt()
+            .await?;
+        if !receipt.status() {
+            return Err(anyhow!(
+                "approval of token {:?} reverted in {}",
+                token,
+                receipt.transaction_hash
+            ));
+        }
+        println!(
+            "Approved {:?} to spend {} of token {:?}",
+            spender, amount, token
+        );
+        Ok(Some(TxSummary::from_receipt(&receipt)))
+    }
+
+    /// Returns the ERC-20 balance of `owner` at the latest block
+    pub async fn token_balance(&self, token: Address, owner: Address) -> Result<U256> {
+        Ok(IERC20::new(token, self.position_manager.provider().clone())
+            .balanceOf(owner)
+            .call()
+            .await?)
+    }
+
+    /// Returns the tick spacing of a pool
+    pub async fn tick_spacing(&self, pool: Address) -> Result<i32> {
+        let spacing = IUniswapV3Pool::new(pool, self.position_manager.provider().clone())
+            .tickSpacing()
+            .call()
+            .await?;
+        Ok(spacing.as_i32())
+    }
+
+    /// Returns the attached signer or an error if no wallet was attached
+    pub(crate) fn signer(&self) -> Result<&Signer> {
+        self.signer
+            .as_ref()
+            .ok_or_else(|| anyhow!("no wallet attached to UniswapV3PositionManager"))
+    }
+
+    /// Returns a deadline `config.deadline_secs` after the latest block timestamp
+    pub(crate) async fn deadline(&self, config: &LiquidityTxConfig) -> Result<U256> {
+        let block = self
+            .position_manager
+            .provider()
+            .get_block(BlockId::latest())
+            .await?
+            .ok_or_else(|| anyhow!("failed to get latest block"))?;
+        Ok(U256::from(block.header.timestamp + config.deadline_secs))
+    }
+
+    /// Builds a `decreaseLiquidity` call with minimum amounts from a simulation and a deadline
+    async fn decrease_call_with_limits(
+        &self,
+        token_id: U256,
+        liquidity: u128,
+        config: &LiquidityTxConfig,
+    ) -> Result<IPositionManager::decreaseLiquidityCall> {
+        let signer = self.signer()?;
+        let expected = self
+            .position_manager
+            .call_builder(&decrease_call(token_id, liquidity))
+            .from(signer.address)
+            .call()
+            .await?;
+        Ok(IPositionManager::decreaseLiquidityCall {
+            params: DecreaseLiquidityParams {
+                tokenId: token_id,
+                liquidity,
+                amount0Min: apply_slippage(expected.amount0, config.slippage_bps),
+                amount1Min: apply_slippage(expected.amount1, config.slippage_bps),
+                deadline: self.deadline(config).await?,
+            },
+        })
+    }
+
+    /// Sends the encoded calls as one PositionManager `multicall`, waits for the receipt and
+    /// decodes the PositionManager events
+    pub(crate) async fn execute(&self, calls: Vec<Bytes>) -> Result<LiquidityReceipt> {
+        let signer = self.signer()?;
+        let receipt = signer
+            .position_manager
+            .multicall(calls)
+            .send()
+            .await?
+            .get_receipt()
+            .await?;
+        if !receipt.status() {
+            return Err(anyhow!(
+                "PositionManager transaction {} reverted",
+                receipt.transaction_hash
+            ));
+        }
+
+        let position_manager = *self.position_manager.address();
+        let mut result = LiquidityReceipt {
+            tx: TxSummary::from_receipt(&receipt),
+            increased: Vec::new(),
+            decreased: Vec::new(),
+            collected: Vec::new(),
+            burned: Vec::new(),
+            minted: Vec::new(),
+        };
+        for log in receipt
+            .logs()
+            .iter()
+            .filter(|log| log.address() == position_manager)
+        {
+            if let Ok(event) = log.log_decode::<IPositionManager::IncreaseLiquidity>() {
+                let event = event.inner.data;
+                result.increased.push(IncreaseLiquidityEvent {
+                    token_id: event.tokenId,
+                    liquidity: event.liquidity,
+                    amount0: event.amount0,
+                    amount1: event.amount1,
+                });
+            } else if let Ok(event) = log.log_decode::<IPositionManager::DecreaseLiquidity>() {
+                let event = event.inner.data;
+                result.decreased.push(DecreaseLiquidityEvent {
+                    token_id: event.tokenId,
+                    liquidity: event.liquidity,
+                    amount0: event.amount0,
+                    amount1: event.amount1,
+                });
+            } else if let Ok(event) = log.log_decode::<IPositionManager::Collect>() {
+                let event = event.inner.data;
+                result.collected.push(CollectEvent {
+                    token_id: event.tokenId,
+                    recipient: event.recipient,
+                    amount0: event.amount0,
+                    amount1: event.amount1,
+                });
+            } else if let Ok(event) = log.log_decode::<IPositionManager::Transfer>() {
+                if event.inner.data.to == Address::ZERO {
+                    result.burned.push(event.inner.data.tokenId);
+                } else if event.inner.data.from == Address::ZERO {
+                    result.minted.push(event.inner.data.tokenId);
+                }
+            }
+        }
+        Ok(result)
+    }
+}
+
+/// Reduces an expected amount by `slippage_bps` basis points
+pub(crate) fn apply_slippage(amount: U256, slippage_bps: u32) -> U256 {
+    let bps = U256::from(10_000u32.saturating_sub(slippage_bps));
+    amount * bps / U256::from(10_000u32)
+}
@@ -0,0 +1,318 @@
+//! Exact integer versions of the on-chain `TickMath`, `SqrtPriceMath` and fee
+//! accounting formulas.
+//!
+//! Used where amounts cannot be read by simulating a transaction (e.g. Uniswap V4
+//! positions, whose PositionManager has no per-call `decreaseLiquidity`). Results
+//! match the amounts a full withdrawal returns, rounded down.
+
+use alloy::primitives::{I256, U256, U512};
+
+use crate::math::{MAX_TICK, MIN_TICK};
+
+/// `2^96`, the fixed point scale of sqrt prices
+const Q96: U256 = U256::from_limbs([0, 1 << 32, 0, 0]);
+
+/// Sqrt price at `MIN_TICK`, the lowest `sqrt_ratio_at_tick` returns
+pub const MIN_SQRT_RATIO: U256 = U256::from_limbs([4_295_128_739, 0, 0, 0]);
+
+/// Sqrt price at `MAX_TICK`, the highest `sqrt_ratio_at_tick` returns
+pub const MAX_SQRT_RATIO: U256 =
+    U256::from_limbs([0x5d951d5263988d26, 0xefd1fc6a50648849, 0xfffd8963, 0]);
+
+/// Multipliers of `TickMath.getSqrtRatioAtTick` for bits 1..=19 of the absolute tick
+const TICK_RATIOS: [u128; 19] = [
+    0xfff97272373d413259a46990580e213a,
+    0xfff2e50f5f656932ef12357cf3c7fdcc,
+    0xffe5caca7e10e4e61c3624eaa0941cd0,
+    0xffcb9843d60f6159c9db58835c926644,
+    0xff973b41fa98c081472e6896dfb254c0,
+    0xff2ea16466c96a3843ec78b326b52861,
+    0xfe5dee046a99a2a811c461f1969c3053,
+    0xfcbe86c7900a88aedcffc83b479aa3a4,
+    0xf987a7253ac413176f2b074cf7815e54,
+    0xf3392b0822b70005940c7a398e4b70f3,
+    0xe7159475a2c29b7443b29c7fa6e889d9,
+    0xd097f3bdfd2022b8845ad8f792aa5825,
+    0xa9f746462d870fdf8a65dc1f90e061e5,
+    0x70d869a156d2a1b890bb3df62baf32f7,
+    0x31be135f97d08fd981231505542fcfa6,
+    0x9aa508b5b7a84e1c677de54f3e99bc9,
+    0x5d6af8dedb81196699c329225ee604,
+    0x2216e584f5fa1ea926041bedfe98,
+    0x48a170391f7dc42444e8fa2,
+];
+
+/// Returns `floor(a * b / denominator)` computed with a 512-bit intermediate product,
+/// saturating at `U256::MAX`.
+pub fn mul_div(a: U256, b: U256, denominator: U256) -> U256 {
+    if denominator.is_zero() {
+        return U256::ZERO;
+    }
+    let product: U512 = a.widening_mul(b);
+    let quotient = product / U512::from(denominator);
+    U256::checked_from_limbs_slice(quotient.as_limbs()).unwrap_or(U256::MAX)
+}
+
+/// Returns the Q64.96 sqrt price at `tick` exactly as `TickMath.getSqrtRatioAtTick`.
+///
+/// Ticks outside `[MIN_TICK, MAX_TICK]` are clamped.
+pub fn sqrt_ratio_at_tick(tick: i32) -> U256 {
+    let tick = tick.clamp(MIN_TICK, MAX_TICK);
+    let abs_tick = tick.unsigned_abs();
+    let mut ratio = if abs_tick & 1 != 0 {
+        U256::from(0xfffcb933bd6fad37aa2d162d1a594001u128)
+    } else {
+        U256::from(1) << 128
+    };
+    for (bit, multiplier) in TICK_RATIOS.iter().enumerate() {
+        if abs_tick & (2 << bit) != 0 {
+            ratio = (ratio * U256::from(*multiplier)) >> 128;
+        }
+    }
+    if tick > 0 {
+        ratio = U256::MAX / ratio;
+    }
+    // Round up from Q128.128 to Q64.96
+    let remainder = ratio & U256::from(u32::MAX);
+    (ratio >> 32) + U256::from(!remainder.is_zero() as u8)
+}
+
+/// Returns the greatest tick whose sqrt price is at most `sqrt_price_x96`, exactly as
+/// `TickMath.getTickAtSqrtRatio`.
+///
+/// Prices outside `[MIN_SQRT_RATIO, MAX_SQRT_RATIO)` are clamped.
+pub fn tick_at_sqrt_ratio(sqrt_price_x96: U256) -> i32 {
+    let sqrt_price_x96 = sqrt_price_x96.clamp(MIN_SQRT_RATIO, MAX_SQRT_RATIO - U256::from(1));
+    let ratio: U256 = sqrt_price_x96 << 32;
+
+    // Integer part of log2(ratio) in Q128.128, then 14 fractional bits by repeated squaring
+    let msb = 255 - ratio.leading_zeros();
+    let mut r: U256 = if msb >= 128 {
+        ratio >> (msb - 127)
+    } else {
+        ratio << (127 - msb)
+    };
+    let mut log_2: I256 = I256::try_from(msb as i64 - 128).unwrap_or_default() << 64;
+    for shift in (50..64).rev() {
+        r = (r * r) >> 127;
+        if r.bit(128) {
+            log_2 += I256::ONE << shift;
+            r >>= 1;
+        }
+    }
+
+    // log_sqrt(1.0001)(ratio) in Q128.128, with the error bounds of the on-chain library
+    let log_sqrt10001: I256 =
+        log_2 * I256::from_raw(U256::from(255_738_958_999_603_826_347_141u128));
+    let tick_low = (log_sqrt10001
+        - I256::from_raw(U256::from(
+            3_402_992_956_809_132_418_596_140_100_660_247_210u128,
+        )))
+    .asr(128)
+    .low_i32();
+    let tick_high = (log_sqrt10001
+        + I256::from_raw(U256::from(
+            291_339_464_771_989_622_907_027_621_153_398_088_495u128,
+        )))
+    .asr(128)
+    .low_i32();
+    if tick_low == tick_high || sqrt_ratio_at_tick(tick_high) > sqrt_price_x96 {
+        tick_low
+    } else {
+        tick_high
+    }
+}
+
+/// Token0 amount between two sqrt prices for `liquidity`, rounded down.
+fn amount0_delta(sqrt_a: U256, sqrt_b: U256, liquidity: u128) -> U256 {
+    let (lower, upper) = if sqrt_a <= sqrt_b {
+        (sqrt_a, sqrt_b)
+    } else {
+        (sqrt_b, sqrt_a)
+    };
+    if lower.is_zero() {
+        return U256::ZERO;
+    }
+    mul_div(U256::from(liquidity) << 96, upper - lower, upper) / lower
+}
+
+/// Token1 amount between two sqrt prices for `liquidity`, rounded down.
+fn amount1_delta(sqrt_a: U256, sqrt_b: U256, liquidity: u128) -> U256 {
+    let (lower, upper) = if sqrt_a <= sqrt_b {
+        (sqrt_a, sqrt_b)
+    } else {
+        (sqrt_b, sqrt_a)
+    };
+    mul_div(U256::from(liquidity), upper - lower, Q96)
+}
+
+/// Computes the raw token amounts a full withdrawal of `liquidity` in
+/// `[tick_lower, tick_upper)` returns at `sqrt_price_x96`.
+///
+/// # Returns
+/// `(amount0, amount1)` in raw token units, rounded down
+pub fn amounts_for_liquidity(
+    sqrt_price_x96: U256,
+    tick_lower: i32,
+    tick_upper: i32,
+    liquidity: u128,
+) -> (U256, U256) {
+    let sqrt_lower = sqrt_ratio_at_tick(tick_lower.min(tick_upper));
+    let sqrt_upper = sqrt_ratio_at_tick(tick_lower.max(tick_upper));
+    if sqrt_price_x96 <= sqrt_lower {
+        (amount0_delta(sqrt_lower, sqrt_upper, liquidity), U256::ZERO)
+    } else if sqrt_price_x96 < sqrt_upper {
+        (
+            amount0_delta(sqrt_price_x96, sqrt_upper, liquidity),
+            amount1_delta(sqrt_lower, sqrt_price_x96, liquidity),
+        )
+    } else {
+        (U256::ZERO, amount1_delta(sqrt_lower, sqrt_upper, liquidity))
+    }
+}
+
+/// Computes the fees owed to a position from the fee growth inside its range
+///
+/// Fee growth counters are allowed to overflow on-chain, so the difference wraps.
+///
+/// # Arguments
+/// * `fee_growth_inside_x128` - Current fee growth inside the range (Q128.128 per unit of liquidity)
+/// * `fee_growth_inside_last_x128` - Fee growth inside the range at the position's last update
+/// * `liquidity` - Position liquidity
+pub fn fees_owed(
+    fee_growth_inside_x128: U256,
+    fee_growth_inside_last_x128: U256,
+    liquidity: u128,
+) -> U256 {
+    mul_div(
+        fee_growth_inside_x128.wrapping_sub(fee_growth_inside_last_x128),
+        U256::from(liquidity),
+        U256::from(1) << 128,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `TickMath.getSqrtRatioAtTick` outputs of the Uniswap V3 core test suite
+    const SQRT_RATIOS: [(i32, &str); 11] = [
+        (-50, "79030349367926598376800521322"),
+        (50, "79426470787362580746886972461"),
+        (100, "79625275426524748796330556128"),
+        (250, "80224679980005306637834519095"),
+        (500, "81233731461783161732293370115"),
+        (1000, "83290069058676223003182343270"),
+        (50_000, "965075977353221155028623082916"),
+        (150_000, "143194173941309278083010301478497"),
+        (250_000, "21246587762933397357449903968194344"),
+        (500_000, "5697689776495288729098254600827762987878"),
+        (738_203, "847134979253254120489401328389043031315994541"),
+    ];
+
+    fn sqrt_ratio(tick: i32) -> U256 {
+        let (_, value) = SQRT_RATIOS.iter().find(|(t, _)| *t == tick).unwrap();
+        value.parse().unwrap()
+    }
+
+    #[test]
+    fn sqrt_ratio_at_tick_matches_tick_math() {
+        assert_eq!(sqrt_ratio_at_tick(MIN_TICK), MIN_SQRT_RATIO);
+        assert_eq!(
+            sqrt_ratio_at_tick(MAX_TICK),
+            "1461446703485210103287273052203988822378723970342"
+                .parse::<U256>()
+                .unwrap()
+        );
+        assert_eq!(sqrt_ratio_at_tick(MAX_TICK), MAX_SQRT_RATIO);
+        assert_eq!(sqrt_ratio_at_tick(0), Q96);
+        for (tick, value) in SQRT_RATIOS {
+            assert_eq!(
+                sqrt_ratio_at_tick(tick),
+                value.parse::<U256>().unwrap(),
+                "tick {}",
+                tick
+            );
+        }
+        assert_eq!(sqrt_ratio_at_tick(MIN_TICK - 1), MIN_SQRT_RATIO);
+        assert_eq!(sqrt_ratio_at_tick(MAX_TICK + 1), MAX_SQRT_RATIO);
+    }
+
+    #[test]
+    fn tick_at_sqrt_ratio_bounds() {
+        assert_eq!(tick_at_sqrt_ratio(MIN_SQRT_RATIO), MIN_TICK);
+        assert_eq!(tick_at_sqrt_ratio(MIN_SQRT_RATIO + U256::from(1)), MIN_TICK);
+        assert_eq!(
+            tick_at_sqrt_ratio(MAX_SQRT_RATIO - U256::from(1)),
+            MAX_TICK - 1
+        );
+        assert_eq!(tick_at_sqrt_ratio(Q96), 0);
+        assert_eq!(tick_at_sqrt_ratio(Q96 - U256::from(1)), -1);
+    }
+
+    #[test]
+    fn tick_at_sqrt_ratio_round_trips() {
+        let ticks = (MIN_TICK..MAX_TICK)
+            .step_by(7_919)
+            .chain([MIN_TICK, -1, 0, 1, MAX_TICK - 1])
+            .chain(SQRT_RATIOS.iter().map(|(tick, _)| *tick));
+        for tick in ticks {
+            let sqrt_price = sqrt_ratio_at_tick(tick);
+            assert_eq!(tick_at_sqrt_ratio(sqrt_price), tick, "tick {}", tick);
+            // Just below the next tick's price still maps to this tick
+            let below_next = sqrt_ratio_at_tick(tick + 1) - U256::from(1);
+            assert_eq!(
+                tick_at_sqrt_ratio(below_next),
+                tick,
+                "below tick {}",
+                tick + 1
+            );
+        }
+    }
+
+    // Expected amounts follow `SqrtPriceMath.getAmount0Delta` / `getAmount1Delta` rounded
+    // down, on the sqrt prices of ticks 50 and 100 above
+    const LIQUIDITY: u128 = 1_000_000_000_000_000_000;
+
+    #[test]
+    fn amounts_below_the_range_are_all_token0() {
+        assert_eq!(
+            amounts_for_liquidity(Q96, 50, 100, LIQUIDITY),
+            (U256::from(2_490_519_147_795_409u64), U256::ZERO)
+        );
+        // At the lower bound the position is still entirely token0
+        assert_eq!(
+            amounts_for_liquidity(sqrt_ratio(50), 50, 100, LIQUIDITY),
+            (U256::from(2_490_519_147_795_409u64), U256::ZERO)
+        );
+    }
+
+    #[test]
+    fn amounts_inside_the_range_split_at_the_price() {
+        let sqrt_price: U256 = "79500000000000000000000000000".parse().unwrap();
+        assert_eq!(
+            amounts_for_liquidity(sqrt_price, 50, 100, LIQUIDITY),
+            (
+                U256::from(1_567_932_627_533_216u64),
+                U256::from(928_069_139_861_485u64)
+            )
+        );
+        // Swapped bounds describe the same range
+        assert_eq!(
+            amounts_for_liquidity(sqrt_price, 100, 50, LIQUIDITY),
+            amounts_for_liquidity(sqrt_price, 50, 100, LIQUIDITY)
+        );
+    }
+
+    #[test]
+    fn amounts_above_the_range_are_all_token1() {
+        assert_eq!(
+            amounts_for_liquidity(sqrt_ratio(250), 50, 100, LIQUIDITY),
+            (U256::ZERO, U256::from(2_509_267_321_785_672u64))
+        );
+        assert_eq!(
+            amounts_for_liquidity(sqrt_ratio(100), 50, 100, LIQUIDITY),
+            (U256::ZERO, U256::from(2_509_267_321_785_672u64))
+        );
+    }
+}
@@ -0,0 +1,79 @@
+//! Uniswap V3 concentrated liquidity math in floating point.
+//!
+//! These helpers mirror the on-chain `TickMath` / `LiquidityAmounts` formulas
+//! using `f64`. They are intended for analytics (greeks, scenario ladders),
+//! not for building exact transaction amounts.
+
+use alloy::primitives::U256;
+
+/// Base of the Uniswap V3 tick price scale (`price = 1.0001^tick`).
+pub const TICK_BASE: f64 = 1.0001;
+
+/// Returns `sqrt(1.0001^tick)` in raw token units (token1 per token0).
+pub fn sqrt_price_at_tick(tick: i32) -> f64 {
+    TICK_BASE.powf(tick as f64 / 2.0)
+}
+
+/// Returns the tick whose price is closest to (and not above) the given raw sqrt price.
+pub fn tick_at_sqrt_price(sqrt_price: f64) -> i32 {
+    (2.0 * sqrt_price.ln() / TICK_BASE.ln()).floor() as i32
+}
+
+/// Converts a Q64.96 `sqrtPriceX96` value into a raw sqrt price.
+pub fn sqrt_price_x96_to_f64(sqrt_price_x96: U256) -> f64 {
+    let q96 = 2f64.powi(96);
+    f64::from(sqrt_price_x96) / q96
+}
+
+/// Computes token amounts (raw units) held by `liquidity` in the range
+/// `[sqrt_lower, sqrt_upper]` at the current `sqrt_price`.
+///
+/// # Returns
+/// `(amount0, amount1)` in raw token units
+pub fn amounts_for_liquidity(
+    liquidity: f64,
+    sqrt_price: f64,
+    sqrt_lower: f64,
+    sqrt_upper: f64,
+) -> (f64, f64) {
+    let (sqrt_lower, sqrt_upper) = ordered(sqrt_lower, sqrt_upper);
+    if sqrt_price <= sqrt_lower {
+        (liquidity * (1.0 / sqrt_lower - 1.0 / sqrt_upper), 0.0)
+    } else if sqrt_price < sqrt_upper {
+        (
+            liquidity * (1.0 / sqrt_price - 1.0 / sqrt_upper),
+            liquidity * (sqrt_price - sqrt_lower),
+        )
+    } else {
+        (0.0, liquidity * (sqrt_upper - sqrt_lower))
+    }
+}
+
+fn ordered(a: f64, b: f64) -> (f64, f64) {
+    if a <= b {
+        (a, b)
+    } else {
+        (b, a)
+    }
+}
+
+/// Lowest tick supported by Uniswap V3 pools.
+pub const MIN_TICK: i32 = -887_272;
+
+/// Highest tick supported by Uniswap V3 pools.
+pub const MAX_TICK: i32 = 887_272;
+
+/// Returns a `[tick_lower, tick_upper)` range of about `width` ticks centred on `tick`.
+///
+/// Both bounds are aligned to `tick_spacing`, the range is at least one spacing wide
+/// and is clamped to the usable ticks of the pool.
+pub fn centered_range(tick: i32, tick_spacing: i32, width: i32) -> (i32, i32) {
+    let spacing = tick_spacing.max(1);
+    let half = (width / 2).max(spacing);
+    let align_down = |t: i32| t.div_euclid(spacing) * spacing;
+    let min_usable = align_down(MIN_TICK) + spacing;
+    let max_usable = align_down(MAX_TICK);
+    let lower = align_down(tick - half).max(min_usable);
+    let upper = (align_down(tick + half) + spacing).min(max_usable);
+    (lower, upper)
+}
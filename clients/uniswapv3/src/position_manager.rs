@@ -1,26 +1,63 @@
 //! Uniswap V3 PositionManager client and position data types.
 
-use alloy::eips::BlockId;
-use alloy::primitives::{Address, U256};
-use alloy::providers::{DynProvider, Provider};
+use alloy::eips::{BlockId, RpcBlockHash};
+use alloy::primitives::aliases::{I24, U24};
+use alloy::primitives::{keccak256, Address, B256, U256};
+use alloy::providers::{DynProvider, MulticallItem, Provider};
+use alloy::sol_types::{SolCall, SolValue};
 use anyhow::Result;
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
 use std::sync::Arc;
 
-use crate::config::UniswapV3PositionManagerConfig;
-use crate::contracts::{CollectParams, DecreaseLiquidityParams, IPositionManager};
+use crate::config::{AmmConfig, AmmKind, UniswapV3PositionManagerConfig};
+use crate::contracts::{
+    CollectParams, DecreaseLiquidityParams, IAlgebraFactory, IAlgebraPool, IAlgebraPositionManager,
+    ICLFactory, ICLGauge, ICLPool, IMasterChefV3, IPancakePositionManager, IPositionManager,
+    ISlipstreamPositionManager, IUniswapV3Factory, IUniswapV3Pool,
+};
+use crate::erc20::{Erc20MetadataClient, TokenMetadata};
+use crate::liquidity::Signer;
 
 /// Position data structure containing all relevant information for a position
 #[derive(Debug, Clone)]
 pub struct PositionData {
     /// The position NFT token ID
     pub token_id: U256,
+    /// Address that owned the position NFT when it was read (the staker for farmed positions)
+    pub owner: Address,
+    /// Farm contract holding the NFT when the position is staked (e.g. MasterChefV3)
+    pub staked_in: Option<Address>,
+    /// Pending farm rewards of a staked position, in raw units of the reward token
+    pub pending_reward: U256,
+    /// Block number the position data was read at
+    pub block_number: u64,
+    /// Hash of the block the position data was read at
+    pub block_hash: B256,
     /// Address of token0 in the pair
     pub token0: Address,
     /// Address of token1 in the pair
     pub token1: Address,
+    /// Metadata (decimals, symbol, name) of token0
+    pub token0_metadata: TokenMetadata,
+    /// Metadata (decimals, symbol, name) of token1
+    pub token1_metadata: TokenMetadata,
+    /// Pool fee tier in hundredths of a bip
+    pub fee: u32,
+    /// Lower tick of the position range
+    pub tick_lower: i32,
+    /// Upper tick of the position range
+    pub tick_upper: i32,
     /// Current liquidity amount in the position
     pub liquidity: u128,
+    /// Address of the pool the position belongs to (the PoolManager singleton for Uniswap V4)
+    pub pool: Address,
+    /// Uniswap V4 pool ID (`keccak256` of the pool key); `None` for V3 style pools
+    pub pool_id: Option<B256>,
+    /// Current pool price as a Q64.96 sqrt price (token1 per token0, raw units)
+    pub sqrt_price_x96: U256,
+    /// Current pool tick
+    pub tick: i32,
     /// Amount of token0 that would be withdrawn if all liquidity is removed
     pub withdrawable_amount0: U256,
     /// Amount of token1 that would be withdrawn if all liquidity is removed
@@ -29,14 +66,96 @@ pub struct PositionData {
     pub collectable_amount0: U256,
     /// Amount of token1 fees/rewards that can be collected
     pub collectable_amount1: U256,
+    /// Outcome of reading this position during the last `sync_lp`
+    pub sync_status: SyncStatus,
+}
+
+/// Block a sync was pinned to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SyncedBlock {
+    /// Block number
+    pub number: u64,
+    /// Block hash
+    pub hash: B256,
+}
+
+/// Error returned when a block reads were pinned to is no longer canonical
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BlockReorged {
+    /// Block the reads were pinned to
+    pub synced: SyncedBlock,
+    /// Hash of the canonical block at the same height
+    pub canonical: B256,
+}
+
+impl std::fmt::Display for BlockReorged {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "block {} was reorged: synced at {:?}, canonical is {:?}",
+            self.synced.number, self.synced.hash, self.canonical
+        )
+    }
+}
+
+impl std::error::Error for BlockReorged {}
+
+impl SyncedBlock {
+    /// Returns a block ID that pins calls to this exact block and fails if it is no
+    /// longer canonical
+    pub fn block_id(&self) -> BlockId {
+        BlockId::Hash(RpcBlockHash::from_hash(self.hash, Some(true)))
+    }
+}
+
+/// Outcome of reading a position during `sync_lp`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SyncStatus {
+    /// Position details and both simulations were read at the synced block
+    Ok,
+    /// The `decreaseLiquidity` or `collect` simulation failed; amounts are zero and not reliable
+    SimulationFailed(String),
+    /// The position was not refreshed by the last sync; data is from an earlier block
+    Stale,
+}
+
+impl SyncStatus {
+    /// Returns true if the position data was fully read by the last sync
+    pub fn is_ok(&self) -> bool {
+        matches!(self, SyncStatus::Ok)
+    }
+}
+
+impl fmt::Display for SyncStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SyncStatus::Ok => write!(f, "ok"),
+            SyncStatus::SimulationFailed(reason) => write!(f, "simulation failed: {}", reason),
+            SyncStatus::Stale => write!(f, "stale"),
+        }
+    }
 }
 
 /// UniswapV3PositionManager provides functionality to interact with Uniswap V3 PositionManager contracts
 pub struct UniswapV3PositionManager {
     /// PositionManager contract instance for making RPC calls
-    position_manager: IPositionManager::IPositionManagerInstance<Arc<DynProvider>>,
-    /// Internal cache of position data keyed by token ID
+    pub(crate) position_manager: IPositionManager::IPositionManagerInstance<Arc<DynProvider>>,
+    /// Internal cache of position data keyed by token ID, reconciled per owner on each sync
     positions: BTreeMap<U256, PositionData>,
+    /// AMM fork and deployment addresses
+    amm: AmmConfig,
+    /// Factory address, configured or read from the PositionManager on first use
+    factory: Option<Address>,
+    /// Reward token of the configured farm, read on first sync of a staked position
+    reward_token: Option<Address>,
+    /// Cached ERC-20 metadata of the position tokens
+    pub(crate) tokens: Erc20MetadataClient,
+    /// Transaction signer, present when a wallet is attached via `with_wallet`
+    pub(crate) signer: Option<Signer>,
+    /// SwapRouter address used by `swap_exact_input_single`
+    pub(crate) swap_router: Option<Address>,
+    /// Number of blocks behind the latest block that syncs are pinned to
+    confirmations: u64,
 }
 
 impl UniswapV3PositionManager {
@@ -49,18 +168,55 @@ impl UniswapV3PositionManager {
     /// # Returns
     /// A new `UniswapV3PositionManager` instance with the `PositionManagerInstance` initialized at the given address
     pub fn new(config: UniswapV3PositionManagerConfig, provider: Arc<DynProvider>) -> Self {
+        let tokens = Erc20MetadataClient::new(provider.clone());
         let position_manager = IPositionManager::new(config.address, provider);
         Self {
             position_manager,
             positions: BTreeMap::new(),
+            factory: config.amm.factory,
+            amm: config.amm,
+            reward_token: None,
+            tokens,
+            signer: None,
+            swap_router: config.swap_router,
+            confirmations: config.confirmations,
         }
     }
 
+    /// Returns the AMM fork and deployment addresses the client was configured with.
+    pub fn amm(&self) -> &AmmConfig {
+        &self.amm
+    }
+
+    /// Returns the reward token of the configured farm, once a staked position was synced.
+    pub fn reward_token(&self) -> Option<Address> {
+        self.reward_token
+    }
+
     /// Returns a reference to the cached position data keyed by token ID.
     pub fn positions(&self) -> &BTreeMap<U256, PositionData> {
         &self.positions
     }
 
+    /// Returns the cached ERC-20 metadata of a position token, if it was read by a sync.
+    pub fn token_metadata(&self, token: Address) -> Option<&TokenMetadata> {
+        self.tokens.cached(token)
+    }
+
+    /// Returns the cached positions that were read at the given block number.
+    pub fn positions_at(&self, block_number: u64) -> impl Iterator<Item = &PositionData> {
+        self.positions
+            .values()
+            .filter(move |pos| pos.block_number == block_number)
+    }
+
+    /// Returns the cached positions owned by the given address.
+    pub fn positions_of(&self, owner: Address) -> impl Iterator<Item = &PositionData> {
+        self.positions
+            .values()
+            .filter(move |pos| pos.owner == owner)
+    }
+
     /// Gets the current block number from the blockchain provider
     ///
     /// # Returns
@@ -75,129 +231,1076 @@ impl UniswapV3PositionManager {
         Ok(block.number())
     }
 
-    /// Synchronizes the internal `BTreeMap` with the current on-chain state of all positions owned by the specified address
-    ///
-    /// This function performs the following steps:
-    /// 1. Enumerates all positions owned by the address
-    /// 2. Reads basic position information (token0, token1, liquidity)
-    /// 3. Simulates liquidity withdrawal to get withdrawable amounts
-    /// 4. Simulates fee collection to get collectable amounts
-    /// 5. Updates the internal BTreeMap with all collected data
-    ///
-    /// # Arguments
-    /// * `owner` - The Ethereum address that owns the Uniswap V3 positions
-    ///
-    /// # Returns
-    /// `Result<()>` - Returns an error if any critical operation fails
-    pub async fn sync_lp(&mut self, owner: Address) -> Result<()> {
-        let block_number = self
+    /// Returns the block `confirmations` behind the latest block, which syncs are pinned to
+    pub async fn confirmed_block(&self) -> Result<SyncedBlock> {
+        let latest = self.get_block_number().await?;
+        self.block_at(latest.saturating_sub(self.confirmations))
+            .await
+    }
+
+    /// Returns the number and hash of the canonical block at `number`
+    pub async fn block_at(&self, number: u64) -> Result<SyncedBlock> {
+        let block = self
             .position_manager
             .provider()
-            .get_block(BlockId::latest())
+            .get_block(BlockId::number(number))
             .await?
-            .ok_or_else(|| anyhow::anyhow!("failed to get latest block"))?
-            .number();
-        let block_id = BlockId::number(block_number);
+            .ok_or_else(|| anyhow::anyhow!("failed to get block {}", number))?;
+        Ok(SyncedBlock {
+            number,
+            hash: block.header.hash,
+        })
+    }
 
-        let balance = self
-            .position_manager
-            .balanceOf(owner)
-            .block(block_id)
-            .call()
-            .await?;
+    /// Returns true if `block` is still part of the canonical chain
+    pub async fn is_canonical(&self, block: &SyncedBlock) -> Result<bool> {
+        Ok(self.block_at(block.number).await?.hash == block.hash)
+    }
 
-        for index in 0..balance.to::<u64>() {
-            let token_id = self
-                .position_manager
-                .tokenOfOwnerByIndex(owner, U256::from(index))
-                .block(block_id)
+    /// Returns an error if `block` was reorged out of the canonical chain
+    pub async fn ensure_canonical(&self, block: &SyncedBlock) -> Result<()> {
+        let canonical = self.block_at(block.number).await?;
+        if canonical.hash != block.hash {
+            return Err(BlockReorged {
+                synced: *block,
+                canonical: canonical.hash,
+            }
+            .into());
+        }
+        Ok(())
+    }
+
+    /// Gets the current gas price from the blockchain provider
+    ///
+    /// # Returns
+    /// `Result<u128>` - The gas price in wei, or an error if the request fails
+    pub async fn get_gas_price(&self) -> Result<u128> {
+        Ok(self.position_manager.provider().get_gas_price().await?)
+    }
+
+    /// Returns the factory address of the PositionManager, reading it once and caching it.
+    pub(crate) async fn factory(&mut self) -> Result<Address> {
+        if let Some(factory) = self.factory {
+            return Ok(factory);
+        }
+        let factory = self.position_manager.factory().call().await?;
+        self.factory = Some(factory);
+        Ok(factory)
+    }
+
+    /// Returns the CREATE2 deployer and pool init code hash if pool addresses can be
+    /// derived locally, resolving and caching the deployer on first use.
+    async fn pool_derivation(&mut self) -> Result<Option<(Address, B256)>> {
+        let Some(init_code_hash) = self.amm.init_code_hash else {
+            return Ok(None);
+        };
+        let deployer = match (self.amm.pool_deployer, self.amm.kind) {
+            (Some(deployer), _) => deployer,
+            (None, AmmKind::UniswapV3 | AmmKind::Slipstream) => self.factory().await?,
+            (None, AmmKind::Algebra) => {
+                let deployer = IAlgebraPositionManager::new(
+                    *self.position_manager.address(),
+                    self.position_manager.provider().clone(),
+                )
+                .poolDeployer()
                 .call()
                 .await?;
+                self.amm.pool_deployer = Some(deployer);
+                deployer
+            }
+            (None, AmmKind::PancakeSwapV3) => {
+                let deployer = IPancakePositionManager::new(
+                    *self.position_manager.address(),
+                    self.position_manager.provider().clone(),
+                )
+                .deployer()
+                .call()
+                .await?;
+                self.amm.pool_deployer = Some(deployer);
+                deployer
+            }
+        };
+        Ok(Some((deployer, init_code_hash)))
+    }
 
-            // Read position details
-            let position_info = self
+    /// Resolves the pool of each `(token0, token1, pool_param)` key, where `pool_param` is
+    /// the fee tier (Uniswap, PancakeSwap), the tick spacing (Slipstream) or unused (Algebra)
+    ///
+    /// Pools are derived with CREATE2 when the init code hash is known; otherwise they are
+    /// looked up with the factory (`getPool`, or `poolByPair` on Algebra) in one Multicall3
+    /// batch at `block_id`.
+    ///
+    /// # Returns
+    /// One entry per key; `None` where the lookup failed or the pool does not exist
+    pub(crate) async fn resolve_pools(
+        &mut self,
+        keys: &[(Address, Address, u32)],
+        block_id: BlockId,
+    ) -> Result<Vec<Option<Address>>> {
+        let kind = self.amm.kind;
+        if let Some((deployer, init_code_hash)) = self.pool_derivation().await? {
+            return Ok(keys
+                .iter()
+                .map(|&(token0, token1, pool_param)| {
+                    Some(pool_address(
+                        kind,
+                        deployer,
+                        init_code_hash,
+                        token0,
+                        token1,
+                        pool_param,
+                    ))
+                })
+                .collect());
+        }
+        if keys.is_empty() {
+            return Ok(Vec::new());
+        }
+        let provider = self.position_manager.provider().clone();
+        let factory = self.factory().await?;
+        let pools = match kind {
+            AmmKind::UniswapV3 | AmmKind::PancakeSwapV3 => {
+                let factory = IUniswapV3Factory::new(factory, provider.clone());
+                provider
+                    .multicall()
+                    .dynamic()
+                    .extend_calls(keys.iter().map(|&(token0, token1, fee)| {
+                        factory
+                            .getPool(token0, token1, U24::from(fee))
+                            .into_call(true)
+                    }))
+                    .block(block_id)
+                    .aggregate3()
+                    .await?
+            }
+            AmmKind::Algebra => {
+                let factory = IAlgebraFactory::new(factory, provider.clone());
+                provider
+                    .multicall()
+                    .dynamic()
+                    .extend_calls(keys.iter().map(|&(token0, token1, _)| {
+                        factory.poolByPair(token0, token1).into_call(true)
+                    }))
+                    .block(block_id)
+                    .aggregate3()
+                    .await?
+            }
+            AmmKind::Slipstream => {
+                let factory = ICLFactory::new(factory, provider.clone());
+                provider
+                    .multicall()
+                    .dynamic()
+                    .extend_calls(keys.iter().map(|&(token0, token1, tick_spacing)| {
+                        factory
+                            .getPool(token0, token1, tick_spacing_param(tick_spacing))
+                            .into_call(true)
+                    }))
+                    .block(block_id)
+                    .aggregate3()
+                    .await?
+            }
+        };
+        Ok(pools
+            .into_iter()
+            .map(|pool| pool.ok().filter(|pool| !pool.is_zero()))
+            .collect())
+    }
+
+    /// Reads the details of each position in one Multicall3 batch at `block_id`, decoding
+    /// the `positions` ABI of the configured AMM
+    ///
+    /// # Returns
+    /// One entry per token ID; `None` where the call failed (e.g. burned position)
+    pub(crate) async fn read_positions(
+        &self,
+        token_ids: &[U256],
+        block_id: BlockId,
+    ) -> Result<Vec<Option<PositionInfo>>> {
+        if token_ids.is_empty() {
+            return Ok(Vec::new());
+        }
+        let provider = self.position_manager.provider().clone();
+        let address = *self.position_manager.address();
+        Ok(match self.amm.kind {
+            AmmKind::UniswapV3 | AmmKind::PancakeSwapV3 => provider
+                .multicall()
+                .dynamic()
+                .extend_calls(
+                    token_ids
+                        .iter()
+                        .map(|&token_id| self.position_manager.positions(token_id).into_call(true)),
+                )
+                .block(block_id)
+                .aggregate3()
+                .await?
+                .into_iter()
+                .map(|info| info.ok().map(PositionInfo::from))
+                .collect(),
+            AmmKind::Algebra => {
+                let position_manager = IAlgebraPositionManager::new(address, provider.clone());
+                provider
+                    .multicall()
+                    .dynamic()
+                    .extend_calls(
+                        token_ids
+                            .iter()
+                            .map(|&token_id| position_manager.positions(token_id).into_call(true)),
+                    )
+                    .block(block_id)
+                    .aggregate3()
+                    .await?
+                    .into_iter()
+                    .map(|info| info.ok().map(PositionInfo::from))
+                    .collect()
+            }
+            AmmKind::Slipstream => {
+                let position_manager = ISlipstreamPositionManager::new(address, provider.clone());
+                provider
+                    .multicall()
+                    .dynamic()
+                    .extend_calls(
+                        token_ids
+                            .iter()
+                            .map(|&token_id| position_manager.positions(token_id).into_call(true)),
+                    )
+                    .block(block_id)
+                    .aggregate3()
+                    .await?
+                    .into_iter()
+                    .map(|info| info.ok().map(PositionInfo::from))
+                    .collect()
+            }
+        })
+    }
+
+    /// Reads the details of a single position at `block_id` without Multicall3
+    pub(crate) async fn position_info(
+        &self,
+        token_id: U256,
+        block_id: BlockId,
+    ) -> Result<PositionInfo> {
+        let provider = self.position_manager.provider().clone();
+        let address = *self.position_manager.address();
+        Ok(match self.amm.kind {
+            AmmKind::UniswapV3 | AmmKind::PancakeSwapV3 => self
                 .position_manager
                 .positions(token_id)
                 .block(block_id)
                 .call()
-                .await?;
+                .await?
+                .into(),
+            AmmKind::Algebra => IAlgebraPositionManager::new(address, provider)
+                .positions(token_id)
+                .block(block_id)
+                .call()
+                .await?
+                .into(),
+            AmmKind::Slipstream => ISlipstreamPositionManager::new(address, provider)
+                .positions(token_id)
+                .block(block_id)
+                .call()
+                .await?
+                .into(),
+        })
+    }
+
+    /// Reads the price, tick and (for dynamic-fee AMMs) current fee of each pool in
+    /// Multicall3 batches at `block_id`
+    ///
+    /// # Returns
+    /// One entry per pool; `None` where the read failed
+    pub(crate) async fn read_pool_states(
+        &self,
+        pools: &[Address],
+        block_id: BlockId,
+    ) -> Result<Vec<Option<PoolState>>> {
+        if pools.is_empty() {
+            return Ok(Vec::new());
+        }
+        let provider = self.position_manager.provider().clone();
+        Ok(match self.amm.kind {
+            AmmKind::UniswapV3 | AmmKind::PancakeSwapV3 => provider
+                .multicall()
+                .dynamic()
+                .extend_calls(pools.iter().map(|&pool| {
+                    IUniswapV3Pool::new(pool, provider.clone())
+                        .slot0()
+                        .into_call(true)
+                }))
+                .block(block_id)
+                .aggregate3()
+                .await?
+                .into_iter()
+                .map(|slot0| {
+                    slot0.ok().map(|slot0| PoolState {
+                        sqrt_price_x96: U256::from(slot0.sqrtPriceX96),
+                        tick: slot0.tick.as_i32(),
+                        fee: None,
+                    })
+                })
+                .collect(),
+            AmmKind::Algebra => provider
+                .multicall()
+                .dynamic()
+                .extend_calls(pools.iter().map(|&pool| {
+                    IAlgebraPool::new(pool, provider.clone())
+                        .globalState()
+                        .into_call(true)
+                }))
+                .block(block_id)
+                .aggregate3()
+                .await?
+                .into_iter()
+                .map(|state| {
+                    state.ok().map(|state| PoolState {
+                        sqrt_price_x96: U256::from(state.price),
+                        tick: state.tick.as_i32(),
+                        fee: Some(state.fee.into()),
+                    })
+                })
+                .collect(),
+            AmmKind::Slipstream => {
+                let slot0s =
+                    provider
+                        .multicall()
+                        .dynamic()
+                        .extend_calls(pools.iter().map(|&pool| {
+                            ICLPool::new(pool, provider.clone()).slot0().into_call(true)
+                        }))
+                        .block(block_id)
+                        .aggregate3()
+                        .await?;
+                let fees =
+                    provider
+                        .multicall()
+                        .dynamic()
+                        .extend_calls(pools.iter().map(|&pool| {
+                            ICLPool::new(pool, provider.clone()).fee().into_call(true)
+                        }))
+                        .block(block_id)
+                        .aggregate3()
+                        .await?;
+                slot0s
+                    .into_iter()
+                    .zip(fees)
+                    .map(|(slot0, fee)| {
+                        let slot0 = slot0.ok()?;
+                        Some(PoolState {
+                            sqrt_price_x96: U256::from(slot0.sqrtPriceX96),
+                            tick: slot0.tick.as_i32(),
+                            fee: Some(fee.ok()?.to::<u32>()),
+                        })
+                    })
+                    .collect()
+            }
+        })
+    }
 
-            let token0 = position_info.token0;
-            let token1 = position_info.token1;
-            let liquidity = position_info.liquidity;
-
-            // Step 3: Simulate liquidity withdrawal
-            let mut withdrawable_amount0 = U256::ZERO;
-            let mut withdrawable_amount1 = U256::ZERO;
-
-            if liquidity > 0 {
-                let decrease_params = DecreaseLiquidityParams {
-                    tokenId: token_id,
-                    liquidity,
-                    amount0Min: U256::ZERO,
-                    amount1Min: U256::ZERO,
-                    deadline: U256::from(u64::MAX), // Future timestamp for simulation
-                };
-
-                match self
-                    .position_manager
-                    .decreaseLiquidity(decrease_params)
+    /// Reads the state of a single pool at `block_id`
+    pub(crate) async fn pool_state(&self, pool: Address, block_id: BlockId) -> Result<PoolState> {
+        self.read_pool_states(&[pool], block_id)
+            .await?
+            .pop()
+            .flatten()
+            .ok_or_else(|| anyhow::anyhow!("failed to read the state of pool {:?}", pool))
+    }
+
+    /// Lists the token IDs `owner` holds in an ERC-721 style enumerable contract: the
+    /// PositionManager itself, or a farm that tracks staked NFTs per user.
+    async fn enumerate(
+        &self,
+        contract: Address,
+        owner: Address,
+        block_id: BlockId,
+    ) -> Result<Vec<U256>> {
+        let provider = self.position_manager.provider().clone();
+        let enumerable = IPositionManager::new(contract, provider.clone());
+        let balance = enumerable.balanceOf(owner).block(block_id).call().await?;
+        if balance.is_zero() {
+            return Ok(Vec::new());
+        }
+        provider
+            .multicall()
+            .dynamic()
+            .extend_calls((0..balance.to::<u64>()).map(|index| {
+                enumerable
+                    .tokenOfOwnerByIndex(owner, U256::from(index))
+                    .into_call(true)
+            }))
+            .block(block_id)
+            .aggregate3()
+            .await?
+            .into_iter()
+            .collect::<Result<Vec<U256>, _>>()
+            .map_err(|failure| {
+                anyhow::anyhow!(
+                    "tokenOfOwnerByIndex({}) failed on {:?}",
+                    failure.idx,
+                    contract
+                )
+            })
+    }
+
+    /// Returns the farms whose staked positions are synced with the owner's wallet: the
+    /// MasterChefV3 farm and the Slipstream gauges
+    fn farms(&self) -> Vec<Address> {
+        self.amm
+            .masterchef
+            .into_iter()
+            .chain(self.amm.gauges.iter().copied())
+            .collect()
+    }
+
+    /// Lists the token IDs `owner` staked in `farm` (MasterChefV3 or Slipstream gauge)
+    async fn enumerate_staked(
+        &self,
+        farm: Address,
+        owner: Address,
+        block_id: BlockId,
+    ) -> Result<Vec<U256>> {
+        if self.amm.masterchef == Some(farm) {
+            return self.enumerate(farm, owner, block_id).await;
+        }
+        Ok(
+            ICLGauge::new(farm, self.position_manager.provider().clone())
+                .stakedValues(owner)
+                .block(block_id)
+                .call()
+                .await?,
+        )
+    }
+
+    /// Reads the pending farm rewards of `(token_id, farm)` staked positions, one Multicall3
+    /// batch per farm, loading the reward token and its metadata on first use
+    ///
+    /// MasterChefV3 rewards are read with `pendingCake(tokenId)`, gauge emissions with
+    /// `earned(owner, tokenId)`.
+    ///
+    /// # Returns
+    /// The pending reward per token ID; `None` where the read failed
+    async fn pending_rewards(
+        &mut self,
+        owner: Address,
+        staked: &[(U256, Address)],
+        block_id: BlockId,
+    ) -> Result<BTreeMap<U256, Option<U256>>> {
+        let provider = self.position_manager.provider().clone();
+        let farms: BTreeSet<Address> = staked.iter().map(|&(_, farm)| farm).collect();
+        let mut rewards = BTreeMap::new();
+        for farm in farms {
+            let token_ids: Vec<U256> = staked
+                .iter()
+                .filter(|&&(_, staked_in)| staked_in == farm)
+                .map(|&(token_id, _)| token_id)
+                .collect();
+            let pending = if self.amm.masterchef == Some(farm) {
+                let masterchef = IMasterChefV3::new(farm, provider.clone());
+                if self.reward_token.is_none() {
+                    self.reward_token = Some(masterchef.CAKE().call().await?);
+                }
+                provider
+                    .multicall()
+                    .dynamic()
+                    .extend_calls(
+                        token_ids
+                            .iter()
+                            .map(|&token_id| masterchef.pendingCake(token_id).into_call(true)),
+                    )
                     .block(block_id)
-                    .call()
-                    .await
-                {
-                    Ok(result) => {
-                        withdrawable_amount0 = result.amount0;
-                        withdrawable_amount1 = result.amount1;
+                    .aggregate3()
+                    .await?
+            } else {
+                let gauge = ICLGauge::new(farm, provider.clone());
+                if self.reward_token.is_none() {
+                    self.reward_token = Some(gauge.rewardToken().call().await?);
+                }
+                provider
+                    .multicall()
+                    .dynamic()
+                    .extend_calls(
+                        token_ids
+                            .iter()
+                            .map(|&token_id| gauge.earned(owner, token_id).into_call(true)),
+                    )
+                    .block(block_id)
+                    .aggregate3()
+                    .await?
+            };
+            rewards.extend(
+                token_ids
+                    .into_iter()
+                    .zip(pending.into_iter().map(|reward| reward.ok())),
+            );
+        }
+        self.tokens.load(self.reward_token).await?;
+        Ok(rewards)
+    }
+
+    /// Synchronizes the internal `BTreeMap` with the current on-chain state of all positions owned by the specified address
+    ///
+    /// All reads are pinned to the same block and batched, so the number of RPC round
+    /// trips does not grow with the number of positions:
+    /// 1. Enumerates all positions owned by the address (`balanceOf`, then one Multicall3 batch of `tokenOfOwnerByIndex`),
+    ///    on the PositionManager and, if configured, on the MasterChefV3 farm for staked positions
+    /// 2. Reads basic position information (token0, token1, fee, tick range, liquidity)
+    ///    and the current price of each position's pool (Multicall3 batches of `positions`, `getPool` and `slot0`;
+    ///    pools are derived locally instead of `getPool` when the init code hash is known),
+    ///    plus ERC-20 metadata of tokens not seen before and pending farm rewards of staked positions
+    /// 3. Simulates fee collection to get collectable amounts
+    /// 4. Simulates liquidity withdrawal to get withdrawable amounts
+    /// 5. Updates the internal BTreeMap with all collected data
+    /// 6. Evicts cached positions of the owner that are no longer owned (burned or transferred)
+    ///
+    /// Steps 3 and 4 run through the PositionManager's own `multicall` from the NFT holder (the
+    /// owner, or the farm for staked positions), since Multicall3 is not authorized to act on them.
+    ///
+    /// Simulation failures do not abort the sync; they are recorded in the position's
+    /// `sync_status` together with zero amounts. Positions that could not be re-read keep
+    /// their previous data with `SyncStatus::Stale`.
+    ///
+    /// # Arguments
+    /// * `owner` - The Ethereum address that owns the Uniswap V3 positions
+    ///
+    /// # Returns
+    /// The block the sync was pinned to (`latest - confirmations`), or an error if any
+    /// critical operation fails. Reads are pinned by block hash, so a reorg of that block
+    /// during the sync fails the sync instead of mixing states.
+    pub async fn sync_lp(&mut self, owner: Address) -> Result<SyncedBlock> {
+        let synced = self.confirmed_block().await?;
+        let block_number = synced.number;
+        let block_id = synced.block_id();
+
+        // Every cached entry of this owner is stale until this sync refreshes it
+        for position in self.positions.values_mut() {
+            if position.owner == owner {
+                position.sync_status = SyncStatus::Stale;
+            }
+        }
+
+        // Step 1: Enumerate positions held in the wallet and, with farms configured,
+        // positions the owner staked in them (the farm holds those NFTs)
+        let mut holders: BTreeMap<U256, Address> = self
+            .enumerate(*self.position_manager.address(), owner, block_id)
+            .await?
+            .into_iter()
+            .map(|token_id| (token_id, owner))
+            .collect();
+        for farm in self.farms() {
+            for token_id in self.enumerate_staked(farm, owner, block_id).await? {
+                holders.insert(token_id, farm);
+            }
+        }
+        let token_ids: Vec<U256> = holders.keys().copied().collect();
+        let owned: BTreeSet<U256> = token_ids.iter().copied().collect();
+        if token_ids.is_empty() {
+            self.positions.retain(|_, pos| pos.owner != owner);
+            return Ok(synced);
+        }
+
+        // Step 2: Read position details, then the pool each position is valued at.
+        // A position that was synced before keeps its previous data marked as stale.
+        let position_infos = self.read_positions(&token_ids, block_id).await?;
+        let pool_keys: Vec<(Address, Address, u32)> = position_infos
+            .iter()
+            .flatten()
+            .map(|info| (info.token0, info.token1, info.pool_param))
+            .collect();
+        let pools = self.resolve_pools(&pool_keys, block_id).await?;
+        let pool_addresses: Vec<Address> = pools
+            .iter()
+            .flatten()
+            .copied()
+            .collect::<BTreeSet<Address>>()
+            .into_iter()
+            .collect();
+        let pool_states: BTreeMap<Address, PoolState> = pool_addresses
+            .iter()
+            .copied()
+            .zip(self.read_pool_states(&pool_addresses, block_id).await?)
+            .filter_map(|(pool, state)| Some((pool, state?)))
+            .collect();
+
+        let mut pools = pools.into_iter();
+        let mut reads = Vec::with_capacity(token_ids.len());
+        for (&token_id, info) in token_ids.iter().zip(position_infos) {
+            let read = info.and_then(|info| {
+                let pool = pools.next().flatten()?;
+                let state = *pool_states.get(&pool)?;
+                Some((info, pool, state))
+            });
+            match read {
+                Some(read) => reads.push((token_id, read)),
+                None if self
+                    .positions
+                    .get(&token_id)
+                    .is_some_and(|pos| pos.owner == owner) => {}
+                None => {
+                    return Err(anyhow::anyhow!(
+                        "failed to read position {} at block {}",
+                        token_id,
+                        block_number
+                    ))
+                }
+            }
+        }
+
+        // Pending farm rewards (CAKE, gauge emissions) of staked positions
+        let staked: Vec<(U256, Address)> = reads
+            .iter()
+            .map(|(token_id, _)| (*token_id, holders[token_id]))
+            .filter(|&(_, holder)| holder != owner)
+            .collect();
+        let rewards = self.pending_rewards(owner, &staked, block_id).await?;
+
+        // Read metadata of tokens seen for the first time
+        self.tokens
+            .load(
+                reads
+                    .iter()
+                    .flat_map(|(_, (info, _, _))| [info.token0, info.token1]),
+            )
+            .await?;
+
+        // Steps 3-4: Simulate fee collection and liquidity withdrawal from the NFT holder
+        let simulations = self
+            .simulate(
+                block_id,
+                reads
+                    .iter()
+                    .map(|(token_id, (info, _, _))| (*token_id, info.liquidity, holders[token_id])),
+            )
+            .await?;
+
+        // Step 5: Update BTreeMap
+        for ((token_id, (position_info, pool, state)), simulation) in
+            reads.into_iter().zip(simulations)
+        {
+            let staked_in = Some(holders[&token_id]).filter(|&holder| holder != owner);
+            let mut sync_status = simulation.sync_status;
+            let pending_reward = match rewards.get(&token_id) {
+                Some(Some(reward)) => *reward,
+                Some(None) => {
+                    if sync_status.is_ok() {
+                        sync_status = SyncStatus::SimulationFailed(
+                            "pending farm reward read failed".to_string(),
+                        );
                     }
-                    Err(_) => {
-                        // If simulation fails, leave amounts as zero
+                    U256::ZERO
+                }
+                None => U256::ZERO,
+            };
+            let position_data = PositionData {
+                token_id,
+                owner,
+                staked_in,
+                pending_reward,
+                block_number,
+                block_hash: synced.hash,
+                token0: position_info.token0,
+                token1: position_info.token1,
+                token0_metadata: self.token_metadata_of(position_info.token0)?,
+                token1_metadata: self.token_metadata_of(position_info.token1)?,
+                fee: state.fee.unwrap_or(position_info.pool_param),
+                tick_lower: position_info.tick_lower,
+                tick_upper: position_info.tick_upper,
+                liquidity: position_info.liquidity,
+                pool,
+                pool_id: None,
+                sqrt_price_x96: state.sqrt_price_x96,
+                tick: state.tick,
+                withdrawable_amount0: simulation.withdrawable_amount0,
+                withdrawable_amount1: simulation.withdrawable_amount1,
+                collectable_amount0: simulation.collectable_amount0,
+                collectable_amount1: simulation.collectable_amount1,
+                sync_status,
+            };
+
+            self.positions.insert(token_id, position_data);
+        }
+
+        // Step 6: Evict positions of this owner that were burned or transferred away
+        self.positions
+            .retain(|token_id, pos| pos.owner != owner || owned.contains(token_id));
+
+        Ok(synced)
+    }
+
+    /// Applies liquidity changes and pool prices observed in logs to cached positions of
+    /// `owner`, then re-simulates the affected positions at `block_number`
+    ///
+    /// # Arguments
+    /// * `owner` - Owner whose cached positions are updated
+    /// * `block` - Block the events were observed up to
+    /// * `liquidity_deltas` - Net liquidity change per token ID
+    /// * `prices` - Latest `(sqrtPriceX96, tick)` per pool
+    pub(crate) async fn apply_position_events(
+        &mut self,
+        owner: Address,
+        block: SyncedBlock,
+        liquidity_deltas: &BTreeMap<U256, i128>,
+        prices: &BTreeMap<Address, (U256, i32)>,
+    ) -> Result<()> {
+        let mut affected = Vec::new();
+        for position in self.positions.values_mut() {
+            if position.owner != owner {
+                continue;
+            }
+            let delta = liquidity_deltas.get(&position.token_id);
+            let price = prices.get(&position.pool);
+            if delta.is_none() && price.is_none() {
+                continue;
+            }
+            if let Some(&delta) = delta {
+                position.liquidity =
+                    position
+                        .liquidity
+                        .checked_add_signed(delta)
+                        .ok_or_else(|| {
+                            anyhow::anyhow!(
+                                "liquidity of position {} out of range after events",
+                                position.token_id
+                            )
+                        })?;
+            }
+            if let Some(&(sqrt_price_x96, tick)) = price {
+                position.sqrt_price_x96 = sqrt_price_x96;
+                position.tick = tick;
+            }
+            affected.push((
+                position.token_id,
+                position.liquidity,
+                position.staked_in.unwrap_or(owner),
+            ));
+        }
+
+        let simulations = self
+            .simulate(block.block_id(), affected.iter().copied())
+            .await?;
+        let staked: Vec<(U256, Address)> = affected
+            .iter()
+            .filter(|&&(_, _, holder)| holder != owner)
+            .map(|&(token_id, _, holder)| (token_id, holder))
+            .collect();
+        let rewards = self
+            .pending_rewards(owner, &staked, block.block_id())
+            .await?;
+        for ((token_id, _, _), simulation) in affected.into_iter().zip(simulations) {
+            if let Some(position) = self.positions.get_mut(&token_id) {
+                position.block_number = block.number;
+                position.block_hash = block.hash;
+                position.withdrawable_amount0 = simulation.withdrawable_amount0;
+                position.withdrawable_amount1 = simulation.withdrawable_amount1;
+                position.collectable_amount0 = simulation.collectable_amount0;
+                position.collectable_amount1 = simulation.collectable_amount1;
+                position.sync_status = simulation.sync_status;
+                match rewards.get(&token_id) {
+                    Some(Some(reward)) => position.pending_reward = *reward,
+                    Some(None) if position.sync_status.is_ok() => {
+                        position.sync_status = SyncStatus::SimulationFailed(
+                            "pending farm reward read failed".to_string(),
+                        );
                     }
+                    _ => {}
                 }
             }
+        }
+        Ok(())
+    }
 
-            // Step 4: Simulate fee collection
-            let mut collectable_amount0 = U256::ZERO;
-            let mut collectable_amount1 = U256::ZERO;
+    /// Returns the loaded metadata of a token or an error if it was not loaded.
+    fn token_metadata_of(&self, token: Address) -> Result<TokenMetadata> {
+        self.tokens
+            .cached(token)
+            .cloned()
+            .ok_or_else(|| anyhow::anyhow!("metadata of token {:?} not loaded", token))
+    }
 
-            let collect_params = CollectParams {
-                tokenId: token_id,
-                recipient: owner,
-                amount0Max: u128::MAX,
-                amount1Max: u128::MAX,
-            };
+    /// Simulates `collect` and `decreaseLiquidity` for `(token_id, liquidity, holder)`
+    /// positions, one PositionManager `multicall` per NFT holder (the owner's wallet or
+    /// the farm a position is staked in).
+    ///
+    /// # Returns
+    /// One simulation per position, in input order
+    pub(crate) async fn simulate(
+        &self,
+        block_id: BlockId,
+        positions: impl Iterator<Item = (U256, u128, Address)>,
+    ) -> Result<Vec<Simulation>> {
+        let positions: Vec<(U256, u128, Address)> = positions.collect();
+        let holders: BTreeSet<Address> = positions.iter().map(|&(_, _, holder)| holder).collect();
+        let mut simulations: Vec<Option<Simulation>> = positions.iter().map(|_| None).collect();
+        for holder in holders {
+            let indices: Vec<usize> = (0..positions.len())
+                .filter(|&i| positions[i].2 == holder)
+                .collect();
+            let held = indices
+                .iter()
+                .map(|&i| (positions[i].0, positions[i].1))
+                .collect();
+            for (i, simulation) in indices
+                .into_iter()
+                .zip(self.simulate_held(holder, block_id, held).await?)
+            {
+                simulations[i] = Some(simulation);
+            }
+        }
+        simulations
+            .into_iter()
+            .map(|simulation| simulation.ok_or_else(|| anyhow::anyhow!("missing simulation")))
+            .collect()
+    }
+
+    /// Simulates `collect` and `decreaseLiquidity` for positions held by `owner` in one
+    /// PositionManager `multicall`, sent from the holder.
+    ///
+    /// All collects run before any decrease so that collectable amounts contain fees
+    /// only. The PositionManager's multicall reverts as a whole if any call fails; in
+    /// that case each position is simulated on its own so the failure is attributed
+    /// to the position that caused it.
+    async fn simulate_held(
+        &self,
+        owner: Address,
+        block_id: BlockId,
+        positions: Vec<(U256, u128)>,
+    ) -> Result<Vec<Simulation>> {
+        if positions.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let collects = positions
+            .iter()
+            .map(|&(token_id, _)| collect_call(token_id, owner).abi_encode().into());
+        let decreases = positions
+            .iter()
+            .filter(|(_, liquidity)| *liquidity > 0)
+            .map(|&(token_id, liquidity)| decrease_call(token_id, liquidity).abi_encode().into());
+        let batch = self
+            .position_manager
+            .multicall(collects.chain(decreases).collect())
+            .from(owner)
+            .block(block_id)
+            .call()
+            .await;
+
+        if let Ok(results) = batch {
+            let (collects, decreases) = results.split_at(positions.len());
+            let mut decreases = decreases.iter();
+            return positions
+                .iter()
+                .zip(collects)
+                .map(|(&(_, liquidity), collect)| {
+                    let collect = IPositionManager::collectCall::abi_decode_returns(collect)?;
+                    let (withdrawable_amount0, withdrawable_amount1) = if liquidity > 0 {
+                        let decrease = decreases.next().ok_or_else(|| {
+                            anyhow::anyhow!("missing decreaseLiquidity result in multicall")
+                        })?;
+                        let decrease =
+                            IPositionManager::decreaseLiquidityCall::abi_decode_returns(decrease)?;
+                        (decrease.amount0, decrease.amount1)
+                    } else {
+                        (U256::ZERO, U256::ZERO)
+                    };
+                    Ok(Simulation {
+                        withdrawable_amount0,
+                        withdrawable_amount1,
+                        collectable_amount0: collect.amount0,
+                        collectable_amount1: collect.amount1,
+                        sync_status: SyncStatus::Ok,
+                    })
+                })
+                .collect();
+        }
 
+        let mut simulations = Vec::with_capacity(positions.len());
+        for (token_id, liquidity) in positions {
+            simulations.push(
+                self.simulate_position(owner, block_id, token_id, liquidity)
+                    .await,
+            );
+        }
+        Ok(simulations)
+    }
+
+    /// Simulates `collect` and `decreaseLiquidity` for a single position, recording the
+    /// first failure in the returned sync status.
+    async fn simulate_position(
+        &self,
+        owner: Address,
+        block_id: BlockId,
+        token_id: U256,
+        liquidity: u128,
+    ) -> Simulation {
+        let mut simulation = Simulation {
+            withdrawable_amount0: U256::ZERO,
+            withdrawable_amount1: U256::ZERO,
+            collectable_amount0: U256::ZERO,
+            collectable_amount1: U256::ZERO,
+            sync_status: SyncStatus::Ok,
+        };
+
+        match self
+            .position_manager
+            .call_builder(&collect_call(token_id, owner))
+            .from(owner)
+            .block(block_id)
+            .call()
+            .await
+        {
+            Ok(result) => {
+                simulation.collectable_amount0 = result.amount0;
+                simulation.collectable_amount1 = result.amount1;
+            }
+            Err(e) => {
+                simulation.sync_status = SyncStatus::SimulationFailed(format!("collect: {}", e));
+            }
+        }
+
+        if liquidity > 0 {
+            // Simulate as the owner: the PositionManager only lets approved callers decrease liquidity
             match self
                 .position_manager
-                .collect(collect_params)
+                .call_builder(&decrease_call(token_id, liquidity))
+                .from(owner)
                 .block(block_id)
                 .call()
                 .await
             {
                 Ok(result) => {
-                    collectable_amount0 = result.amount0;
-                    collectable_amount1 = result.amount1;
+                    simulation.withdrawable_amount0 = result.amount0;
+                    simulation.withdrawable_amount1 = result.amount1;
                 }
-                Err(_) => {
-                    // If simulation fails, leave amounts as zero
+                Err(e) => {
+                    if simulation.sync_status.is_ok() {
+                        simulation.sync_status =
+                            SyncStatus::SimulationFailed(format!("decreaseLiquidity: {}", e));
+                    }
                 }
             }
+        }
 
-            // Step 5: Update BTreeMap
-            let position_data = PositionData {
-                token_id,
-                token0,
-                token1,
-                liquidity,
-                withdrawable_amount0,
-                withdrawable_amount1,
-                collectable_amount0,
-                collectable_amount1,
-            };
+        simulation
+    }
+}
 
-            self.positions.insert(token_id, position_data);
+/// Simulated withdrawable and collectable amounts of a single position
+pub(crate) struct Simulation {
+    pub(crate) withdrawable_amount0: U256,
+    pub(crate) withdrawable_amount1: U256,
+    pub(crate) collectable_amount0: U256,
+    pub(crate) collectable_amount1: U256,
+    pub(crate) sync_status: SyncStatus,
+}
+
+/// Position details common to the supported PositionManager ABIs
+#[derive(Debug, Clone)]
+pub(crate) struct PositionInfo {
+    pub(crate) token0: Address,
+    pub(crate) token1: Address,
+    /// Fee tier (Uniswap, PancakeSwap), tick spacing (Slipstream) or zero (Algebra)
+    pub(crate) pool_param: u32,
+    pub(crate) tick_lower: i32,
+    pub(crate) tick_upper: i32,
+    pub(crate) liquidity: u128,
+}
+
+impl From<IPositionManager::positionsReturn> for PositionInfo {
+    fn from(info: IPositionManager::positionsReturn) -> Self {
+        Self {
+            token0: info.token0,
+            token1: info.token1,
+            pool_param: info.fee.to::<u32>(),
+            tick_lower: info.tickLower.as_i32(),
+            tick_upper: info.tickUpper.as_i32(),
+            liquidity: info.liquidity,
         }
+    }
+}
 
-        Ok(())
+impl From<IAlgebraPositionManager::positionsReturn> for PositionInfo {
+    fn from(info: IAlgebraPositionManager::positionsReturn) -> Self {
+        Self {
+            token0: info.token0,
+            token1: info.token1,
+            pool_param: 0,
+            tick_lower: info.tickLower.as_i32(),
+            tick_upper: info.tickUpper.as_i32(),
+            liquidity: info.liquidity,
+        }
+    }
+}
+
+impl From<ISlipstreamPositionManager::positionsReturn> for PositionInfo {
+    fn from(info: ISlipstreamPositionManager::positionsReturn) -> Self {
+        Self {
+            token0: info.token0,
+            token1: info.token1,
+            pool_param: info.tickSpacing.as_i32().unsigned_abs(),
+            tick_lower: info.tickLower.as_i32(),
+            tick_upper: info.tickUpper.as_i32(),
+            liquidity: info.liquidity,
+        }
+    }
+}
+
+/// Price state of a pool
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct PoolState {
+    pub(crate) sqrt_price_x96: U256,
+    pub(crate) tick: i32,
+    /// Current fee of dynamic-fee pools (Algebra, Slipstream); `None` uses the fee tier
+    pub(crate) fee: Option<u32>,
+}
+
+/// Converts a tick spacing pool key parameter to the int24 of Slipstream calls.
+fn tick_spacing_param(tick_spacing: u32) -> I24 {
+    I24::try_from(tick_spacing as i32).unwrap_or(I24::ZERO)
+}
+
+/// Derives a pool address from its CREATE2 deployer, the pool init code hash and the
+/// pool key (`token0 < token1`).
+///
+/// The salt is `keccak256(abi.encode(token0, token1, pool_param))`, where `pool_param`
+/// is the fee tier or tick spacing (positive, so it encodes like a uint256), and
+/// `keccak256(abi.encode(token0, token1))` on Algebra.
+pub(crate) fn pool_address(
+    kind: AmmKind,
+    deployer: Address,
+    init_code_hash: B256,
+    token0: Address,
+    token1: Address,
+    pool_param: u32,
+) -> Address {
+    let salt = match kind {
+        AmmKind::Algebra => keccak256((token0, token1).abi_encode()),
+        _ => keccak256((token0, token1, U256::from(pool_param)).abi_encode()),
+    };
+    deployer.create2(salt, init_code_hash)
+}
+
+/// Builds a `collect` call that collects all fees of the position to `recipient`.
+pub(crate) fn collect_call(token_id: U256, recipient: Address) -> IPositionManager::collectCall {
+    IPositionManager::collectCall {
+        params: CollectParams {
+            tokenId: token_id,
+            recipient,
+            amount0Max: u128::MAX,
+            amount1Max: u128::MAX,
+        },
+    }
+}
+
+/// Builds a `decreaseLiquidity` call that removes `liquidity` without slippage limits.
+pub(crate) fn decrease_call(
+    token_id: U256,
+    liquidity: u128,
+) -> IPositionManager::decreaseLiquidityCall {
+    IPositionManager::decreaseLiquidityCall {
+        params: DecreaseLiquidityParams {
+            tokenId: token_id,
+            liquidity,
+            amount0Min: U256::ZERO,
+            amount1Min: U256::ZERO,
+            deadline: U256::from(u64::MAX), // Future timestamp for simulation
+        },
     }
 }
@@ -0,0 +1,80 @@
+//! Abstraction over the clients that read LP positions into `PositionData`.
+
+use alloy::primitives::Address;
+use anyhow::Result;
+use std::future::Future;
+
+use crate::erc20::TokenMetadata;
+use crate::events::PositionEventTracker;
+use crate::position_manager::{PositionData, SyncedBlock, UniswapV3PositionManager};
+
+/// A client that reads the LP positions of an owner into `PositionData`
+///
+/// Implemented by the Uniswap V3 client and by clients of other position managers
+/// (e.g. Uniswap V4), so strategies can hedge positions without knowing where they live.
+pub trait PositionSource {
+    /// Synchronizes the cached positions of `owner` with the chain, pinned to one block
+    ///
+    /// # Returns
+    /// The block the sync was pinned to
+    fn sync_lp(&mut self, owner: Address) -> impl Future<Output = Result<SyncedBlock>> + Send;
+
+    /// Brings the cached positions of `owner` up to date through `tracker`, which follows
+    /// the same owner
+    ///
+    /// Sources that cannot apply position logs run a full `sync_lp`.
+    ///
+    /// # Returns
+    /// The block the cached positions reflect
+    fn sync_tracked(
+        &mut self,
+        owner: Address,
+        _tracker: &mut PositionEventTracker,
+    ) -> impl Future<Output = Result<SyncedBlock>> + Send {
+        self.sync_lp(owner)
+    }
+
+    /// Returns the cached positions owned by the given address.
+    fn positions_of(&self, owner: Address) -> impl Iterator<Item = &PositionData>;
+
+    /// Returns the cached metadata of a position or reward token, if it was read by a sync.
+    fn token_metadata(&self, token: Address) -> Option<&TokenMetadata>;
+
+    /// Returns the farm reward token, if the source reports pending rewards.
+    fn reward_token(&self) -> Option<Address> {
+        None
+    }
+
+    /// Returns an error if `block` was reorged out of the canonical chain
+    fn ensure_canonical(&self, block: &SyncedBlock) -> impl Future<Output = Result<()>> + Send;
+}
+
+impl PositionSource for UniswapV3PositionManager {
+    fn sync_lp(&mut self, owner: Address) -> impl Future<Output = Result<SyncedBlock>> + Send {
+        UniswapV3PositionManager::sync_lp(self, owner)
+    }
+
+    fn sync_tracked(
+        &mut self,
+        _owner: Address,
+        tracker: &mut PositionEventTracker,
+    ) -> impl Future<Output = Result<SyncedBlock>> + Send {
+        tracker.sync(self)
+    }
+
+    fn positions_of(&self, owner: Address) -> impl Iterator<Item = &PositionData> {
+        UniswapV3PositionManager::positions_of(self, owner)
+    }
+
+    fn token_metadata(&self, token: Address) -> Option<&TokenMetadata> {
+        UniswapV3PositionManager::token_metadata(self, token)
+    }
+
+    fn reward_token(&self) -> Option<Address> {
+        UniswapV3PositionManager::reward_token(self)
+    }
+
+    fn ensure_canonical(&self, block: &SyncedBlock) -> impl Future<Output = Result<()>> + Send {
+        UniswapV3PositionManager::ensure_canonical(self, block)
+    }
+}
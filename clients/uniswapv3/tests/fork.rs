@@ -0,0 +1,122 @@
+//! Block pinning and reorg detection against a local anvil node.
+//!
+//! Ignored by default. Start anvil, forking a chain where `LPH_FORK_OWNER` holds LP positions
+//! for the sync test, then run:
+//!
+//! ```text
+//! anvil --fork-url <rpc url>
+//! LPH_FORK_OWNER=0x... cargo test -p clients-uniswapv3 --test fork -- --ignored
+//! ```
+//!
+//! `LPH_ANVIL_URL` defaults to `http://127.0.0.1:8545` and `LPH_FORK_POSITION_MANAGER` to the
+//! Uniswap V3 NonfungiblePositionManager on Ethereum.
+
+use std::sync::Arc;
+
+use alloy::network::Ethereum;
+use alloy::primitives::{address, Address, U256};
+use alloy::providers::{DynProvider, Provider, RootProvider};
+use clients_uniswapv3::{
+    AmmConfig, BlockReorged, UniswapV3PositionManager, UniswapV3PositionManagerConfig,
+};
+
+const CONFIRMATIONS: u64 = 3;
+
+fn provider() -> Arc<DynProvider> {
+    let url = std::env::var("LPH_ANVIL_URL").unwrap_or_else(|_| "http://127.0.0.1:8545".into());
+    Arc::new(RootProvider::<Ethereum>::new_http(url.parse().expect("LPH_ANVIL_URL")).erased())
+}
+
+async fn manager(provider: Arc<DynProvider>) -> UniswapV3PositionManager {
+    let address = std::env::var("LPH_FORK_POSITION_MANAGER")
+        .map(|a| a.parse().expect("LPH_FORK_POSITION_MANAGER"))
+        .unwrap_or(address!("C36442b4a4522E871399CD717aBDD847Ab11FE88"));
+    let chain_id = provider.get_chain_id().await.expect("chain id");
+    let config = UniswapV3PositionManagerConfig {
+        address,
+        swap_router: None,
+        confirmations: CONFIRMATIONS,
+        amm: AmmConfig::known(chain_id, address).unwrap_or_default(),
+    };
+    UniswapV3PositionManager::new(config, provider)
+}
+
+async fn mine(provider: &DynProvider, blocks: u64) {
+    provider
+        .raw_request::<_, serde_json::Value>("anvil_mine".into(), (U256::from(blocks),))
+        .await
+        .expect("anvil_mine");
+}
+
+#[tokio::test]
+#[ignore = "requires a local anvil fork and LPH_FORK_OWNER"]
+async fn sync_lp_pins_every_read_to_the_confirmed_block() {
+    let owner: Address = std::env::var("LPH_FORK_OWNER")
+        .expect("LPH_FORK_OWNER: address holding positions on the forked chain")
+        .parse()
+        .expect("LPH_FORK_OWNER");
+    let provider = provider();
+    let mut manager = manager(provider.clone()).await;
+    mine(&provider, CONFIRMATIONS + 2).await;
+
+    let head = provider.get_block_number().await.unwrap();
+    let synced = manager.sync_lp(owner).await.unwrap();
+    assert_eq!(synced.number, head - CONFIRMATIONS);
+    assert_eq!(synced, manager.block_at(synced.number).await.unwrap());
+
+    let positions: Vec<_> = manager.positions_of(owner).cloned().collect();
+    assert!(!positions.is_empty(), "owner holds no positions");
+    for position in &positions {
+        assert_eq!(position.block_number, synced.number);
+        assert_eq!(position.block_hash, synced.hash);
+        assert!(position.sync_status.is_ok(), "{}", position.sync_status);
+    }
+
+    // Newer blocks move the pinned block forward by exactly the blocks mined
+    mine(&provider, 4).await;
+    let next = manager.sync_lp(owner).await.unwrap();
+    assert_eq!(next.number, synced.number + 4);
+    assert!(manager
+        .positions_of(owner)
+        .all(|position| position.block_hash == next.hash));
+}
+
+#[tokio::test]
+#[ignore = "requires a local anvil node"]
+async fn ensure_canonical_detects_a_replaced_block() {
+    let provider = provider();
+    let manager = manager(provider.clone()).await;
+    let snapshot: U256 = provider
+        .raw_request("evm_snapshot".into(), ())
+        .await
+        .expect("evm_snapshot");
+
+    mine(&provider, CONFIRMATIONS + 2).await;
+    let synced = manager.confirmed_block().await.unwrap();
+    manager.ensure_canonical(&synced).await.unwrap();
+    assert!(manager.is_canonical(&synced).await.unwrap());
+
+    // Replace the synced block by one with a different timestamp, hence a different hash
+    let reverted: bool = provider
+        .raw_request("evm_revert".into(), (snapshot,))
+        .await
+        .expect("evm_revert");
+    assert!(reverted);
+    provider
+        .raw_request::<_, serde_json::Value>("evm_increaseTime".into(), (U256::from(3_600),))
+        .await
+        .expect("evm_increaseTime");
+    mine(&provider, CONFIRMATIONS + 2).await;
+
+    assert!(!manager.is_canonical(&synced).await.unwrap());
+    let error = manager.ensure_canonical(&synced).await.unwrap_err();
+    let reorged = error
+        .downcast_ref::<BlockReorged>()
+        .expect("error is BlockReorged");
+    assert_eq!(reorged.synced, synced);
+    assert_ne!(reorged.canonical, synced.hash);
+    assert_eq!(
+        reorged.canonical,
+        manager.block_at(synced.number).await.unwrap().hash
+    );
+}
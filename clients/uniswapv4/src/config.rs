@@ -0,0 +1,51 @@
+//! Configuration types for Uniswap V4 clients.
+
+use alloy::primitives::Address;
+use serde::{Deserialize, Serialize};
+
+/// Configuration for UniswapV4PositionManager
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UniswapV4PositionManagerConfig {
+    /// The contract address of the Uniswap V4 PositionManager contract
+    pub position_manager: Address,
+    /// The contract address of the StateView lens used to read pool and position state
+    pub state_view: Address,
+    /// Block to discover positions from, usually the PositionManager deployment block
+    #[serde(default)]
+    pub start_block: u64,
+    /// Maximum number of blocks covered by one `eth_getLogs` request
+    #[serde(default = "default_max_block_range")]
+    pub max_block_range: u64,
+    /// Number of blocks behind the latest block that reads are pinned to (`latest - confirmations`)
+    #[serde(default)]
+    pub confirmations: u64,
+    /// Symbol reported for the chain's native currency (address zero in pool keys)
+    #[serde(default = "default_native_symbol")]
+    pub native_symbol: String,
+}
+
+fn default_max_block_range() -> u64 {
+    2_000
+}
+
+fn default_native_symbol() -> String {
+    "ETH".to_string()
+}
+
+impl UniswapV4PositionManagerConfig {
+    /// Builds a configuration with default discovery and native currency settings
+    ///
+    /// # Arguments
+    /// * `position_manager` - Uniswap V4 PositionManager address
+    /// * `state_view` - StateView address of the same deployment
+    pub fn new(position_manager: Address, state_view: Address) -> Self {
+        Self {
+            position_manager,
+            state_view,
+            start_block: 0,
+            max_block_range: default_max_block_range(),
+            confirmations: 0,
+            native_symbol: default_native_symbol(),
+        }
+    }
+}
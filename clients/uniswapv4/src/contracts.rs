@@ -0,0 +1,53 @@
+//! Contract interfaces generated via alloy's sol! macro.
+
+use alloy::sol;
+
+sol! {
+    // Pool key identifying a pool in the PoolManager singleton; `poolId = keccak256(abi.encode(key))`
+    struct PoolKey {
+        address currency0;
+        address currency1;
+        uint24 fee;
+        int24 tickSpacing;
+        address hooks;
+    }
+
+    // V4 PositionManager contract interface
+    //
+    // `info` packs the position range: bits 8..32 hold `tickLower` and bits 32..56
+    // hold `tickUpper` (both int24).
+    #[sol(rpc)]
+    interface IV4PositionManager {
+        function poolManager() external view returns (address);
+        function ownerOf(uint256 tokenId) external view returns (address);
+        function getPoolAndPositionInfo(uint256 tokenId) external view returns (PoolKey memory poolKey, uint256 info);
+
+        event Transfer(address indexed from, address indexed to, uint256 indexed id);
+    }
+
+    // StateView lens reading pool and position state out of the PoolManager
+    #[sol(rpc)]
+    interface IStateView {
+        function getSlot0(bytes32 poolId) external view returns (
+            uint160 sqrtPriceX96,
+            int24 tick,
+            uint24 protocolFee,
+            uint24 lpFee
+        );
+        function getPositionInfo(
+            bytes32 poolId,
+            address owner,
+            int24 tickLower,
+            int24 tickUpper,
+            bytes32 salt
+        ) external view returns (
+            uint128 liquidity,
+            uint256 feeGrowthInside0LastX128,
+            uint256 feeGrowthInside1LastX128
+        );
+        function getFeeGrowthInside(bytes32 poolId, int24 tickLower, int24 tickUpper) external view returns (
+            uint256 feeGrowthInside0X128,
+            uint256 feeGrowthInside1X128
+        );
+    }
+}
@@ -0,0 +1,6 @@
+mod config;
+mod contracts;
+mod position_manager;
+
+pub use config::UniswapV4PositionManagerConfig;
+pub use position_manager::UniswapV4PositionManager;
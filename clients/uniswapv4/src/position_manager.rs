@@ -0,0 +1,510 @@
+//! Uniswap V4 PositionManager client.
+
+use alloy::eips::BlockId;
+use alloy::primitives::aliases::I24;
+use alloy::primitives::{keccak256, Address, B256, U256};
+use alloy::providers::{DynProvider, MulticallItem, Provider};
+use alloy::rpc::types::Filter;
+use alloy::sol_types::{SolEvent, SolValue};
+use anyhow::Result;
+use clients_uniswapv3::liquidity_math::{amounts_for_liquidity, fees_owed};
+use clients_uniswapv3::{
+    BlockReorged, Erc20MetadataClient, PositionData, PositionSource, SyncStatus, SyncedBlock,
+    TokenMetadata,
+};
+use std::collections::{BTreeMap, BTreeSet};
+use std::future::Future;
+use std::sync::Arc;
+
+use crate::config::UniswapV4PositionManagerConfig;
+use crate::contracts::{IStateView, IV4PositionManager, PoolKey};
+
+/// `fee` value of a pool key whose LP fee is set dynamically by its hook
+const DYNAMIC_FEE_FLAG: u32 = 0x800000;
+
+/// Token IDs seen transferred to an owner and the last block scanned for them
+#[derive(Default)]
+struct Discovery {
+    /// Candidate token IDs, verified with `ownerOf` on every sync
+    token_ids: BTreeSet<U256>,
+    /// Last block whose `Transfer` logs were scanned
+    scanned_to: Option<u64>,
+}
+
+/// Position details read from the PositionManager and StateView
+struct PositionRead {
+    key: PoolKey,
+    pool_id: B256,
+    tick_lower: i32,
+    tick_upper: i32,
+    liquidity: u128,
+    fee_growth_inside0_last_x128: U256,
+    fee_growth_inside1_last_x128: U256,
+}
+
+/// UniswapV4PositionManager reads Uniswap V4 positions into the same `PositionData`
+/// shape as the Uniswap V3 client
+///
+/// V4 pools live in the PoolManager singleton and are identified by a pool ID, so
+/// `PositionData::pool` is the PoolManager address and `PositionData::pool_id` is set.
+/// The PositionManager is not ERC-721 enumerable: owned positions are discovered from
+/// `Transfer` logs and verified with `ownerOf`.
+pub struct UniswapV4PositionManager {
+    /// PositionManager contract instance for making RPC calls
+    position_manager: IV4PositionManager::IV4PositionManagerInstance<Arc<DynProvider>>,
+    /// StateView contract instance for reading pool and position state
+    state_view: IStateView::IStateViewInstance<Arc<DynProvider>>,
+    /// PoolManager address, read from the PositionManager on first sync
+    pool_manager: Option<Address>,
+    /// Internal cache of position data keyed by token ID, reconciled per owner on each sync
+    positions: BTreeMap<U256, PositionData>,
+    /// Position discovery state per owner
+    discovered: BTreeMap<Address, Discovery>,
+    /// Cached ERC-20 metadata of the position currencies, including the native currency
+    tokens: Erc20MetadataClient,
+    /// First block scanned for `Transfer` logs
+    start_block: u64,
+    /// Maximum number of blocks covered by one `eth_getLogs` request
+    max_block_range: u64,
+    /// Number of blocks behind the latest block that syncs are pinned to
+    confirmations: u64,
+}
+
+impl UniswapV4PositionManager {
+    /// Creates a new `UniswapV4PositionManager` instance
+    ///
+    /// # Arguments
+    /// * `config` - A `UniswapV4PositionManagerConfig` instance containing the contract addresses
+    /// * `provider` - Provider instance for making RPC calls to the blockchain
+    ///
+    /// # Returns
+    /// A new `UniswapV4PositionManager` instance with the native currency metadata cached
+    pub fn new(config: UniswapV4PositionManagerConfig, provider: Arc<DynProvider>) -> Self {
+        let mut tokens = Erc20MetadataClient::new(provider.clone());
+        tokens.insert(TokenMetadata {
+            address: Address::ZERO,
+            decimals: 18,
+            symbol: config.native_symbol.clone(),
+            name: config.native_symbol,
+        });
+        Self {
+            position_manager: IV4PositionManager::new(config.position_manager, provider.clone()),
+            state_view: IStateView::new(config.state_view, provider),
+            pool_manager: None,
+            positions: BTreeMap::new(),
+            discovered: BTreeMap::new(),
+            tokens,
+            start_block: config.start_block,
+            max_block_range: config.max_block_range,
+            confirmations: config.confirmations,
+        }
+    }
+
+    /// Returns a reference to the cached position data keyed by token ID.
+    pub fn positions(&self) -> &BTreeMap<U256, PositionData> {
+        &self.positions
+    }
+
+    /// Returns the cached positions owned by the given address.
+    pub fn positions_of(&self, owner: Address) -> impl Iterator<Item = &PositionData> {
+        self.positions
+            .values()
+            .filter(move |pos| pos.owner == owner)
+    }
+
+    /// Returns the cached metadata of a position currency, if it was read by a sync.
+    pub fn token_metadata(&self, token: Address) -> Option<&TokenMetadata> {
+        self.tokens.cached(token)
+    }
+
+    /// Returns the block `confirmations` behind the latest block, which syncs are pinned to
+    pub async fn confirmed_block(&self) -> Result<SyncedBlock> {
+        let latest = self.position_manager.provider().get_block_number().await?;
+        self.block_at(latest.saturating_sub(self.confirmations))
+            .await
+    }
+
+    /// Returns the number and hash of the canonical block at `number`
+    pub async fn block_at(&self, number: u64) -> Result<SyncedBlock> {
+        let block = self
+            .position_manager
+            .provider()
+            .get_block(BlockId::number(number))
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("failed to get block {}", number))?;
+        Ok(SyncedBlock {
+            number,
+            hash: block.header.hash,
+        })
+    }
+
+    /// Returns an error if `block` was reorged out of the canonical chain
+    pub async fn ensure_canonical(&self, block: &SyncedBlock) -> Result<()> {
+        let canonical = self.block_at(block.number).await?;
+        if canonical.hash != block.hash {
+            return Err(BlockReorged {
+                synced: *block,
+                canonical: canonical.hash,
+            }
+            .into());
+        }
+        Ok(())
+    }
+
+    /// Returns the PoolManager address, reading it once and caching it.
+    async fn pool_manager(&mut self) -> Result<Address> {
+        if let Some(pool_manager) = self.pool_manager {
+            return Ok(pool_manager);
+        }
+        let pool_manager = self.position_manager.poolManager().call().await?;
+        self.pool_manager = Some(pool_manager);
+        Ok(pool_manager)
+    }
+
+    /// Scans `Transfer` logs to `owner` up to `to_block` in windows of `max_block_range`
+    /// blocks, adding the transferred token IDs to the owner's candidates
+    ///
+    /// Each window is scanned once; later calls continue after the last scanned block.
+    async fn discover(&mut self, owner: Address, to_block: u64) -> Result<()> {
+        let provider = self.position_manager.provider().clone();
+        let address = *self.position_manager.address();
+        let discovery = self.discovered.entry(owner).or_default();
+        let mut from = discovery
+            .scanned_to
+            .map(|block| block + 1)
+            .unwrap_or(self.start_block);
+        while from <= to_block {
+            let to = to_block.min(from + self.max_block_range.max(1) - 1);
+            let logs = provider
+                .get_logs(
+                    &Filter::new()
+                        .address(address)
+                        .event_signature(IV4PositionManager::Transfer::SIGNATURE_HASH)
+                        .topic2(owner.into_word())
+                        .from_block(from)
+                        .to_block(to),
+                )
+                .await?;
+            for log in logs {
+                if let Ok(transfer) = log.log_decode::<IV4PositionManager::Transfer>() {
+                    discovery.token_ids.insert(transfer.inner.data.id);
+                }
+            }
+            discovery.scanned_to = Some(to);
+            from = to + 1;
+        }
+        Ok(())
+    }
+
+    /// Synchronizes the internal `BTreeMap` with the current on-chain state of all positions owned by the specified address
+    ///
+    /// All reads are pinned to the same block and batched through Multicall3:
+    /// 1. Discovers token IDs transferred to the owner from `Transfer` logs and keeps those
+    ///    `ownerOf` still reports for the owner
+    /// 2. Reads each position's pool key and tick range (`getPoolAndPositionInfo`)
+    /// 3. Reads pool prices (`getSlot0`), position liquidity and fee checkpoints
+    ///    (`getPositionInfo`) and the fee growth inside each range (`getFeeGrowthInside`) from StateView
+    /// 4. Computes withdrawable amounts and unclaimed fees with exact integer math
+    /// 5. Updates the internal BTreeMap and evicts positions that are no longer owned
+    ///
+    /// Amounts do not include fees taken by pool hooks on withdrawal. A failed fee growth
+    /// read is recorded as `SyncStatus::SimulationFailed` with zero amounts; positions that
+    /// could not be re-read keep their previous data with `SyncStatus::Stale`.
+    ///
+    /// # Arguments
+    /// * `owner` - The Ethereum address that owns the Uniswap V4 positions
+    ///
+    /// # Returns
+    /// The block the sync was pinned to (`latest - confirmations`), or an error if any
+    /// critical operation fails
+    pub async fn sync_lp(&mut self, owner: Address) -> Result<SyncedBlock> {
+        let provider = self.position_manager.provider().clone();
+        let synced = self.confirmed_block().await?;
+        let block_id = synced.block_id();
+        let pool_manager = self.pool_manager().await?;
+
+        // Every cached entry of this owner is stale until this sync refreshes it
+        for position in self.positions.values_mut() {
+            if position.owner == owner {
+                position.sync_status = SyncStatus::Stale;
+            }
+        }
+
+        // Step 1: Discover candidates and keep the ones the owner still holds
+        self.discover(owner, synced.number).await?;
+        let candidates: Vec<U256> = self.discovered[&owner].token_ids.iter().copied().collect();
+        let holders = if candidates.is_empty() {
+            Vec::new()
+        } else {
+            provider
+                .multicall()
+                .dynamic()
+                .extend_calls(
+                    candidates
+                        .iter()
+                        .map(|&token_id| self.position_manager.ownerOf(token_id).into_call(true)),
+                )
+                .block(block_id)
+                .aggregate3()
+                .await?
+        };
+        let token_ids: Vec<U256> = candidates
+            .into_iter()
+            .zip(holders)
+            .filter(|(_, holder)| holder.as_ref().is_ok_and(|holder| *holder == owner))
+            .map(|(token_id, _)| token_id)
+            .collect();
+        let owned: BTreeSet<U256> = token_ids.iter().copied().collect();
+        if let Some(discovery) = self.discovered.get_mut(&owner) {
+            discovery.token_ids = owned.clone();
+        }
+        if token_ids.is_empty() {
+            self.positions.retain(|_, pos| pos.owner != owner);
+            return Ok(synced);
+        }
+
+        // Step 2: Pool key and range of each position
+        let infos = provider
+            .multicall()
+            .dynamic()
+            .extend_calls(token_ids.iter().map(|&token_id| {
+                self.position_manager
+                    .getPoolAndPositionInfo(token_id)
+                    .into_call(true)
+            }))
+            .block(block_id)
+            .aggregate3()
+            .await?;
+        let ranges: Vec<Option<(PoolKey, B256, i32, i32)>> = infos
+            .into_iter()
+            .map(|info| {
+                let info = info.ok()?;
+                let pool_id = keccak256(info.poolKey.abi_encode());
+                Some((
+                    info.poolKey,
+                    pool_id,
+                    info_tick(info.info, 8),
+                    info_tick(info.info, 32),
+                ))
+            })
+            .collect();
+
+        // Step 3: Pool prices, position state and fee growth from StateView. The
+        // PoolManager records every position under the PositionManager, salted by token ID.
+        let pool_ids: BTreeSet<B256> = ranges.iter().flatten().map(|range| range.1).collect();
+        let slot0s: BTreeMap<B256, _> = pool_ids
+            .iter()
+            .copied()
+            .zip(
+                provider
+                    .multicall()
+                    .dynamic()
+                    .extend_calls(
+                        pool_ids
+                            .iter()
+                            .map(|&pool_id| self.state_view.getSlot0(pool_id).into_call(true)),
+                    )
+                    .block(block_id)
+                    .aggregate3()
+                    .await?,
+            )
+            .filter_map(|(pool_id, slot0)| Some((pool_id, slot0.ok()?)))
+            .collect();
+        let ranged: Vec<(U256, B256, I24, I24)> = token_ids
+            .iter()
+            .zip(&ranges)
+            .filter_map(|(&token_id, range)| {
+                let (_, pool_id, tick_lower, tick_upper) = range.as_ref()?;
+                Some((
+                    token_id,
+                    *pool_id,
+                    I24::try_from(*tick_lower).ok()?,
+                    I24::try_from(*tick_upper).ok()?,
+                ))
+            })
+            .collect();
+        let mut position_states = provider
+            .multicall()
+            .dynamic()
+            .extend_calls(
+                ranged
+                    .iter()
+                    .map(|&(token_id, pool_id, tick_lower, tick_upper)| {
+                        self.state_view
+                            .getPositionInfo(
+                                pool_id,
+                                *self.position_manager.address(),
+                                tick_lower,
+                                tick_upper,
+                                B256::from(token_id),
+                            )
+                            .into_call(true)
+                    }),
+            )
+            .block(block_id)
+            .aggregate3()
+            .await?
+            .into_iter();
+        let mut fee_growths = provider
+            .multicall()
+            .dynamic()
+            .extend_calls(ranged.iter().map(|&(_, pool_id, tick_lower, tick_upper)| {
+                self.state_view
+                    .getFeeGrowthInside(pool_id, tick_lower, tick_upper)
+                    .into_call(true)
+            }))
+            .block(block_id)
+            .aggregate3()
+            .await?
+            .into_iter();
+
+        let mut reads = Vec::with_capacity(token_ids.len());
+        for (&token_id, range) in token_ids.iter().zip(ranges) {
+            let read = range.and_then(|(key, pool_id, tick_lower, tick_upper)| {
+                // Advance both result iterators before a failed read short-circuits, so later
+                // positions stay paired with their own fee growth
+                let (state, fee_growth) = (position_states.next()?, fee_growths.next()?);
+                let state = state.ok()?;
+                let fee_growth = fee_growth.ok();
+                let slot0 = slot0s.get(&pool_id)?.clone();
+                let position = PositionRead {
+                    key,
+                    pool_id,
+                    tick_lower,
+                    tick_upper,
+                    liquidity: state.liquidity,
+                    fee_growth_inside0_last_x128: state.feeGrowthInside0LastX128,
+                    fee_growth_inside1_last_x128: state.feeGrowthInside1LastX128,
+                };
+                Some((position, slot0, fee_growth))
+            });
+            match read {
+                Some(read) => reads.push((token_id, read)),
+                None if self
+                    .positions
+                    .get(&token_id)
+                    .is_some_and(|pos| pos.owner == owner) => {}
+                None => {
+                    return Err(anyhow::anyhow!(
+                        "failed to read position {} at block {}",
+                        token_id,
+                        synced.number
+                    ))
+                }
+            }
+        }
+
+        // Read metadata of currencies seen for the first time (native is cached at construction)
+        self.tokens
+            .load(
+                reads
+                    .iter()
+                    .flat_map(|(_, (read, _, _))| [read.key.currency0, read.key.currency1]),
+            )
+            .await?;
+
+        // Steps 4-5: Compute amounts and update the BTreeMap
+        for (token_id, (read, slot0, fee_growth)) in reads {
+            let sqrt_price_x96 = U256::from(slot0.sqrtPriceX96);
+            let (sync_status, withdrawable, collectable) = match fee_growth {
+                Some(fee_growth) => (
+                    SyncStatus::Ok,
+                    amounts_for_liquidity(
+                        sqrt_price_x96,
+                        read.tick_lower,
+                        read.tick_upper,
+                        read.liquidity,
+                    ),
+                    (
+                        fees_owed(
+                            fee_growth.feeGrowthInside0X128,
+                            read.fee_growth_inside0_last_x128,
+                            read.liquidity,
+                        ),
+                        fees_owed(
+                            fee_growth.feeGrowthInside1X128,
+                            read.fee_growth_inside1_last_x128,
+                            read.liquidity,
+                        ),
+                    ),
+                ),
+                None => (
+                    SyncStatus::SimulationFailed("getFeeGrowthInside failed".to_string()),
+                    (U256::ZERO, U256::ZERO),
+                    (U256::ZERO, U256::ZERO),
+                ),
+            };
+            let fee = match read.key.fee.to::<u32>() {
+                DYNAMIC_FEE_FLAG => slot0.lpFee.to::<u32>(),
+                fee => fee,
+            };
+            let (Some(token0_metadata), Some(token1_metadata)) = (
+                self.tokens.cached(read.key.currency0).cloned(),
+                self.tokens.cached(read.key.currency1).cloned(),
+            ) else {
+                return Err(anyhow::anyhow!(
+                    "metadata of position {} currencies not loaded",
+                    token_id
+                ));
+            };
+            self.positions.insert(
+                token_id,
+                PositionData {
+                    token_id,
+                    owner,
+                    staked_in: None,
+                    pending_reward: U256::ZERO,
+                    block_number: synced.number,
+                    block_hash: synced.hash,
+                    token0: read.key.currency0,
+                    token1: read.key.currency1,
+                    token0_metadata,
+                    token1_metadata,
+                    fee,
+                    tick_lower: read.tick_lower,
+                    tick_upper: read.tick_upper,
+                    liquidity: read.liquidity,
+                    pool: pool_manager,
+                    pool_id: Some(read.pool_id),
+                    sqrt_price_x96,
+                    tick: slot0.tick.as_i32(),
+                    withdrawable_amount0: withdrawable.0,
+                    withdrawable_amount1: withdrawable.1,
+                    collectable_amount0: collectable.0,
+                    collectable_amount1: collectable.1,
+                    sync_status,
+                },
+            );
+        }
+
+        // Evict positions of this owner that were burned or transferred away
+        self.positions
+            .retain(|token_id, pos| pos.owner != owner || owned.contains(token_id));
+
+        Ok(synced)
+    }
+}
+
+impl PositionSource for UniswapV4PositionManager {
+    fn sync_lp(&mut self, owner: Address) -> impl Future<Output = Result<SyncedBlock>> + Send {
+        UniswapV4PositionManager::sync_lp(self, owner)
+    }
+
+    fn positions_of(&self, owner: Address) -> impl Iterator<Item = &PositionData> {
+        UniswapV4PositionManager::positions_of(self, owner)
+    }
+
+    fn token_metadata(&self, token: Address) -> Option<&TokenMetadata> {
+        UniswapV4PositionManager::token_metadata(self, token)
+    }
+
+    fn ensure_canonical(&self, block: &SyncedBlock) -> impl Future<Output = Result<()>> + Send {
+        UniswapV4PositionManager::ensure_canonical(self, block)
+    }
+}
+
+/// Extracts the sign-extended int24 tick stored at bit `shift` of a packed `PositionInfo`.
+fn info_tick(info: U256, shift: usize) -> i32 {
+    let raw = (info >> shift).as_limbs()[0] as u32 & 0x00ff_ffff;
+    ((raw << 8) as i32) >> 8
+}
@@ -0,0 +1,76 @@
+//! Clean position example: remove all liquidity from a Uniswap V3 position, collect
+//! everything to the signer and burn the NFT in one transaction.
+//!
+//! Usage: clean-position <contract_address> <rpc_url> <token_id> [slippage_bps]
+//!
+//! The signer private key is read from the `PRIVATE_KEY` environment variable.
+
+use std::str::FromStr;
+use std::sync::Arc;
+
+use alloy::network::{Ethereum, EthereumWallet};
+use alloy::primitives::{Address, U256};
+use alloy::providers::{Provider, RootProvider};
+use alloy::signers::local::PrivateKeySigner;
+use anyhow::Result;
+use clients_uniswapv3::{
+    AmmConfig, LiquidityTxConfig, UniswapV3PositionManager, UniswapV3PositionManagerConfig,
+};
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let args: Vec<String> = std::env::args().collect();
+    if args.len() < 4 {
+        eprintln!(
+            "Usage: {} <contract_address> <rpc_url> <token_id> [slippage_bps]",
+            args.first().map(|s| s.as_str()).unwrap_or("clean-position")
+        );
+        std::process::exit(1);
+    }
+
+    let contract_address = Address::from_str(args[1].trim())?;
+    let rpc_url = args[2].trim();
+    let token_id = U256::from_str(args[3].trim())?;
+    let mut tx_config = LiquidityTxConfig::default();
+    if let Some(slippage_bps) = args.get(4) {
+        tx_config.slippage_bps = slippage_bps.trim().parse()?;
+    }
+
+    let signer = PrivateKeySigner::from_str(std::env::var("PRIVATE_KEY")?.trim())?;
+    let recipient = signer.address();
+
+    let provider = Arc::new(RootProvider::<Ethereum>::new_http(rpc_url.parse()?).erased());
+    let chain_id = provider.get_chain_id().await?;
+    let config = UniswapV3PositionManagerConfig {
+        address: contract_address,
+        swap_router: None,
+        confirmations: 0,
+        amm: AmmConfig::known(chain_id, contract_address).unwrap_or_default(),
+    };
+    let manager =
+        UniswapV3PositionManager::new(config, provider).with_wallet(EthereumWallet::from(signer));
+
+    let receipt = manager
+        .close_position(token_id, recipient, &tx_config)
+        .await?;
+    println!(
+        "tx={} block={} gas_used={}",
+        receipt.tx.tx_hash, receipt.tx.block_number, receipt.tx.gas_used
+    );
+    for event in &receipt.decreased {
+        println!(
+            "decreased: token_id={} liquidity={} amount0={} amount1={}",
+            event.token_id, event.liquidity, event.amount0, event.amount1
+        );
+    }
+    for event in &receipt.collected {
+        println!(
+            "collected: token_id={} recipient={} amount0={} amount1={}",
+            event.token_id, event.recipient, event.amount0, event.amount1
+        );
+    }
+    for token_id in &receipt.burned {
+        println!("burned: token_id={}", token_id);
+    }
+    Ok(())
+}
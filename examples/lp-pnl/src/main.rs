@@ -0,0 +1,99 @@
+//! LP PnL example: replay a Uniswap V3 position from its mint block and write a hedged
+//! PnL attribution report (fees, impermanent loss, price exposure and futures income).
+//!
+//! Usage: lp-pnl <contract_address> <rpc_url> <token_id> <base_token_address> <symbol> <binance_api_key> <binance_api_secret> <json|csv> <output_prefix> [mint_block]
+//!
+//! `json` writes `<output_prefix>.json`; `csv` writes `<output_prefix>-summary.csv` and
+//! `<output_prefix>-events.csv`. The RPC endpoint must serve historical state (archive node).
+
+use std::str::FromStr;
+use std::sync::Arc;
+
+use alloy::network::Ethereum;
+use alloy::primitives::{Address, U256};
+use alloy::providers::{Provider, RootProvider};
+use anyhow::{anyhow, Result};
+use clients_binance::{BinancePerpsClient, BinancePerpsClientConfig};
+use clients_uniswapv3::{AmmConfig, UniswapV3PositionManager, UniswapV3PositionManagerConfig};
+use lph::build_pnl_report;
+
+/// Maximum number of blocks covered by one `eth_getLogs` request
+const MAX_BLOCK_RANGE: u64 = 5_000;
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let args: Vec<String> = std::env::args().collect();
+    if args.len() < 10 {
+        eprintln!(
+            "Usage: {} <contract_address> <rpc_url> <token_id> <base_token_address> <symbol> <binance_api_key> <binance_api_secret> <json|csv> <output_prefix> [mint_block]",
+            args.first().map(|s| s.as_str()).unwrap_or("lp-pnl")
+        );
+        std::process::exit(1);
+    }
+
+    let contract_address = Address::from_str(args[1].trim())?;
+    let rpc_url = args[2].trim();
+    let token_id = U256::from_str(args[3].trim())?;
+    let base_token_address = Address::from_str(args[4].trim())?;
+    let symbol = args[5].trim();
+    let api_key = args[6].trim().to_string();
+    let api_secret = args[7].trim().to_string();
+    let format = args[8].trim();
+    let output_prefix = args[9].trim();
+    let mint_block = args.get(10).map(|b| b.trim().parse::<u64>()).transpose()?;
+
+    let provider = Arc::new(RootProvider::<Ethereum>::new_http(rpc_url.parse()?).erased());
+    let chain_id = provider.get_chain_id().await?;
+    let mut uniswap_client = UniswapV3PositionManager::new(
+        UniswapV3PositionManagerConfig {
+            address: contract_address,
+            swap_router: None,
+            confirmations: 0,
+            amm: AmmConfig::known(chain_id, contract_address).unwrap_or_default(),
+        },
+        provider,
+    );
+    let binance_client = BinancePerpsClient::new(
+        Arc::new(reqwest::Client::builder().build()?),
+        BinancePerpsClientConfig {
+            api_key,
+            api_secret,
+            base_url: "https://fapi.binance.com".to_string(),
+        },
+    );
+
+    let report = build_pnl_report(
+        &mut uniswap_client,
+        &binance_client,
+        token_id,
+        base_token_address,
+        symbol,
+        mint_block,
+        MAX_BLOCK_RANGE,
+    )
+    .await?;
+
+    match format {
+        "json" => std::fs::write(format!("{}.json", output_prefix), report.to_json()?)?,
+        "csv" => {
+            std::fs::write(
+                format!("{}-summary.csv", output_prefix),
+                report.summary_csv(),
+            )?;
+            std::fs::write(format!("{}-events.csv", output_prefix), report.events_csv())?;
+        }
+        other => return Err(anyhow!("unknown format {:?}, expected json or csv", other)),
+    }
+
+    println!(
+        "Position {}: LP PnL {:.4} USDT (price {:.4}, IL {:.4}, fees {:.4}), hedge {:.4} USDT, net {:.4} USDT",
+        report.token_id,
+        report.lp_pnl_usdt,
+        report.price_exposure_usdt,
+        report.impermanent_loss_usdt,
+        report.fees_usdt,
+        report.hedge_pnl_usdt,
+        report.net_pnl_usdt
+    );
+    Ok(())
+}
@@ -0,0 +1,285 @@
+//! LPH backtest example: replay historical pool prices, perp mark prices and funding through
+//! the hedge decision of the LPH strategy, and write the PnL series and a threshold sweep.
+//!
+//! Usage: lph-backtest <config.toml>
+//!
+//! Pool prices come from a CSV file or from the pool's Swap events (the RPC endpoint must serve
+//! the requested block range). CSV files have a header row followed by `timestamp,price`
+//! (mark prices, pool prices) or `timestamp,funding_rate` (funding) rows, timestamps in
+//! seconds since Unix epoch. See `lph-backtest.example.toml` for the config layout.
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use alloy::network::Ethereum;
+use alloy::primitives::Address;
+use alloy::providers::{Provider, RootProvider};
+use anyhow::{bail, Context, Result};
+use clients_uniswapv3::pool_price_history;
+use lph::{
+    run_backtest, summaries_csv, sweep, BacktestConfig, BacktestData, BacktestPosition,
+    BacktestSummary, FundingSample, LpGreeks, PriceSample,
+};
+use serde::Deserialize;
+
+/// Layout of the TOML config file
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Config {
+    /// LP position replayed
+    position: BacktestPosition,
+    /// Market data sources
+    data: DataSection,
+    /// Hedge parameters and execution model
+    backtest: BacktestConfig,
+    /// Threshold grid; `None` runs the `[backtest]` thresholds only
+    #[serde(default)]
+    sweep: Option<SweepSection>,
+    /// Output files
+    #[serde(default)]
+    output: OutputSection,
+}
+
+/// Market data sources
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct DataSection {
+    /// CSV of pool prices (`timestamp,price`)
+    #[serde(default)]
+    pool_prices_csv: Option<PathBuf>,
+    /// Swap events of the pool, used when `pool_prices_csv` is not set
+    #[serde(default)]
+    swap_events: Option<SwapEventsSection>,
+    /// CSV of perp mark prices (`timestamp,price`); without it the pool price is used
+    #[serde(default)]
+    mark_prices_csv: Option<PathBuf>,
+    /// CSV of funding settlements (`timestamp,funding_rate`)
+    #[serde(default)]
+    funding_csv: Option<PathBuf>,
+}
+
+/// Pool prices read from Swap events
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SwapEventsSection {
+    /// RPC endpoint of the pool's chain
+    rpc_url: String,
+    /// Pool address
+    pool: Address,
+    /// First block to read
+    from_block: u64,
+    /// Last block to read
+    to_block: u64,
+    /// Maximum number of blocks covered by one `eth_getLogs` request
+    #[serde(default = "default_max_block_range")]
+    max_block_range: u64,
+}
+
+fn default_max_block_range() -> u64 {
+    5_000
+}
+
+/// Threshold grid of the parameter sweep
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SweepSection {
+    /// Values of `base_delta_ratio_threshold` (n)
+    base_delta_ratio_thresholds: Vec<f64>,
+    /// Values of `base_delta_threshold` (m)
+    base_delta_thresholds: Vec<f64>,
+}
+
+/// Output files; unset files are not written
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct OutputSection {
+    /// Series of the `[backtest]` run as CSV
+    #[serde(default)]
+    series_csv: Option<PathBuf>,
+    /// Series and totals of the `[backtest]` run as JSON
+    #[serde(default)]
+    result_json: Option<PathBuf>,
+    /// One row per sweep combination
+    #[serde(default)]
+    sweep_csv: Option<PathBuf>,
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let args: Vec<String> = std::env::args().collect();
+    if args.len() < 2 {
+        eprintln!(
+            "Usage: {} <config.toml>",
+            args.first().map(|s| s.as_str()).unwrap_or("lph-backtest")
+        );
+        std::process::exit(1);
+    }
+
+    let path = Path::new(args[1].trim());
+    let text = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read config file {}", path.display()))?;
+    let config: Config =
+        toml::from_str(&text).with_context(|| format!("Invalid config file {}", path.display()))?;
+    config.backtest.validate().context("backtest")?;
+
+    let greeks = config.position.greeks();
+    let mut data = BacktestData {
+        pool_prices: load_pool_prices(&config.data, &greeks).await?,
+        mark_prices: match &config.data.mark_prices_csv {
+            Some(path) => read_price_csv(path)?,
+            None => Vec::new(),
+        },
+        funding: match &config.data.funding_csv {
+            Some(path) => read_csv(path, |timestamp, funding_rate| FundingSample {
+                timestamp,
+                funding_rate,
+            })?,
+            None => Vec::new(),
+        },
+    };
+    data.sort();
+    println!(
+        "Loaded {} pool prices, {} mark prices, {} funding settlements",
+        data.pool_prices.len(),
+        data.mark_prices.len(),
+        data.funding.len()
+    );
+
+    let result = run_backtest(&greeks, &data, &config.backtest)?;
+    print_summaries(
+        std::slice::from_ref(&result.summary),
+        config.backtest.lp_fee_bps,
+    );
+    if let Some(path) = &config.output.series_csv {
+        write(path, &result.series_csv())?;
+    }
+    if let Some(path) = &config.output.result_json {
+        write(path, &result.to_json()?)?;
+    }
+
+    if let Some(grid) = &config.sweep {
+        let summaries = sweep(
+            &greeks,
+            &data,
+            &config.backtest,
+            &grid.base_delta_ratio_thresholds,
+            &grid.base_delta_thresholds,
+        )?;
+        println!();
+        println!("Sweep of {} combinations:", summaries.len());
+        print_summaries(&summaries, config.backtest.lp_fee_bps);
+        if let Some(path) = &config.output.sweep_csv {
+            write(path, &summaries_csv(&summaries))?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Reads the pool prices from the CSV file or from the pool's Swap events
+async fn load_pool_prices(data: &DataSection, greeks: &LpGreeks) -> Result<Vec<PriceSample>> {
+    match (&data.pool_prices_csv, &data.swap_events) {
+        (Some(path), None) => read_price_csv(path),
+        (None, Some(swaps)) => {
+            let provider =
+                Arc::new(RootProvider::<Ethereum>::new_http(swaps.rpc_url.parse()?).erased());
+            let points = pool_price_history(
+                &provider,
+                swaps.pool,
+                swaps.from_block,
+                swaps.to_block,
+                swaps.max_block_range,
+            )
+            .await?;
+            Ok(points
+                .iter()
+                .map(|point| PriceSample {
+                    timestamp: point.timestamp,
+                    price: greeks.price_from_sqrt_price_x96(point.sqrt_price_x96),
+                })
+                .collect())
+        }
+        _ => bail!("data: set exactly one of pool_prices_csv and swap_events"),
+    }
+}
+
+/// Reads a `timestamp,price` CSV file
+fn read_price_csv(path: &Path) -> Result<Vec<PriceSample>> {
+    read_csv(path, |timestamp, price| PriceSample { timestamp, price })
+}
+
+/// Reads a two-column CSV file, skipping the header row and blank lines
+///
+/// # Arguments
+/// * `path` - CSV file
+/// * `sample` - Builds a sample from the timestamp and the value of a row
+fn read_csv<T>(path: &Path, sample: impl Fn(u64, f64) -> T) -> Result<Vec<T>> {
+    let text = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    let mut samples = Vec::new();
+    for (i, line) in text.lines().enumerate().skip(1) {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let parsed = line.split_once(',').and_then(|(timestamp, value)| {
+            Some(sample(
+                timestamp.trim().parse().ok()?,
+                value.trim().parse().ok()?,
+            ))
+        });
+        match parsed {
+            Some(parsed) => samples.push(parsed),
+            None => bail!("{}:{}: invalid row {:?}", path.display(), i + 1, line),
+        }
+    }
+    Ok(samples)
+}
+
+fn write(path: &Path, contents: &str) -> Result<()> {
+    std::fs::write(path, contents)
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    println!("Wrote {}", path.display());
+    Ok(())
+}
+
+/// Prints one row per run, and a note when LP fee income is excluded
+fn print_summaries(summaries: &[BacktestSummary], lp_fee_bps: f64) {
+    println!(
+        "{:>8} {:>10} {:>12} {:>10} {:>12} {:>10} {:>10} {:>12} {:>14} {:>7} {:>12} {:>12}",
+        "n",
+        "m",
+        "lp_pnl",
+        "lp_fees",
+        "futures_pnl",
+        "fees",
+        "funding",
+        "hedged_pnl",
+        "turnover",
+        "trades",
+        "mean_err",
+        "max_err"
+    );
+    for s in summaries {
+        println!(
+            "{:>8} {:>10} {:>12.2} {:>10.2} {:>12.2} {:>10.2} {:>10.2} {:>12.2} {:>14.2} {:>7} {:>12.2} {:>12.2}",
+            s.base_delta_ratio_threshold,
+            s.base_delta_threshold,
+            s.lp_pnl_usdt,
+            s.lp_fees_usdt,
+            s.futures_pnl_usdt,
+            s.trading_fees_usdt,
+            s.funding_usdt,
+            s.hedged_pnl_usdt,
+            s.turnover_usdt,
+            s.trades,
+            s.mean_abs_hedge_error_usdt,
+            s.max_abs_hedge_error_usdt
+        );
+    }
+    if lp_fee_bps == 0.0 {
+        println!(
+            "LP fee income excluded (set backtest.lp_fee_bps to the pool fee tier to estimate it)"
+        );
+    }
+}
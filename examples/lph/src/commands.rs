@@ -0,0 +1,146 @@
+//! Telegram commands toggling the daemon-wide kill switch.
+//!
+//! `/kill [reason]` creates the kill switch file and cancels open orders on every hedged
+//! symbol; `/resume` removes the file. Only messages from the configured chat are accepted.
+//! The file is the state: it survives restarts and can be created or removed by hand as well.
+
+use anyhow::{Context, Result};
+use clients_binance::BinancePerpsClient;
+use clients_telegrambot::{TelegramBot, Update};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::sync::watch;
+use tokio::time::Duration;
+
+/// Seconds Telegram holds a `getUpdates` request open
+const POLL_TIMEOUT_SECS: u64 = 30;
+/// Delay before polling again after a failed poll
+const RETRY_DELAY: Duration = Duration::from_secs(5);
+
+/// Command accepted from the Telegram chat
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Command {
+    /// Engage the kill switch, with an optional reason
+    Kill(Option<String>),
+    /// Release the kill switch
+    Resume,
+}
+
+/// Listens for kill switch commands until `shutdown` flips to `true`
+///
+/// Messages sent while the daemon was down are skipped, so a stale `/resume` cannot release
+/// a kill switch engaged later.
+///
+/// # Arguments
+/// * `telegram` - Shared Telegram bot
+/// * `file` - Kill switch file
+/// * `binance_client` - Shared Binance futures client
+/// * `symbols` - Hedged symbols whose open orders are cancelled on `/kill`
+/// * `shutdown` - Receiver set to `true` when the daemon stops
+pub async fn run_kill_switch_commands(
+    telegram: Arc<TelegramBot>,
+    file: PathBuf,
+    binance_client: BinancePerpsClient,
+    symbols: Vec<String>,
+    mut shutdown: watch::Receiver<bool>,
+) {
+    let mut offset = None;
+    while !*shutdown.borrow() {
+        let poll = match offset {
+            // Confirm the backlog without acting on it
+            None => telegram.get_updates(-1, 0).await.map(|updates| {
+                offset = Some(updates.last().map_or(0, |update| update.update_id + 1));
+                Vec::new()
+            }),
+            Some(next) => tokio::select! {
+                result = telegram.get_updates(next, POLL_TIMEOUT_SECS) => result,
+                _ = shutdown.wait_for(|stop| *stop) => return,
+            },
+        };
+        let updates = match poll {
+            Ok(updates) => updates,
+            Err(e) => {
+                println!("Failed to poll Telegram commands: {:#}", e);
+                tokio::select! {
+                    _ = tokio::time::sleep(RETRY_DELAY) => {}
+                    _ = shutdown.wait_for(|stop| *stop) => return,
+                }
+                continue;
+            }
+        };
+        for update in updates {
+            offset = Some(update.update_id + 1);
+            let Some(command) = parse_command(&update, telegram.chat_id()) else {
+                continue;
+            };
+            let reply = match command {
+                Command::Kill(reason) => engage(&file, reason, &binance_client, &symbols).await,
+                Command::Resume => release(&file),
+            };
+            let reply = reply.unwrap_or_else(|e| format!("Kill switch command failed: {:#}", e));
+            println!("{}", reply);
+            if let Err(e) = telegram.push_message(&reply).await {
+                println!("Failed to push kill switch reply: {:#}", e);
+            }
+        }
+    }
+}
+
+/// Parses a command from a message of the configured chat; other messages are ignored
+fn parse_command(update: &Update, chat_id: &str) -> Option<Command> {
+    let message = update.message.as_ref()?;
+    if message.chat.id.to_string() != chat_id {
+        return None;
+    }
+    let text = message.text.as_deref()?.trim();
+    let (command, argument) = text.split_once(char::is_whitespace).unwrap_or((text, ""));
+    // Commands may be addressed to the bot as `/kill@name_bot`
+    let command = command.split('@').next().unwrap_or_default();
+    match command {
+        "/kill" => {
+            let reason = argument.trim();
+            Some(Command::Kill(
+                (!reason.is_empty()).then(|| reason.to_string()),
+            ))
+        }
+        "/resume" => Some(Command::Resume),
+        _ => None,
+    }
+}
+
+/// Creates the kill switch file, then cancels open orders on every hedged symbol
+///
+/// # Returns
+/// The reply for the chat; cancellation failures are listed in it
+async fn engage(
+    file: &Path,
+    reason: Option<String>,
+    binance_client: &BinancePerpsClient,
+    symbols: &[String],
+) -> Result<String> {
+    let reason = reason.unwrap_or_else(|| "engaged from Telegram".to_string());
+    std::fs::write(file, format!("{}\n", reason))
+        .with_context(|| format!("failed to write {}", file.display()))?;
+    let mut reply = format!("Kill switch engaged ({}): {}", file.display(), reason);
+    for symbol in symbols {
+        match binance_client.cancel_all_open_orders(symbol).await {
+            Ok(()) => reply.push_str(&format!("\nOpen orders on {} cancelled", symbol)),
+            Err(e) => reply.push_str(&format!(
+                "\nFailed to cancel open orders on {}: {:#}",
+                symbol, e
+            )),
+        }
+    }
+    Ok(reply)
+}
+
+/// Removes the kill switch file
+fn release(file: &Path) -> Result<String> {
+    match std::fs::remove_file(file) {
+        Ok(()) => Ok(format!("Kill switch released ({})", file.display())),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            Ok(format!("Kill switch was not engaged ({})", file.display()))
+        }
+        Err(e) => Err(e).with_context(|| format!("failed to remove {}", file.display())),
+    }
+}
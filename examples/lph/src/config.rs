@@ -0,0 +1,436 @@
+//! Config file of the LPH bot.
+
+use alloy::primitives::Address;
+use alloy::signers::local::PrivateKeySigner;
+use anyhow::{anyhow, bail, Context, Result};
+use clients_rpc::RpcPoolConfig;
+use clients_uniswapv3::AmmConfig;
+use lph::{CompoundMode, LPHStrategyConfig};
+use serde::Deserialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+
+/// Top-level layout of the TOML config file
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DaemonConfig {
+    /// Binance futures API settings, shared by every strategy
+    pub binance: BinanceSection,
+    /// Telegram report settings, shared by every strategy
+    pub telegram: TelegramSection,
+    /// Retry backoff, alerting and safe mode of the strategy loops
+    #[serde(default)]
+    pub resilience: ResilienceSection,
+    /// Kill switch shared by every strategy; `None` leaves it to each strategy's risk limits
+    #[serde(default)]
+    pub kill_switch: Option<KillSwitchSection>,
+    /// SQLite state store shared by every strategy; `None` keeps no records
+    #[serde(default)]
+    pub store: Option<StoreSection>,
+    /// Prometheus metrics and health endpoint; `None` serves nothing
+    #[serde(default)]
+    pub metrics: Option<MetricsSection>,
+    /// RPC endpoints per chain name; strategies on the same chain share one transport
+    pub chains: BTreeMap<String, RpcPoolConfig>,
+    /// Strategies run concurrently, one task each
+    pub strategies: Vec<StrategySection>,
+}
+
+/// One hedged LP setup
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct StrategySection {
+    /// Unique name used in logs and reports
+    pub name: String,
+    /// Key of the `[chains]` entry the positions live on
+    pub chain: String,
+    /// Place hedge orders after each status report; `false` only reports
+    #[serde(default)]
+    pub hedge: bool,
+    /// Label of the BASE asset in reports; defaults to the futures symbol
+    #[serde(default)]
+    pub base_label: Option<String>,
+    /// PositionManager holding the LP positions
+    pub position_manager: PositionManagerSection,
+    /// Loop timing
+    #[serde(default)]
+    pub intervals: IntervalSection,
+    /// Private key of `strategy.owner`, signing the transactions of `strategy.rebalance` and
+    /// `strategy.compound`; required with either of them and rejected without
+    #[serde(default)]
+    pub wallet: Option<Secret>,
+    /// Strategy parameters, including `[strategies.strategy.risk]` limits
+    pub strategy: LPHStrategyConfig,
+}
+
+/// PositionManager settings
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PositionManagerSection {
+    /// NonfungiblePositionManager address
+    pub address: Address,
+    /// SwapRouter address; `None` disables swaps
+    #[serde(default)]
+    pub swap_router: Option<Address>,
+    /// Blocks a position read must be behind the chain head
+    #[serde(default = "default_confirmations")]
+    pub confirmations: u64,
+    /// AMM fork settings; `None` uses the known deployment of the chain, if any
+    #[serde(default)]
+    pub amm: Option<AmmConfig>,
+}
+
+/// Binance futures API settings
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BinanceSection {
+    /// Base URL for API endpoints
+    #[serde(default = "default_binance_base_url")]
+    pub base_url: String,
+    /// API key
+    pub api_key: Secret,
+    /// API secret
+    pub api_secret: Secret,
+}
+
+/// Telegram report settings
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TelegramSection {
+    /// Bot token
+    pub bot_token: Secret,
+    /// Chat receiving the status reports
+    pub chat_id: String,
+}
+
+/// Loop timing
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct IntervalSection {
+    /// Seconds between status reports
+    #[serde(default = "default_status_secs")]
+    pub status_secs: u64,
+    /// Seconds between log polls that can bring a status report forward
+    #[serde(default = "default_poll_secs")]
+    pub poll_secs: u64,
+    /// Seconds between re-centering and compounding checks, run after a status report
+    #[serde(default = "default_onchain_secs")]
+    pub onchain_secs: u64,
+}
+
+impl Default for IntervalSection {
+    fn default() -> Self {
+        Self {
+            status_secs: default_status_secs(),
+            poll_secs: default_poll_secs(),
+            onchain_secs: default_onchain_secs(),
+        }
+    }
+}
+
+/// Retry backoff, alerting and safe mode of the strategy loops
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ResilienceSection {
+    /// Seconds before the first retry after a transient error (jittered by ±50%)
+    #[serde(default = "default_initial_backoff_secs")]
+    pub initial_backoff_secs: u64,
+    /// Upper bound of the doubling retry delay, in seconds
+    #[serde(default = "default_max_backoff_secs")]
+    pub max_backoff_secs: u64,
+    /// Consecutive failed cycles after which an alert is pushed
+    #[serde(default = "default_alert_after_failures")]
+    pub alert_after_failures: u32,
+    /// Clean snapshots required before a strategy leaves safe mode and hedges again
+    #[serde(default = "default_safe_mode_clean_snapshots")]
+    pub safe_mode_clean_snapshots: u32,
+}
+
+impl Default for ResilienceSection {
+    fn default() -> Self {
+        Self {
+            initial_backoff_secs: default_initial_backoff_secs(),
+            max_backoff_secs: default_max_backoff_secs(),
+            alert_after_failures: default_alert_after_failures(),
+            safe_mode_clean_snapshots: default_safe_mode_clean_snapshots(),
+        }
+    }
+}
+
+/// Daemon-wide kill switch
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct KillSwitchSection {
+    /// While this file exists no strategy places orders; used by every strategy that does not
+    /// set its own `risk.kill_switch_file`
+    pub file: PathBuf,
+    /// Accept `/kill [reason]` and `/resume` from the configured Telegram chat
+    #[serde(default = "default_telegram_commands")]
+    pub telegram_commands: bool,
+}
+
+/// SQLite state store of snapshots, decisions, orders, fills and income
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct StoreSection {
+    /// Database file, created if missing
+    pub path: PathBuf,
+}
+
+/// HTTP endpoint serving `/metrics` and `/healthz`
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MetricsSection {
+    /// Socket address to listen on (e.g. `0.0.0.0:9100`)
+    pub listen: SocketAddr,
+    /// `/healthz` fails once a strategy has not completed a cycle for this many seconds
+    #[serde(default = "default_max_cycle_age_secs")]
+    pub max_cycle_age_secs: u64,
+}
+
+/// Where a secret is read from; secrets are never written inline in the config file
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+pub enum Secret {
+    /// Name of an environment variable holding the secret
+    Env(String),
+    /// Path of a file holding the secret (surrounding whitespace is trimmed)
+    File(PathBuf),
+}
+
+impl Secret {
+    /// Reads the secret
+    ///
+    /// # Arguments
+    /// * `field` - Config field the secret belongs to, used in error messages
+    pub fn resolve(&self, field: &str) -> Result<String> {
+        let value = match self {
+            Secret::Env(name) => std::env::var(name)
+                .map_err(|_| anyhow!("{}: environment variable {} is not set", field, name))?,
+            Secret::File(path) => std::fs::read_to_string(path)
+                .with_context(|| format!("{}: failed to read {}", field, path.display()))?,
+        };
+        let value = value.trim();
+        if value.is_empty() {
+            bail!("{}: secret is empty", field);
+        }
+        Ok(value.to_string())
+    }
+}
+
+impl StrategySection {
+    /// Whether the strategy sends on-chain transactions (re-centering or compounding)
+    pub fn has_onchain_actions(&self) -> bool {
+        self.strategy.rebalance.is_some() || self.strategy.compound.is_some()
+    }
+
+    /// Reads the wallet key
+    ///
+    /// # Returns
+    /// The signer, `None` without a `wallet`, or an error if the key cannot be read or
+    /// parsed or does not belong to `strategy.owner`
+    pub fn signer(&self) -> Result<Option<PrivateKeySigner>> {
+        let Some(wallet) = &self.wallet else {
+            return Ok(None);
+        };
+        let field = format!("strategies[{}].wallet", self.name);
+        let signer: PrivateKeySigner = wallet
+            .resolve(&field)?
+            .parse()
+            .map_err(|e| anyhow!("{}: invalid private key: {}", field, e))?;
+        if signer.address() != self.strategy.owner {
+            bail!(
+                "{}: key of {} does not belong to strategy.owner {}",
+                field,
+                signer.address(),
+                self.strategy.owner
+            );
+        }
+        Ok(Some(signer))
+    }
+}
+
+fn default_confirmations() -> u64 {
+    3
+}
+
+fn default_binance_base_url() -> String {
+    "https://fapi.binance.com".to_string()
+}
+
+fn default_status_secs() -> u64 {
+    90
+}
+
+fn default_poll_secs() -> u64 {
+    6
+}
+
+fn default_onchain_secs() -> u64 {
+    600
+}
+
+fn default_telegram_commands() -> bool {
+    true
+}
+
+fn default_max_cycle_age_secs() -> u64 {
+    300
+}
+
+fn default_initial_backoff_secs() -> u64 {
+    2
+}
+
+fn default_max_backoff_secs() -> u64 {
+    120
+}
+
+fn default_alert_after_failures() -> u32 {
+    5
+}
+
+fn default_safe_mode_clean_snapshots() -> u32 {
+    2
+}
+
+impl DaemonConfig {
+    /// Reads and validates a config file
+    ///
+    /// Strategies without their own `risk.kill_switch_file` use the `[kill_switch]` file.
+    ///
+    /// # Arguments
+    /// * `path` - Path of the TOML config file
+    ///
+    /// # Returns
+    /// The parsed config, or an error naming the offending field
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read config file {}", path.display()))?;
+        let mut config: DaemonConfig = toml::from_str(&text)
+            .with_context(|| format!("Invalid config file {}", path.display()))?;
+        if let Some(kill_switch) = &config.kill_switch {
+            for section in &mut config.strategies {
+                let risk = &mut section.strategy.risk;
+                if risk.kill_switch_file.is_none() {
+                    risk.kill_switch_file = Some(kill_switch.file.clone());
+                }
+            }
+        }
+        config
+            .validate()
+            .with_context(|| format!("Invalid config file {}", path.display()))?;
+        Ok(config)
+    }
+
+    /// Checks the sections for values the daemon cannot run with
+    fn validate(&self) -> Result<()> {
+        if self.telegram.chat_id.trim().is_empty() {
+            bail!("telegram.chat_id: must not be empty");
+        }
+        let resilience = &self.resilience;
+        if resilience.initial_backoff_secs == 0 {
+            bail!("resilience.initial_backoff_secs: must be positive");
+        }
+        if resilience.max_backoff_secs < resilience.initial_backoff_secs {
+            bail!(
+                "resilience.max_backoff_secs: must be at least initial_backoff_secs ({}), got {}",
+                resilience.initial_backoff_secs,
+                resilience.max_backoff_secs
+            );
+        }
+        if resilience.alert_after_failures == 0 {
+            bail!("resilience.alert_after_failures: must be positive");
+        }
+        if resilience.safe_mode_clean_snapshots == 0 {
+            bail!("resilience.safe_mode_clean_snapshots: must be positive");
+        }
+        if let Some(kill_switch) = &self.kill_switch {
+            if kill_switch.file.as_os_str().is_empty() {
+                bail!("kill_switch.file: must not be empty");
+            }
+        }
+        if let Some(metrics) = &self.metrics {
+            if metrics.max_cycle_age_secs == 0 {
+                bail!("metrics.max_cycle_age_secs: must be positive");
+            }
+        }
+        for (name, rpc) in &self.chains {
+            if rpc.urls.is_empty() {
+                bail!("chains.{}.urls: at least one endpoint is required", name);
+            }
+            if rpc.quorum == 0 || rpc.quorum > rpc.urls.len() {
+                bail!(
+                    "chains.{}.quorum: must be between 1 and the number of urls ({}), got {}",
+                    name,
+                    rpc.urls.len(),
+                    rpc.quorum
+                );
+            }
+        }
+        if self.strategies.is_empty() {
+            bail!("strategies: at least one strategy is required");
+        }
+        let mut names = BTreeSet::new();
+        for section in &self.strategies {
+            let name = &section.name;
+            if name.trim().is_empty() {
+                bail!("strategies.name: must not be empty");
+            }
+            if !names.insert(name.as_str()) {
+                bail!("strategies.name: duplicate name {}", name);
+            }
+            if !self.chains.contains_key(&section.chain) {
+                bail!(
+                    "strategies[{}].chain: unknown chain {}",
+                    name,
+                    section.chain
+                );
+            }
+            if section.intervals.status_secs == 0 {
+                bail!(
+                    "strategies[{}].intervals.status_secs: must be positive",
+                    name
+                );
+            }
+            if section.intervals.poll_secs == 0 {
+                bail!("strategies[{}].intervals.poll_secs: must be positive", name);
+            }
+            if section.intervals.onchain_secs == 0 {
+                bail!(
+                    "strategies[{}].intervals.onchain_secs: must be positive",
+                    name
+                );
+            }
+            section
+                .strategy
+                .validate()
+                .map_err(|e| anyhow!("strategies[{}].strategy.{}", name, e))?;
+            match (section.has_onchain_actions(), &section.wallet) {
+                (true, None) => bail!(
+                    "strategies[{}].wallet: required with strategy.rebalance or strategy.compound",
+                    name
+                ),
+                (false, Some(_)) => bail!(
+                    "strategies[{}].wallet: only used with strategy.rebalance or strategy.compound",
+                    name
+                ),
+                _ => {}
+            }
+            // Re-centering and reinvesting re-hedge the changed LP amount in the same cycle
+            let reinvests = section
+                .strategy
+                .compound
+                .as_ref()
+                .is_some_and(|compound| compound.mode == CompoundMode::Reinvest);
+            if (section.strategy.rebalance.is_some() || reinvests) && !section.hedge {
+                bail!(
+                    "strategies[{}].hedge: must be true with strategy.rebalance or a reinvesting strategy.compound, which re-hedge in the same cycle",
+                    name
+                );
+            }
+        }
+        Ok(())
+    }
+}
@@ -1,76 +1,196 @@
-//! LPH example: run LPH monitor in a loop every 1 minute and push the monitoring message via Telegram.
+//! LPH daemon: runs every configured LPH strategy as an independent task on shared Binance,
+//! Telegram and per-chain RPC clients. Each strategy pushes its monitoring message via
+//! Telegram every status interval, or earlier when position or pool logs show a significant
+//! change, and optionally hedges after each report.
 //!
-//! Usage: lph <owner_address> <contract_address> <rpc_url> <binance_api_key> <binance_api_secret> <telegram_bot_key> <telegram_chat_id>
+//! Usage: lph [config_path]
 //!
-//! Symbol and token addresses are fixed: BNBUSDC, WBNB, USDT (BSC).
+//! `config_path` defaults to `lph.toml`; see `lph.example.toml` for the layout. Errors never
+//! stop the process: transient failures are retried with jittered backoff and escalated by
+//! alert, uncertain state suspends orders (safe mode), and a fatal error stops only the
+//! strategy it occurred in. On SIGTERM or Ctrl-C the strategies finish their current cycle,
+//! then open orders on every hedged symbol are cancelled before the process exits.
+//!
+//! Strategies with `[strategies.strategy.rebalance]` or `[strategies.strategy.compound]`
+//! re-centre and compound their positions every `onchain_secs`, signing with their `wallet`.
+//!
+//! Strategies with `[strategies.strategy.paper_trading]` fill their orders on a simulated
+//! account and never place or cancel orders on Binance.
+//!
+//! With a `[kill_switch]` section, no strategy places orders while its file exists; the file
+//! can be created with `/kill [reason]` and removed with `/resume` from the Telegram chat.
+//!
+//! With a `[store]` section, snapshots, decisions, orders, fills and Binance income are
+//! recorded in a SQLite file, and orders a crash left unconfirmed are reconciled on start.
+//!
+//! With a `[metrics]` section, `/metrics` serves Prometheus metrics of every strategy and of
+//! the RPC and Binance clients, and `/healthz` reports whether every strategy completed a
+//! cycle recently.
+
+mod commands;
+mod config;
+mod metrics;
+mod resilience;
+mod runner;
 
-use alloy::network::Ethereum;
-use alloy::primitives::Address;
-use alloy::providers::{Provider, RootProvider};
+use alloy::network::EthereumWallet;
 use clients_binance::BinancePerpsClient;
+use clients_rpc::FailoverTransport;
 use clients_telegrambot::TelegramBot;
-use clients_uniswapv3::UniswapV3PositionManager;
-use lph::{LPHStrategy, LPHStrategyConfig};
-use std::str::FromStr;
+use config::DaemonConfig;
+use lph::{SqliteStore, StateStore};
+use metrics::Metrics;
+use runner::StrategyRunner;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::PathBuf;
 use std::sync::Arc;
-use tokio::time::Duration;
+use tokio::sync::watch;
 
-const SYMBOL: &str = "BNBUSDC";
-const BASE_TOKEN_ADDRESS: &str = "0xbb4CdB9CBd36B01bD1cBaEBF2De08d9173bc095c";
-const USDT_TOKEN_ADDRESS: &str = "0x55d398326f99059fF775485246999027B3197955";
+const DEFAULT_CONFIG_PATH: &str = "lph.toml";
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let args: Vec<String> = std::env::args().collect();
-    if args.len() < 8 {
-        eprintln!(
-            "Usage: {} <owner_address> <contract_address> <rpc_url> <binance_api_key> <binance_api_secret> <telegram_bot_key> <telegram_chat_id>",
-            args.first().map(|s| s.as_str()).unwrap_or("lph")
-        );
-        std::process::exit(1);
-    }
-
-    let owner = Address::from_str(args[1].trim())?;
-    let contract_address = Address::from_str(args[2].trim())?;
-    let rpc_url = args[3].trim();
-    let api_key = args[4].trim().to_string();
-    let api_secret = args[5].trim().to_string();
-    let telegram_bot_key = args[6].trim().to_string();
-    let telegram_chat_id = args[7].trim().to_string();
-    let symbol = SYMBOL.to_string();
-    let base_token_address = Address::from_str(BASE_TOKEN_ADDRESS)?;
-    let usdt_token_address = Address::from_str(USDT_TOKEN_ADDRESS)?;
+    let config_path = std::env::args()
+        .nth(1)
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from(DEFAULT_CONFIG_PATH));
+    let config = DaemonConfig::load(&config_path)?;
 
     let client = reqwest::Client::builder().build()?;
     let client = Arc::new(client);
     let perps_config = clients_binance::BinancePerpsClientConfig {
-        api_key,
-        api_secret,
-        base_url: "https://fapi.binance.com".to_string(),
+        api_key: config.binance.api_key.resolve("binance.api_key")?,
+        api_secret: config.binance.api_secret.resolve("binance.api_secret")?,
+        base_url: config.binance.base_url.clone(),
     };
     let binance_client = BinancePerpsClient::new(Arc::clone(&client), perps_config);
+    let telegram = Arc::new(TelegramBot::new(
+        config.telegram.bot_token.resolve("telegram.bot_token")?,
+        config.telegram.chat_id.clone(),
+    ));
 
-    let provider = Arc::new(RootProvider::<Ethereum>::new_http(rpc_url.parse()?).erased());
-    let uniswap_config = clients_uniswapv3::UniswapV3PositionManagerConfig {
-        address: contract_address,
+    let store = match &config.store {
+        Some(section) => {
+            println!("Recording state to {}", section.path.display());
+            Some(Arc::new(SqliteStore::open(&section.path)?) as Arc<dyn StateStore>)
+        }
+        None => None,
     };
-    let uniswap_client = UniswapV3PositionManager::new(uniswap_config, provider);
 
-    let config = LPHStrategyConfig {
-        owner,
-        symbol,
-        base_token_address,
-        usdt_token_address,
-        base_delta_ratio_threshold: 0.01,
-        base_delta_threshold: 0.001,
-    };
-    let mut monitor = LPHStrategy::new(config, uniswap_client, binance_client);
-    let telegram = TelegramBot::new(telegram_bot_key, telegram_chat_id);
+    // Only chains some strategy uses get a transport
+    let mut transports = BTreeMap::new();
+    for section in &config.strategies {
+        if !transports.contains_key(&section.chain) {
+            let transport = FailoverTransport::new(config.chains[&section.chain].clone())?;
+            transports.insert(section.chain.clone(), transport);
+        }
+    }
+
+    let metrics = Arc::new(Metrics::new(
+        config
+            .strategies
+            .iter()
+            .map(|section| section.name.as_str()),
+        transports.clone(),
+        binance_client.clone(),
+    ));
+
+    let (shutdown_tx, shutdown_rx) = watch::channel(false);
+    let mut tasks = Vec::new();
+    if let Some(section) = &config.metrics {
+        let listener = tokio::net::TcpListener::bind(section.listen).await?;
+        println!("Serving /metrics and /healthz on {}", section.listen);
+        let server = metrics::serve(
+            listener,
+            Arc::clone(&metrics),
+            std::time::Duration::from_secs(section.max_cycle_age_secs),
+            shutdown_rx.clone(),
+        );
+        tasks.push(tokio::spawn(async move {
+            if let Err(e) = server.await {
+                println!("{:#}", e);
+            }
+        }));
+    }
+    for section in &config.strategies {
+        let mut runner = StrategyRunner::new(
+            section.clone(),
+            config.resilience.clone(),
+            transports[&section.chain].clone(),
+            binance_client.clone(),
+            Arc::clone(&telegram),
+            store.clone(),
+            Arc::clone(&metrics),
+        );
+        if let Some(signer) = section.signer()? {
+            runner = runner.with_wallet(EthereumWallet::from(signer));
+        }
+        let mode = if section.strategy.paper_trading.is_some() {
+            " (paper trading)"
+        } else {
+            ""
+        };
+        println!(
+            "[{}] starting on chain {}{}",
+            runner.name(),
+            section.chain,
+            mode
+        );
+        tasks.push(tokio::spawn(runner.run_with_restarts(shutdown_rx.clone())));
+    }
+
+    // Paper trading strategies never place orders on Binance
+    let hedged_symbols: BTreeSet<&str> = config
+        .strategies
+        .iter()
+        .filter(|section| section.hedge && section.strategy.paper_trading.is_none())
+        .map(|section| section.strategy.symbol.as_str())
+        .collect();
+    if let Some(kill_switch) = config.kill_switch.as_ref().filter(|k| k.telegram_commands) {
+        println!(
+            "Accepting /kill and /resume for kill switch {}",
+            kill_switch.file.display()
+        );
+        tasks.push(tokio::spawn(commands::run_kill_switch_commands(
+            Arc::clone(&telegram),
+            kill_switch.file.clone(),
+            binance_client.clone(),
+            hedged_symbols.iter().map(|s| s.to_string()).collect(),
+            shutdown_rx.clone(),
+        )));
+    }
 
-    loop {
-        let snapshot = monitor.status().await?;
-        let message = snapshot.to_message("BNB");
-        telegram.push_message(&message).await?;
-        tokio::time::sleep(Duration::from_secs(90)).await;
+    wait_for_shutdown_signal().await?;
+    println!("Shutdown requested; waiting for strategies to finish their current cycle");
+    shutdown_tx.send_replace(true);
+    for task in tasks {
+        if let Err(e) = task.await {
+            println!("Task ended abnormally: {}", e);
+        }
     }
+
+    for symbol in hedged_symbols {
+        if let Err(e) = binance_client.cancel_all_open_orders(symbol).await {
+            println!("Failed to cancel open orders on {}: {:#}", symbol, e);
+        }
+    }
+    println!("Shutdown complete");
+    Ok(())
+}
+
+/// Resolves on SIGTERM or Ctrl-C
+#[cfg(unix)]
+async fn wait_for_shutdown_signal() -> std::io::Result<()> {
+    let mut terminate = tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())?;
+    tokio::select! {
+        _ = terminate.recv() => {}
+        result = tokio::signal::ctrl_c() => result?,
+    }
+    Ok(())
+}
+
+/// Resolves on Ctrl-C
+#[cfg(not(unix))]
+async fn wait_for_shutdown_signal() -> std::io::Result<()> {
+    tokio::signal::ctrl_c().await
 }
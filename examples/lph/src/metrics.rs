@@ -0,0 +1,922 @@
+//! Prometheus metrics and health endpoint of the daemon.
+//!
+//! Runners report each cycle to a shared `Metrics` registry; RPC and Binance request
+//! statistics are read from the shared clients when the endpoint is scraped. `/metrics` serves
+//! the Prometheus text format, `/healthz` answers 200 while every strategy completed a cycle
+//! within `max_cycle_age_secs` and 503 otherwise.
+
+use anyhow::{Context, Result};
+use axum::extract::State;
+use axum::http::{header, StatusCode};
+use axum::response::IntoResponse;
+use axum::routing::get;
+use axum::Router;
+use clients_binance::BinancePerpsClient;
+use clients_rpc::FailoverTransport;
+use lph::{LPHStrategy, MonitoringSnapshot, StrategyCounts};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio::net::TcpListener;
+use tokio::sync::watch;
+use utils::LatencyHistogram;
+
+use crate::resilience::ErrorClass;
+
+/// Content type of the Prometheus text exposition format
+const PROMETHEUS_CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+/// Gauge name, help text and the value it reads from a `T`
+type Gauge<T> = (&'static str, &'static str, fn(&T) -> f64);
+
+/// Gauges exported for every numeric field of the last snapshot
+const SNAPSHOT_GAUGES: &[Gauge<MonitoringSnapshot>] = &[
+    (
+        "lph_block_number",
+        "Block the LP positions were read at",
+        |s| s.block_number as f64,
+    ),
+    (
+        "lph_amm_base_amount",
+        "BASE amount across the hedged LP positions",
+        |s| s.amm_base_amount,
+    ),
+    (
+        "lph_amm_usdt_amount",
+        "USDT amount across the hedged LP positions",
+        |s| s.amm_usdt_amount,
+    ),
+    (
+        "lph_amm_collectable_base",
+        "BASE collectable as fees",
+        |s| s.amm_collectable_base,
+    ),
+    (
+        "lph_amm_collectable_usdt",
+        "USDT collectable as fees",
+        |s| s.amm_collectable_usdt,
+    ),
+    (
+        "lph_amm_collectable_value_usdt",
+        "Value of collectable fees and pending farm rewards, in USDT",
+        |s| s.amm_collectable_value_usdt,
+    ),
+    (
+        "lph_amm_pending_reward",
+        "Pending farm rewards, in reward token units",
+        |s| s.amm_pending_reward,
+    ),
+    (
+        "lph_amm_reward_value_usdt",
+        "Value of the pending farm rewards, in USDT",
+        |s| s.amm_reward_value_usdt,
+    ),
+    (
+        "lph_futures_position",
+        "Net futures position in BASE units (negative = short)",
+        |s| s.futures_position,
+    ),
+    (
+        "lph_unrealized_pnl_usdt",
+        "Unrealized PnL of the futures position, in USDT",
+        |s| s.unrealized_pnl,
+    ),
+    (
+        "lph_futures_timestamp_seconds",
+        "Time of the futures position data, in seconds since Unix epoch",
+        |s| s.futures_timestamp as f64 / 1000.0,
+    ),
+    ("lph_base_price_usdt", "BASE mark price in USDT", |s| {
+        s.base_price_usdt
+    }),
+    (
+        "lph_base_delta",
+        "Net BASE exposure (AMM BASE amount plus futures position)",
+        |s| s.base_delta,
+    ),
+    (
+        "lph_base_delta_ratio",
+        "Net BASE exposure relative to the AMM BASE amount",
+        |s| s.base_delta_ratio,
+    ),
+    (
+        "lph_amm_total_value_usdt",
+        "Value of the hedged LP positions, in USDT",
+        |s| s.amm_total_value_usdt,
+    ),
+    (
+        "lph_total_value_usdt",
+        "LP value plus unrealized futures PnL, in USDT",
+        |s| s.total_value_usdt,
+    ),
+    (
+        "lph_amm_price_usdt",
+        "BASE price in USDT implied by the pool",
+        |s| s.amm_price_usdt,
+    ),
+    (
+        "lph_amm_base_gamma",
+        "Change of the AMM BASE amount per 1 USDT of BASE price move",
+        |s| s.amm_base_gamma,
+    ),
+];
+
+/// Registry of the strategy runners' state, rendered on scrape
+pub struct Metrics {
+    /// When the daemon started; strategies that never completed a cycle are judged against it
+    started: Instant,
+    /// Transports per chain name
+    transports: BTreeMap<String, FailoverTransport>,
+    /// Shared Binance futures client
+    binance_client: BinancePerpsClient,
+    /// State per strategy name
+    strategies: Mutex<BTreeMap<String, StrategyMetrics>>,
+}
+
+/// What the registry knows about one strategy
+#[derive(Default)]
+struct StrategyMetrics {
+    /// Last snapshot and when it was read
+    snapshot: Option<(MonitoringSnapshot, SystemTime)>,
+    /// Last successful cycle, as monotonic and wall clock time
+    last_success: Option<(Instant, SystemTime)>,
+    /// Failed cycles and runs since the last successful cycle
+    consecutive_failures: u64,
+    /// Successful cycles
+    cycles: u64,
+    /// Failed cycles and failed runs by error class
+    failures: BTreeMap<&'static str, u64>,
+    /// Whether orders are suspended by safe mode
+    safe_mode: bool,
+    /// Whether the kill switch is engaged
+    kill_switch: bool,
+    /// Counts of the runs before the current one
+    previous_runs: StrategyCounts,
+    /// Counts of the current run's strategy
+    current_run: StrategyCounts,
+}
+
+impl StrategyMetrics {
+    /// Counts of every run of the strategy
+    fn counts(&self) -> StrategyCounts {
+        let mut counts = self.previous_runs.clone();
+        for (action, n) in &self.current_run.decisions {
+            *counts.decisions.entry(action).or_default() += n;
+        }
+        for (status, n) in &self.current_run.orders {
+            *counts.orders.entry(status).or_default() += n;
+        }
+        counts.sync_errors += self.current_run.sync_errors;
+        counts
+    }
+}
+
+impl Metrics {
+    /// Creates the registry
+    ///
+    /// # Arguments
+    /// * `strategies` - Names of the configured strategies, reported before their first cycle
+    /// * `transports` - Transports per chain name, read on scrape
+    /// * `binance_client` - Shared Binance futures client, read on scrape
+    pub fn new<'a>(
+        strategies: impl IntoIterator<Item = &'a str>,
+        transports: BTreeMap<String, FailoverTransport>,
+        binance_client: BinancePerpsClient,
+    ) -> Self {
+        Self {
+            started: Instant::now(),
+            transports,
+            binance_client,
+            strategies: Mutex::new(
+                strategies
+                    .into_iter()
+                    .map(|name| (name.to_string(), StrategyMetrics::default()))
+                    .collect(),
+            ),
+        }
+    }
+
+    /// Applies `update` to a strategy's entry
+    fn update(&self, strategy: &str, update: impl FnOnce(&mut StrategyMetrics)) {
+        let mut strategies = self.strategies.lock().unwrap_or_else(|e| e.into_inner());
+        update(strategies.entry(strategy.to_string()).or_default());
+    }
+
+    /// Records the start of a new run, whose strategy counts from zero again
+    pub fn run_started(&self, strategy: &str) {
+        self.update(strategy, |entry| {
+            entry.previous_runs = entry.counts();
+            entry.current_run = StrategyCounts::default();
+        });
+    }
+
+    /// Records a run that failed during setup, panicked or stopped on a fatal error
+    pub fn run_failed(&self, strategy: &str, class: ErrorClass) {
+        self.update(strategy, |entry| {
+            entry.consecutive_failures += 1;
+            *entry.failures.entry(class_label(class)).or_default() += 1;
+        });
+    }
+
+    /// Records the snapshot a cycle read
+    pub fn record_snapshot(&self, strategy: &str, snapshot: &MonitoringSnapshot) {
+        self.update(strategy, |entry| {
+            entry.snapshot = Some((snapshot.clone(), SystemTime::now()));
+        });
+    }
+
+    /// Records the outcome of a cycle and the strategy's state after it
+    ///
+    /// # Arguments
+    /// * `strategy` - Strategy name
+    /// * `error` - Class of the cycle's error; `None` for a successful cycle
+    /// * `monitor` - Strategy of the run, read for its counts and kill switch
+    /// * `safe_mode` - Whether orders are suspended by safe mode
+    pub fn record_cycle(
+        &self,
+        strategy: &str,
+        error: Option<ErrorClass>,
+        monitor: &LPHStrategy,
+        safe_mode: bool,
+    ) {
+        self.update(strategy, |entry| {
+            match error {
+                None => {
+                    entry.cycles += 1;
+                    entry.consecutive_failures = 0;
+                    entry.last_success = Some((Instant::now(), SystemTime::now()));
+                }
+                Some(class) => {
+                    entry.consecutive_failures += 1;
+                    *entry.failures.entry(class_label(class)).or_default() += 1;
+                }
+            }
+            entry.current_run = monitor.counts().clone();
+            entry.safe_mode = safe_mode;
+            entry.kill_switch = monitor.kill_switch_engaged();
+        });
+    }
+
+    /// Whether every strategy completed a cycle recently
+    ///
+    /// A strategy that never completed a cycle is healthy until `max_cycle_age` after the
+    /// daemon started.
+    ///
+    /// # Arguments
+    /// * `max_cycle_age` - Oldest acceptable last successful cycle
+    ///
+    /// # Returns
+    /// The overall verdict and a JSON body with the state of each strategy
+    pub fn health(&self, max_cycle_age: Duration) -> (bool, serde_json::Value) {
+        let strategies = self.strategies.lock().unwrap_or_else(|e| e.into_inner());
+        let mut healthy = true;
+        let mut report = serde_json::Map::new();
+        for (name, entry) in strategies.iter() {
+            let age = entry.last_success.map(|(at, _)| at.elapsed());
+            let strategy_healthy = age.unwrap_or_else(|| self.started.elapsed()) <= max_cycle_age;
+            healthy &= strategy_healthy;
+            report.insert(
+                name.clone(),
+                serde_json::json!({
+                    "healthy": strategy_healthy,
+                    "last_success_age_secs": age.map(|age| age.as_secs_f64()),
+                    "consecutive_failures": entry.consecutive_failures,
+                    "safe_mode": entry.safe_mode,
+                    "kill_switch": entry.kill_switch,
+                }),
+            );
+        }
+        let body = serde_json::json!({
+            "status": if healthy { "ok" } else { "unhealthy" },
+            "max_cycle_age_secs": max_cycle_age.as_secs(),
+            "strategies": report,
+        });
+        (healthy, body)
+    }
+
+    /// Renders every metric in the Prometheus text format
+    pub fn render(&self) -> String {
+        let mut out = Exposition::default();
+        self.render_strategies(&mut out);
+        self.render_rpc(&mut out);
+        self.render_binance(&mut out);
+        out.text
+    }
+
+    fn render_strategies(&self, out: &mut Exposition) {
+        let strategies = self.strategies.lock().unwrap_or_else(|e| e.into_inner());
+
+        out.family(
+            "lph_last_successful_cycle_timestamp_seconds",
+            "gauge",
+            "Time of the last successful cycle, in seconds since Unix epoch",
+        );
+        for (name, entry) in strategies.iter() {
+            if let Some((_, at)) = entry.last_success {
+                out.sample(
+                    "lph_last_successful_cycle_timestamp_seconds",
+                    &[("strategy", name)],
+                    unix_secs(at),
+                );
+            }
+        }
+        out.family(
+            "lph_last_successful_cycle_age_seconds",
+            "gauge",
+            "Seconds since the last successful cycle",
+        );
+        for (name, entry) in strategies.iter() {
+            if let Some((at, _)) = entry.last_success {
+                out.sample(
+                    "lph_last_successful_cycle_age_seconds",
+                    &[("strategy", name)],
+                    at.elapsed().as_secs_f64(),
+                );
+            }
+        }
+        out.family("lph_cycles_total", "counter", "Successful strategy cycles");
+        for (name, entry) in strategies.iter() {
+            out.sample(
+                "lph_cycles_total",
+                &[("strategy", name)],
+                entry.cycles as f64,
+            );
+        }
+        out.family(
+            "lph_cycle_failures_total",
+            "counter",
+            "Failed cycles and runs by error class",
+        );
+        for (name, entry) in strategies.iter() {
+            for class in ["transient", "fatal"] {
+                let n = entry.failures.get(class).copied().unwrap_or(0);
+                out.sample(
+                    "lph_cycle_failures_total",
+                    &[("strategy", name), ("class", class)],
+                    n as f64,
+                );
+            }
+        }
+        out.family(
+            "lph_consecutive_cycle_failures",
+            "gauge",
+            "Failed cycles and runs since the last successful cycle",
+        );
+        for (name, entry) in strategies.iter() {
+            out.sample(
+                "lph_consecutive_cycle_failures",
+                &[("strategy", name)],
+                entry.consecutive_failures as f64,
+            );
+        }
+        out.family(
+            "lph_safe_mode",
+            "gauge",
+            "Whether orders are suspended by safe mode",
+        );
+        for (name, entry) in strategies.iter() {
+            out.sample(
+                "lph_safe_mode",
+                &[("strategy", name)],
+                flag(entry.safe_mode),
+            );
+        }
+        out.family(
+            "lph_kill_switch_engaged",
+            "gauge",
+            "Whether the kill switch is engaged",
+        );
+        for (name, entry) in strategies.iter() {
+            out.sample(
+                "lph_kill_switch_engaged",
+                &[("strategy", name)],
+                flag(entry.kill_switch),
+            );
+        }
+
+        let counts: Vec<(&String, StrategyCounts)> = strategies
+            .iter()
+            .map(|(name, entry)| (name, entry.counts()))
+            .collect();
+        out.family(
+            "lph_decisions_total",
+            "counter",
+            "Hedge decisions by action",
+        );
+        for (name, counts) in &counts {
+            for (action, n) in &counts.decisions {
+                out.sample(
+                    "lph_decisions_total",
+                    &[("strategy", name), ("action", action)],
+                    *n as f64,
+                );
+            }
+        }
+        out.family(
+            "lph_orders_total",
+            "counter",
+            "Hedge orders placed, by status after placement",
+        );
+        for (name, counts) in &counts {
+            for (status, n) in &counts.orders {
+                out.sample(
+                    "lph_orders_total",
+                    &[("strategy", name), ("status", status)],
+                    *n as f64,
+                );
+            }
+        }
+        out.family(
+            "lph_sync_errors_total",
+            "counter",
+            "Snapshots refused because a hedged LP position did not sync",
+        );
+        for (name, counts) in &counts {
+            out.sample(
+                "lph_sync_errors_total",
+                &[("strategy", name)],
+                counts.sync_errors as f64,
+            );
+        }
+
+        let snapshots: Vec<(&String, &MonitoringSnapshot, SystemTime)> = strategies
+            .iter()
+            .filter_map(|(name, entry)| {
+                entry
+                    .snapshot
+                    .as_ref()
+                    .map(|(snapshot, at)| (name, snapshot, *at))
+            })
+            .collect();
+        out.family(
+            "lph_snapshot_info",
+            "gauge",
+            "Symbol and block hash of the last snapshot",
+        );
+        for (name, snapshot, _) in &snapshots {
+            let block_hash = snapshot.block_hash.to_string();
+            out.sample(
+                "lph_snapshot_info",
+                &[
+                    ("strategy", name),
+                    ("symbol", &snapshot.symbol),
+                    ("block_hash", &block_hash),
+                ],
+                1.0,
+            );
+        }
+        out.family(
+            "lph_snapshot_timestamp_seconds",
+            "gauge",
+            "Time the last snapshot was read, in seconds since Unix epoch",
+        );
+        for (name, _, at) in &snapshots {
+            out.sample(
+                "lph_snapshot_timestamp_seconds",
+                &[("strategy", name)],
+                unix_secs(*at),
+            );
+        }
+        for (metric, help, value) in SNAPSHOT_GAUGES {
+            out.family(metric, "gauge", help);
+            for (name, snapshot, _) in &snapshots {
+                out.sample(metric, &[("strategy", name)], value(snapshot));
+            }
+        }
+
+        let shock_gauges: [Gauge<lph::PriceShock>; 4] = [
+            (
+                "lph_price_shock_price_usdt",
+                "Shocked BASE price in USDT",
+                |s| s.price,
+            ),
+            (
+                "lph_price_shock_amm_base_amount",
+                "AMM BASE amount at the shocked price",
+                |s| s.amm_base_amount,
+            ),
+            (
+                "lph_price_shock_base_delta",
+                "Net BASE exposure at the shocked price",
+                |s| s.base_delta,
+            ),
+            (
+                "lph_price_shock_value_change_usdt",
+                "Change of AMM value plus futures PnL at the shocked price, in USDT",
+                |s| s.value_change_usdt,
+            ),
+        ];
+        for (metric, help, value) in shock_gauges {
+            out.family(metric, "gauge", help);
+            for (name, snapshot, _) in &snapshots {
+                for shock in &snapshot.price_shocks {
+                    let label = shock.shock.to_string();
+                    out.sample(
+                        metric,
+                        &[("strategy", name), ("shock", &label)],
+                        value(shock),
+                    );
+                }
+            }
+        }
+
+        let position_gauges: [Gauge<lph::PositionSnapshot>; 11] = [
+            ("lph_position_tick_lower", "Lower tick of the range", |p| {
+                p.tick_lower as f64
+            }),
+            ("lph_position_tick_upper", "Upper tick of the range", |p| {
+                p.tick_upper as f64
+            }),
+            (
+                "lph_position_in_range",
+                "Whether the pool tick is inside the range",
+                |p| flag(p.in_range),
+            ),
+            (
+                "lph_position_price_usdt",
+                "BASE price in USDT implied by the position's pool",
+                |p| p.price_usdt,
+            ),
+            (
+                "lph_position_base_amount",
+                "BASE amount of the position",
+                |p| p.base_amount,
+            ),
+            (
+                "lph_position_usdt_amount",
+                "USDT amount of the position",
+                |p| p.usdt_amount,
+            ),
+            (
+                "lph_position_collectable_base",
+                "BASE collectable as fees",
+                |p| p.collectable_base,
+            ),
+            (
+                "lph_position_collectable_usdt",
+                "USDT collectable as fees",
+                |p| p.collectable_usdt,
+            ),
+            (
+                "lph_position_staked",
+                "Whether the position is staked in a farm",
+                |p| flag(p.staked),
+            ),
+            (
+                "lph_position_pending_reward",
+                "Pending farm rewards, in reward token units",
+                |p| p.pending_reward,
+            ),
+            (
+                "lph_position_gamma",
+                "Position gamma at its pool price",
+                |p| p.gamma,
+            ),
+        ];
+        for (metric, help, value) in position_gauges {
+            out.family(metric, "gauge", help);
+            for (name, snapshot, _) in &snapshots {
+                for position in &snapshot.positions {
+                    let token_id = position.token_id.to_string();
+                    out.sample(
+                        metric,
+                        &[("strategy", name), ("token_id", &token_id)],
+                        value(position),
+                    );
+                }
+            }
+        }
+
+        let paper_gauges: [Gauge<lph::PaperAccountSnapshot>; 9] = [
+            (
+                "lph_paper_position",
+                "Simulated position in BASE units",
+                |p| p.position,
+            ),
+            (
+                "lph_paper_entry_price",
+                "Average entry price of the simulated position",
+                |p| p.entry_price,
+            ),
+            (
+                "lph_paper_unrealized_pnl_usdt",
+                "Unrealized PnL of the simulated position, in USDT",
+                |p| p.unrealized_pnl,
+            ),
+            (
+                "lph_paper_realized_pnl_usdt",
+                "PnL realized on the simulated account, in USDT",
+                |p| p.realized_pnl,
+            ),
+            (
+                "lph_paper_fees_paid_usdt",
+                "Fees paid on simulated fills, in USDT",
+                |p| p.fees_paid,
+            ),
+            (
+                "lph_paper_net_pnl_usdt",
+                "Realized plus unrealized PnL minus fees of the simulated account, in USDT",
+                |p| p.net_pnl,
+            ),
+            (
+                "lph_paper_filled_notional_usdt",
+                "Notional of every simulated fill, in USDT",
+                |p| p.filled_notional,
+            ),
+            ("lph_paper_fills", "Number of simulated fills", |p| {
+                p.fills as f64
+            }),
+            (
+                "lph_paper_open_orders",
+                "Orders resting on the simulated account",
+                |p| p.open_orders as f64,
+            ),
+        ];
+        for (metric, help, value) in paper_gauges {
+            out.family(metric, "gauge", help);
+            for (name, snapshot, _) in &snapshots {
+                if let Some(paper) = &snapshot.paper {
+                    out.sample(metric, &[("strategy", name)], value(paper));
+                }
+            }
+        }
+    }
+
+    fn render_rpc(&self, out: &mut Exposition) {
+        // Endpoints are labelled by position and host: URLs often embed API keys
+        let endpoints: Vec<(&String, String, String, clients_rpc::EndpointStatus)> = self
+            .transports
+            .iter()
+            .flat_map(|(chain, transport)| {
+                transport
+                    .endpoint_status()
+                    .into_iter()
+                    .enumerate()
+                    .map(move |(i, status)| {
+                        (chain, i.to_string(), endpoint_host(&status.url), status)
+                    })
+            })
+            .collect();
+
+        out.family(
+            "lph_rpc_request_duration_seconds",
+            "histogram",
+            "Latency of successful RPC requests per endpoint",
+        );
+        for (chain, index, host, status) in &endpoints {
+            out.histogram(
+                "lph_rpc_request_duration_seconds",
+                &[("chain", chain), ("endpoint", index), ("host", host)],
+                &status.latency,
+            );
+        }
+        out.family(
+            "lph_rpc_requests_total",
+            "counter",
+            "RPC requests per endpoint by result",
+        );
+        for (chain, index, host, status) in &endpoints {
+            for (result, n) in [("success", status.successes), ("failure", status.failures)] {
+                out.sample(
+                    "lph_rpc_requests_total",
+                    &[
+                        ("chain", chain),
+                        ("endpoint", index),
+                        ("host", host),
+                        ("result", result),
+                    ],
+                    n as f64,
+                );
+            }
+        }
+        out.family(
+            "lph_rpc_endpoint_healthy",
+            "gauge",
+            "Whether the endpoint is neither on cooldown nor lagging",
+        );
+        for (chain, index, host, status) in &endpoints {
+            out.sample(
+                "lph_rpc_endpoint_healthy",
+                &[("chain", chain), ("endpoint", index), ("host", host)],
+                flag(status.healthy),
+            );
+        }
+        out.family(
+            "lph_rpc_endpoint_head_lag_blocks",
+            "gauge",
+            "Blocks the endpoint trails the highest known head",
+        );
+        for (chain, index, host, status) in &endpoints {
+            out.sample(
+                "lph_rpc_endpoint_head_lag_blocks",
+                &[("chain", chain), ("endpoint", index), ("host", host)],
+                status.head_lag as f64,
+            );
+        }
+    }
+
+    fn render_binance(&self, out: &mut Exposition) {
+        let stats = self.binance_client.stats();
+        out.family(
+            "lph_binance_request_duration_seconds",
+            "histogram",
+            "Latency of Binance REST requests that got a response, per endpoint",
+        );
+        for (endpoint, endpoint_stats) in &stats.endpoints {
+            out.histogram(
+                "lph_binance_request_duration_seconds",
+                &[("endpoint", endpoint)],
+                &endpoint_stats.latency,
+            );
+        }
+        out.family(
+            "lph_binance_requests_total",
+            "counter",
+            "Binance REST requests per endpoint",
+        );
+        for (endpoint, endpoint_stats) in &stats.endpoints {
+            out.sample(
+                "lph_binance_requests_total",
+                &[("endpoint", endpoint)],
+                endpoint_stats.requests as f64,
+            );
+        }
+        out.family(
+            "lph_binance_request_errors_total",
+            "counter",
+            "Binance REST requests that failed or returned a non-2xx status, per endpoint",
+        );
+        for (endpoint, endpoint_stats) in &stats.endpoints {
+            out.sample(
+                "lph_binance_request_errors_total",
+                &[("endpoint", endpoint)],
+                endpoint_stats.errors as f64,
+            );
+        }
+        out.family(
+            "lph_binance_used_weight_1m",
+            "gauge",
+            "Request weight used in the current minute, from the last response",
+        );
+        if let Some(weight) = stats.used_weight_1m {
+            out.sample("lph_binance_used_weight_1m", &[], weight as f64);
+        }
+        out.family(
+            "lph_binance_order_count_1m",
+            "gauge",
+            "Orders placed in the current minute, from the last order response",
+        );
+        if let Some(count) = stats.order_count_1m {
+            out.sample("lph_binance_order_count_1m", &[], count as f64);
+        }
+    }
+}
+
+/// Prometheus text format being written
+#[derive(Default)]
+struct Exposition {
+    text: String,
+}
+
+impl Exposition {
+    /// Writes the HELP and TYPE lines of a metric family
+    fn family(&mut self, name: &str, kind: &str, help: &str) {
+        let _ = writeln!(self.text, "# HELP {} {}", name, help);
+        let _ = writeln!(self.text, "# TYPE {} {}", name, kind);
+    }
+
+    /// Writes one sample
+    fn sample(&mut self, name: &str, labels: &[(&str, &str)], value: f64) {
+        self.text.push_str(name);
+        if !labels.is_empty() {
+            self.text.push('{');
+            for (i, (key, value)) in labels.iter().enumerate() {
+                if i > 0 {
+                    self.text.push(',');
+                }
+                let _ = write!(self.text, "{}=\"{}\"", key, escape_label(value));
+            }
+            self.text.push('}');
+        }
+        let _ = writeln!(self.text, " {}", format_value(value));
+    }
+
+    /// Writes the bucket, sum and count samples of a histogram
+    fn histogram(&mut self, name: &str, labels: &[(&str, &str)], histogram: &LatencyHistogram) {
+        let bucket_name = format!("{}_bucket", name);
+        for (bound, count) in histogram.buckets() {
+            let le = bound.to_string();
+            let mut bucket_labels = labels.to_vec();
+            bucket_labels.push(("le", &le));
+            self.sample(&bucket_name, &bucket_labels, count as f64);
+        }
+        let mut inf_labels = labels.to_vec();
+        inf_labels.push(("le", "+Inf"));
+        self.sample(&bucket_name, &inf_labels, histogram.count() as f64);
+        self.sample(&format!("{}_sum", name), labels, histogram.sum_secs());
+        self.sample(&format!("{}_count", name), labels, histogram.count() as f64);
+    }
+}
+
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+fn format_value(value: f64) -> String {
+    if value.is_nan() {
+        "NaN".to_string()
+    } else if value.is_infinite() {
+        if value > 0.0 { "+Inf" } else { "-Inf" }.to_string()
+    } else {
+        value.to_string()
+    }
+}
+
+fn flag(value: bool) -> f64 {
+    if value {
+        1.0
+    } else {
+        0.0
+    }
+}
+
+fn unix_secs(time: SystemTime) -> f64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs_f64())
+        .unwrap_or(0.0)
+}
+
+fn class_label(class: ErrorClass) -> &'static str {
+    match class {
+        ErrorClass::Transient => "transient",
+        ErrorClass::Fatal => "fatal",
+    }
+}
+
+/// Host of an endpoint URL, without path, query or credentials
+fn endpoint_host(url: &str) -> String {
+    url::Url::parse(url)
+        .ok()
+        .and_then(|url| url.host_str().map(str::to_string))
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+/// Shared state of the HTTP handlers
+#[derive(Clone)]
+struct ServerState {
+    metrics: Arc<Metrics>,
+    max_cycle_age: Duration,
+}
+
+/// Serves `/metrics` and `/healthz` until `shutdown` flips to `true`
+///
+/// # Arguments
+/// * `listener` - Bound listening socket
+/// * `metrics` - Registry the runners report to
+/// * `max_cycle_age` - Oldest last successful cycle `/healthz` accepts
+/// * `shutdown` - Receiver set to `true` when the daemon stops
+pub async fn serve(
+    listener: TcpListener,
+    metrics: Arc<Metrics>,
+    max_cycle_age: Duration,
+    mut shutdown: watch::Receiver<bool>,
+) -> Result<()> {
+    let app = Router::new()
+        .route("/metrics", get(metrics_handler))
+        .route("/healthz", get(health_handler))
+        .with_state(ServerState {
+            metrics,
+            max_cycle_age,
+        });
+    axum::serve(listener, app)
+        .with_graceful_shutdown(async move {
+            let _ = shutdown.wait_for(|stop| *stop).await;
+        })
+        .await
+        .context("metrics server failed")
+}
+
+async fn metrics_handler(State(state): State<ServerState>) -> impl IntoResponse {
+    (
+        [(header::CONTENT_TYPE, PROMETHEUS_CONTENT_TYPE)],
+        state.metrics.render(),
+    )
+}
+
+async fn health_handler(State(state): State<ServerState>) -> impl IntoResponse {
+    let (healthy, body) = state.metrics.health(state.max_cycle_age);
+    let status = if healthy {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    (
+        status,
+        [(header::CONTENT_TYPE, "application/json")],
+        body.to_string(),
+    )
+}
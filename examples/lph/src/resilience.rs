@@ -0,0 +1,321 @@
+//! Error classification, jittered backoff and safe mode of the strategy loop.
+
+use clients_binance::BinanceApiError;
+use clients_uniswapv3::BlockReorged;
+use lph::RiskRejection;
+use rand::Rng;
+use tokio::time::Duration;
+
+/// How the strategy loop reacts to an error
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorClass {
+    /// Likely to succeed on retry (network, RPC, rate limits, reorgs); the loop keeps running
+    Transient,
+    /// Retrying cannot succeed (rejected credentials, malformed requests); the strategy stops
+    Fatal,
+}
+
+/// Classifies an error by the typed errors in its chain
+///
+/// Unknown errors are treated as transient: the strategy keeps running and the failure is
+/// escalated by alert if it persists.
+pub fn classify(error: &anyhow::Error) -> ErrorClass {
+    for cause in error.chain() {
+        if let Some(api_error) = cause.downcast_ref::<BinanceApiError>() {
+            return if api_error.is_auth_error() || api_error.is_invalid_request() {
+                ErrorClass::Fatal
+            } else {
+                ErrorClass::Transient
+            };
+        }
+        if let Some(http_error) = cause.downcast_ref::<reqwest::Error>() {
+            let unauthorized = http_error
+                .status()
+                .is_some_and(|status| status.as_u16() == 401 || status.as_u16() == 403);
+            return if http_error.is_builder() || unauthorized {
+                ErrorClass::Fatal
+            } else {
+                ErrorClass::Transient
+            };
+        }
+    }
+    ErrorClass::Transient
+}
+
+/// Whether a failed `hedge` call may have left an order on the exchange
+///
+/// Reorgs and risk rejections are detected before any order is sent, and a
+/// `BinanceApiError` means the order was rejected, except -1007 where Binance does not know
+/// whether it was executed; any other failure may have happened after the order request
+/// was sent.
+pub fn order_outcome_unknown(error: &anyhow::Error) -> bool {
+    !error.chain().any(|cause| {
+        cause.is::<BlockReorged>()
+            || cause.is::<RiskRejection>()
+            || cause
+                .downcast_ref::<BinanceApiError>()
+                .is_some_and(|api_error| !api_error.is_execution_unknown())
+    })
+}
+
+/// Whether an error reports that the snapshot's block was reorged out
+pub fn is_reorg(error: &anyhow::Error) -> bool {
+    error.chain().any(|cause| cause.is::<BlockReorged>())
+}
+
+/// Exponential backoff with ±50% jitter, so strategies failing together do not retry in lockstep
+#[derive(Debug, Clone)]
+pub struct Backoff {
+    /// Delay after the first failure
+    initial: Duration,
+    /// Upper bound of the un-jittered delay
+    max: Duration,
+    /// Un-jittered delay of the next failure
+    current: Duration,
+}
+
+impl Backoff {
+    /// Creates a backoff starting at `initial` and doubling up to `max`
+    pub fn new(initial: Duration, max: Duration) -> Self {
+        Self {
+            initial,
+            max,
+            current: initial,
+        }
+    }
+
+    /// Returns the jittered delay before the next retry and doubles the base delay
+    pub fn next_delay(&mut self) -> Duration {
+        let base = self.current;
+        self.current = (self.current * 2).min(self.max);
+        base.mul_f64(rand::rng().random_range(0.5..1.5))
+    }
+
+    /// Restarts from the initial delay after a success
+    pub fn reset(&mut self) {
+        self.current = self.initial;
+    }
+}
+
+/// Safe mode: the strategy keeps reporting but places no orders until the state is certain again
+#[derive(Debug, Clone)]
+pub struct SafeMode {
+    /// Why safe mode was entered; `None` when inactive
+    reason: Option<String>,
+    /// Open orders must be cancelled before snapshots count as clean
+    cancel_pending: bool,
+    /// Clean snapshots seen since safe mode was entered
+    clean_snapshots: u32,
+    /// Clean snapshots required to leave safe mode
+    required_clean_snapshots: u32,
+    /// Only a restart of the daemon leaves safe mode, e.g. while funds wait in the wallet
+    held: bool,
+}
+
+impl SafeMode {
+    /// Creates an inactive safe mode that is left after `required_clean_snapshots` clean snapshots
+    pub fn new(required_clean_snapshots: u32) -> Self {
+        Self {
+            reason: None,
+            cancel_pending: false,
+            clean_snapshots: 0,
+            required_clean_snapshots,
+            held: false,
+        }
+    }
+
+    /// Enters (or re-enters) safe mode
+    ///
+    /// # Arguments
+    /// * `reason` - Why the state is uncertain, shown in reports
+    /// * `cancel_orders` - Whether open orders must be cancelled before the state can be trusted
+    pub fn enter(&mut self, reason: String, cancel_orders: bool) {
+        self.reason = Some(reason);
+        self.cancel_pending |= cancel_orders;
+        self.clean_snapshots = 0;
+    }
+
+    /// Enters safe mode until the daemon is restarted: clean snapshots do not end it
+    ///
+    /// Used when the snapshot cannot show the state, e.g. tokens left in the wallet by a
+    /// failed re-mint, which hedging on the LP exposure alone would unhedge.
+    ///
+    /// # Arguments
+    /// * `reason` - What needs an operator, shown in reports
+    pub fn hold(&mut self, reason: String) {
+        self.enter(reason, false);
+        self.held = true;
+    }
+
+    /// Why safe mode is active; `None` when orders may be placed
+    pub fn reason(&self) -> Option<&str> {
+        self.reason.as_deref()
+    }
+
+    /// Whether open orders still have to be cancelled
+    pub fn cancel_pending(&self) -> bool {
+        self.cancel_pending
+    }
+
+    /// Records that open orders were cancelled
+    pub fn orders_cancelled(&mut self) {
+        self.cancel_pending = false;
+    }
+
+    /// Records a snapshot read without error
+    ///
+    /// # Returns
+    /// `true` if this snapshot ended safe mode
+    pub fn record_clean_snapshot(&mut self) -> bool {
+        if self.reason.is_none() || self.cancel_pending || self.held {
+            return false;
+        }
+        self.clean_snapshots += 1;
+        if self.clean_snapshots < self.required_clean_snapshots {
+            return false;
+        }
+        self.reason = None;
+        self.clean_snapshots = 0;
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy::primitives::B256;
+    use anyhow::anyhow;
+    use clients_uniswapv3::SyncedBlock;
+
+    fn api_error(status: u16, code: i64) -> anyhow::Error {
+        anyhow::Error::new(BinanceApiError {
+            status,
+            code,
+            msg: String::new(),
+        })
+        .context("place_order")
+    }
+
+    fn http_error(status: u16) -> anyhow::Error {
+        let response = axum::http::Response::builder()
+            .status(status)
+            .body("")
+            .unwrap();
+        let error = reqwest::Response::from(response)
+            .error_for_status()
+            .unwrap_err();
+        anyhow::Error::new(error).context("get_position")
+    }
+
+    #[test]
+    fn rejected_credentials_and_malformed_requests_are_fatal() {
+        assert_eq!(classify(&api_error(401, -2015)), ErrorClass::Fatal);
+        assert_eq!(classify(&api_error(400, -1022)), ErrorClass::Fatal);
+        assert_eq!(classify(&api_error(400, -1111)), ErrorClass::Fatal);
+        assert_eq!(classify(&api_error(400, -1121)), ErrorClass::Fatal);
+        assert_eq!(classify(&http_error(401)), ErrorClass::Fatal);
+        assert_eq!(classify(&http_error(403)), ErrorClass::Fatal);
+    }
+
+    #[test]
+    fn rate_limits_and_server_errors_are_transient() {
+        assert_eq!(classify(&api_error(429, -1003)), ErrorClass::Transient);
+        assert_eq!(classify(&api_error(418, -1003)), ErrorClass::Transient);
+        assert_eq!(classify(&api_error(400, -2019)), ErrorClass::Transient);
+        assert_eq!(classify(&api_error(503, -1007)), ErrorClass::Transient);
+        assert_eq!(classify(&http_error(429)), ErrorClass::Transient);
+        assert_eq!(classify(&http_error(502)), ErrorClass::Transient);
+        assert_eq!(classify(&anyhow!("rpc timeout")), ErrorClass::Transient);
+    }
+
+    #[test]
+    fn only_errors_before_or_refused_by_binance_have_a_known_outcome() {
+        assert!(!order_outcome_unknown(&api_error(400, -2019)));
+        assert!(!order_outcome_unknown(
+            &anyhow::Error::new(BlockReorged {
+                synced: SyncedBlock {
+                    number: 1,
+                    hash: B256::ZERO,
+                },
+                canonical: B256::repeat_byte(1),
+            })
+            .context("hedge")
+        ));
+        // Binance timed out on its backend and does not know whether the order exists
+        assert!(order_outcome_unknown(&api_error(503, -1007)));
+        assert!(order_outcome_unknown(&http_error(502)));
+        assert!(order_outcome_unknown(&anyhow!("connection reset")));
+    }
+
+    #[test]
+    fn backoff_doubles_up_to_the_maximum_with_jitter() {
+        let initial = Duration::from_secs(2);
+        let max = Duration::from_secs(10);
+        let mut backoff = Backoff::new(initial, max);
+        for base in [2.0, 4.0, 8.0, 10.0, 10.0] {
+            let delay = backoff.next_delay().as_secs_f64();
+            assert!(
+                (base * 0.5..base * 1.5).contains(&delay),
+                "delay {} outside the jitter of {}",
+                delay,
+                base
+            );
+        }
+        backoff.reset();
+        let delay = backoff.next_delay().as_secs_f64();
+        assert!((1.0..3.0).contains(&delay));
+    }
+
+    #[test]
+    fn backoff_jitter_varies_the_delay() {
+        let delays: Vec<Duration> = (0..20)
+            .map(|_| Backoff::new(Duration::from_secs(60), Duration::from_secs(60)).next_delay())
+            .collect();
+        assert!(delays.iter().any(|delay| *delay != delays[0]));
+    }
+
+    #[test]
+    fn safe_mode_is_left_after_the_required_clean_snapshots() {
+        let mut safe_mode = SafeMode::new(2);
+        assert!(!safe_mode.record_clean_snapshot());
+
+        safe_mode.enter("reorg".to_string(), false);
+        assert_eq!(safe_mode.reason(), Some("reorg"));
+        assert!(!safe_mode.record_clean_snapshot());
+        assert!(safe_mode.record_clean_snapshot());
+        assert_eq!(safe_mode.reason(), None);
+    }
+
+    #[test]
+    fn safe_mode_counts_clean_snapshots_only_after_the_cancel() {
+        let mut safe_mode = SafeMode::new(1);
+        safe_mode.enter("order outcome unknown".to_string(), true);
+        assert!(safe_mode.cancel_pending());
+        assert!(!safe_mode.record_clean_snapshot());
+        assert!(!safe_mode.record_clean_snapshot());
+
+        safe_mode.orders_cancelled();
+        assert!(safe_mode.record_clean_snapshot());
+        assert_eq!(safe_mode.reason(), None);
+    }
+
+    #[test]
+    fn entering_again_restarts_the_count() {
+        let mut safe_mode = SafeMode::new(2);
+        safe_mode.enter("first".to_string(), false);
+        assert!(!safe_mode.record_clean_snapshot());
+        safe_mode.enter("second".to_string(), false);
+        assert!(!safe_mode.record_clean_snapshot());
+        assert!(safe_mode.record_clean_snapshot());
+    }
+
+    #[test]
+    fn held_safe_mode_is_not_left_by_clean_snapshots() {
+        let mut safe_mode = SafeMode::new(1);
+        safe_mode.hold("tokens in the wallet".to_string());
+        for _ in 0..5 {
+            assert!(!safe_mode.record_clean_snapshot());
+        }
+        assert_eq!(safe_mode.reason(), Some("tokens in the wallet"));
+    }
+}
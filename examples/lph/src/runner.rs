@@ -0,0 +1,431 @@
+//! Supervised task running one LPH strategy.
+
+use alloy::network::EthereumWallet;
+use alloy::providers::Provider;
+use anyhow::{anyhow, Result};
+use clients_binance::BinancePerpsClient;
+use clients_rpc::FailoverTransport;
+use clients_telegrambot::TelegramBot;
+use clients_uniswapv3::{AmmConfig, UniswapV3PositionManager};
+use lph::{LPHStrategy, RiskManager, StateStore};
+use std::sync::Arc;
+use tokio::sync::watch;
+use tokio::time::{Duration, Instant};
+
+use crate::config::{ResilienceSection, StrategySection};
+use crate::metrics::Metrics;
+use crate::resilience::{classify, is_reorg, order_outcome_unknown, Backoff, ErrorClass, SafeMode};
+
+/// One strategy together with the shared clients it runs on
+#[derive(Clone)]
+pub struct StrategyRunner {
+    /// Strategy settings from the config file
+    section: StrategySection,
+    /// Signer of the re-centering and compounding transactions, if enabled
+    wallet: Option<EthereumWallet>,
+    /// Retry, alerting and safe mode settings
+    resilience: ResilienceSection,
+    /// Transport of the strategy's chain, shared with other strategies on that chain
+    rpc: FailoverTransport,
+    /// Shared Binance futures client
+    binance_client: BinancePerpsClient,
+    /// Shared Telegram bot
+    telegram: Arc<TelegramBot>,
+    /// Shared state store; `None` keeps no records
+    store: Option<Arc<dyn StateStore>>,
+    /// Shared metrics registry
+    metrics: Arc<Metrics>,
+    /// Risk manager shared by every run, so a restart keeps the turnover and order windows
+    risk: RiskManager,
+}
+
+/// State of one run of the strategy loop
+struct LoopState {
+    /// Strategy being run
+    monitor: LPHStrategy,
+    /// When the next status report is due
+    next_status: Instant,
+    /// When re-centering and compounding are next checked
+    next_onchain: Instant,
+    /// Whether orders are currently suspended
+    safe_mode: SafeMode,
+}
+
+impl StrategyRunner {
+    /// Creates a runner for one strategy
+    ///
+    /// # Arguments
+    /// * `section` - Strategy settings from the config file
+    /// * `resilience` - Retry, alerting and safe mode settings
+    /// * `rpc` - Transport of the strategy's chain
+    /// * `binance_client` - Shared Binance futures client
+    /// * `telegram` - Shared Telegram bot
+    /// * `store` - Shared state store, if configured
+    /// * `metrics` - Shared metrics registry
+    pub fn new(
+        section: StrategySection,
+        resilience: ResilienceSection,
+        rpc: FailoverTransport,
+        binance_client: BinancePerpsClient,
+        telegram: Arc<TelegramBot>,
+        store: Option<Arc<dyn StateStore>>,
+        metrics: Arc<Metrics>,
+    ) -> Self {
+        let risk = RiskManager::new(section.strategy.risk.clone());
+        Self {
+            section,
+            wallet: None,
+            resilience,
+            rpc,
+            binance_client,
+            telegram,
+            store,
+            metrics,
+            risk,
+        }
+    }
+
+    /// Signs the re-centering and compounding transactions with a wallet of `strategy.owner`
+    ///
+    /// # Arguments
+    /// * `wallet` - Wallet built from `StrategySection::signer`
+    pub fn with_wallet(mut self, wallet: EthereumWallet) -> Self {
+        self.wallet = Some(wallet);
+        self
+    }
+
+    /// Strategy name from the config file
+    pub fn name(&self) -> &str {
+        &self.section.name
+    }
+
+    /// Runs the strategy until `shutdown` flips to `true`
+    ///
+    /// A run that fails during setup or panics is restarted with jittered backoff, starting
+    /// in safe mode since an order may have been in flight. A fatal error stops the strategy
+    /// for good; the other strategies keep running.
+    ///
+    /// # Arguments
+    /// * `shutdown` - Receiver set to `true` when the daemon stops
+    pub async fn run_with_restarts(self, mut shutdown: watch::Receiver<bool>) {
+        let mut backoff = self.backoff();
+        let mut restarts = 0u32;
+        let mut safe_mode_reason = None;
+        loop {
+            let started = Instant::now();
+            // Run each attempt in its own task so a panic is caught like an error
+            let runner = self.clone();
+            let attempt_shutdown = shutdown.clone();
+            let attempt =
+                tokio::spawn(async move { runner.run(attempt_shutdown, safe_mode_reason).await });
+            let (error, panicked) = match attempt.await {
+                Ok(Ok(())) => return,
+                Ok(Err(e)) => (e, false),
+                Err(e) => (anyhow!("strategy task panicked: {}", e), true),
+            };
+            let class = classify(&error);
+            self.metrics.run_failed(self.name(), class);
+            if class == ErrorClass::Fatal {
+                println!(
+                    "[{}] strategy stopped on fatal error: {:#}",
+                    self.name(),
+                    error
+                );
+                self.alert(&format!("strategy stopped on fatal error: {:#}", error))
+                    .await;
+                return;
+            }
+            if started.elapsed() >= Duration::from_secs(self.resilience.max_backoff_secs) {
+                backoff.reset();
+                restarts = 0;
+            }
+            restarts += 1;
+            let delay = backoff.next_delay();
+            println!(
+                "[{}] strategy failed: {:#}; restarting in {:.1}s",
+                self.name(),
+                error,
+                delay.as_secs_f64()
+            );
+            // Setup failures are escalated like failed cycles; a panic is a bug and always alerted
+            if panicked || restarts == self.resilience.alert_after_failures {
+                self.alert(&format!(
+                    "strategy failed {} times in a row, restarting in {:.0}s: {:#}",
+                    restarts,
+                    delay.as_secs_f64(),
+                    error
+                ))
+                .await;
+            }
+            safe_mode_reason = Some(format!("restarted after: {:#}", error));
+            tokio::select! {
+                _ = tokio::time::sleep(delay) => {}
+                _ = shutdown.wait_for(|stop| *stop) => return,
+            }
+        }
+    }
+
+    /// Runs the strategy loop until shutdown
+    ///
+    /// Transient errors of a cycle are retried with jittered backoff; after
+    /// `alert_after_failures` consecutive failures an alert is pushed once. Shutdown is only
+    /// observed between cycles, so an order being placed is never interrupted.
+    ///
+    /// # Arguments
+    /// * `shutdown` - Receiver set to `true` when the daemon stops
+    /// * `safe_mode_reason` - Start in safe mode for this reason, e.g. after a restart
+    ///
+    /// # Returns
+    /// `Ok(())` on shutdown; the error if setup fails or a cycle fails fatally
+    async fn run(
+        self,
+        mut shutdown: watch::Receiver<bool>,
+        safe_mode_reason: Option<String>,
+    ) -> Result<()> {
+        let mut state = self.start(safe_mode_reason).await?;
+        self.metrics.run_started(self.name());
+        let poll_interval = Duration::from_secs(self.section.intervals.poll_secs);
+        let mut backoff = self.backoff();
+        let mut failures = 0u32;
+        while !*shutdown.borrow() {
+            let result = self.cycle(&mut state).await;
+            let error_class = result.as_ref().err().map(classify);
+            if error_class != Some(ErrorClass::Fatal) {
+                self.metrics.record_cycle(
+                    self.name(),
+                    error_class,
+                    &state.monitor,
+                    state.safe_mode.reason().is_some(),
+                );
+            }
+            let delay = match result {
+                Ok(()) => {
+                    if failures >= self.resilience.alert_after_failures {
+                        self.alert(&format!("recovered after {} failed cycles", failures))
+                            .await;
+                    }
+                    failures = 0;
+                    backoff.reset();
+                    poll_interval
+                }
+                Err(e) => {
+                    if error_class == Some(ErrorClass::Fatal) {
+                        return Err(e);
+                    }
+                    failures += 1;
+                    let delay = backoff.next_delay().max(poll_interval);
+                    println!(
+                        "[{}] cycle failed ({} in a row): {:#}; retrying in {:.1}s",
+                        self.name(),
+                        failures,
+                        e,
+                        delay.as_secs_f64()
+                    );
+                    if failures == self.resilience.alert_after_failures {
+                        self.alert(&format!(
+                            "{} consecutive failed cycles, last error: {:#}",
+                            failures, e
+                        ))
+                        .await;
+                    }
+                    delay
+                }
+            };
+            tokio::select! {
+                _ = tokio::time::sleep(delay) => {}
+                _ = shutdown.changed() => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Builds the position client and strategy of one run, then reconciles the orders the
+    /// store still holds as pending or resting
+    async fn start(&self, safe_mode_reason: Option<String>) -> Result<LoopState> {
+        let section = &self.section;
+        let provider = self.rpc.provider();
+        let chain_id = provider.get_chain_id().await?;
+        let position_manager = &section.position_manager;
+        let amm = position_manager
+            .amm
+            .clone()
+            .or_else(|| AmmConfig::known(chain_id, position_manager.address))
+            .unwrap_or_default();
+        let uniswap_config = clients_uniswapv3::UniswapV3PositionManagerConfig {
+            address: position_manager.address,
+            swap_router: position_manager.swap_router,
+            confirmations: position_manager.confirmations,
+            amm,
+        };
+        let mut uniswap_client = UniswapV3PositionManager::new(uniswap_config, provider);
+        if let Some(wallet) = &self.wallet {
+            uniswap_client = uniswap_client.with_wallet(wallet.clone());
+        }
+        let mut monitor = LPHStrategy::new(
+            section.strategy.clone(),
+            uniswap_client,
+            self.binance_client.clone(),
+        )?
+        .with_risk_manager(self.risk.clone());
+        if let Some(store) = &self.store {
+            monitor = monitor.with_store(section.name.clone(), Arc::clone(store));
+            let report = monitor.reconcile_orders().await?;
+            if report.checked > 0 {
+                println!("[{}] reconciled order journal: {}", section.name, report);
+            }
+        }
+        let mut safe_mode = SafeMode::new(self.resilience.safe_mode_clean_snapshots);
+        if let Some(reason) = safe_mode_reason {
+            safe_mode.enter(reason, section.hedge);
+        }
+        Ok(LoopState {
+            monitor,
+            next_status: Instant::now(),
+            next_onchain: Instant::now(),
+            safe_mode,
+        })
+    }
+
+    /// One cycle: polls logs and, when due, reads a snapshot, hedges unless in safe mode or
+    /// the kill switch is engaged, re-centres and compounds when their interval elapsed,
+    /// and pushes the report
+    ///
+    /// A failed report push is logged and does not fail the cycle.
+    async fn cycle(&self, state: &mut LoopState) -> Result<()> {
+        let section = &self.section;
+        if state.safe_mode.cancel_pending() {
+            state.monitor.cancel_open_orders().await?;
+            state.safe_mode.orders_cancelled();
+        }
+
+        let poll = state.monitor.poll_events().await?;
+        if !poll.should_reevaluate() && Instant::now() < state.next_status {
+            return Ok(());
+        }
+        let snapshot = state.monitor.status().await?;
+        self.metrics.record_snapshot(&section.name, &snapshot);
+        if state.safe_mode.record_clean_snapshot() {
+            println!("[{}] left safe mode", section.name);
+            self.alert("left safe mode, hedging resumed").await;
+        }
+
+        let kill_switch = state.monitor.kill_switch_engaged();
+        if section.hedge && state.safe_mode.reason().is_none() && !kill_switch {
+            let mut result = state.monitor.hedge(&snapshot).await;
+            if result.is_ok() {
+                result = self.onchain_actions(state).await;
+            }
+            if let Err(e) = result {
+                if is_reorg(&e) {
+                    state
+                        .safe_mode
+                        .enter(format!("snapshot block reorged: {:#}", e), false);
+                } else if order_outcome_unknown(&e) {
+                    state
+                        .safe_mode
+                        .enter(format!("hedge order outcome unknown: {:#}", e), true);
+                }
+                if let Some(reason) = state.safe_mode.reason() {
+                    println!("[{}] entered safe mode: {}", section.name, reason);
+                    self.alert(&format!("entered safe mode, orders suspended: {}", reason))
+                        .await;
+                }
+                return Err(e);
+            }
+        }
+
+        let base_label = section
+            .base_label
+            .as_deref()
+            .unwrap_or(&section.strategy.symbol);
+        let mut message = format!("[{}]\n{}", section.name, snapshot.to_message(base_label));
+        if let Some(reason) = state.safe_mode.reason() {
+            message.push_str(&format!("\nSAFE MODE (no orders): {}", reason));
+        }
+        if kill_switch {
+            message.push_str("\nKILL SWITCH ENGAGED (no orders)");
+        }
+        if let Err(e) = self.telegram.push_message(&message).await {
+            println!("[{}] failed to push status report: {:#}", section.name, e);
+        }
+        state.next_status = Instant::now() + Duration::from_secs(section.intervals.status_secs);
+        if let Err(e) = state.monitor.sync_income().await {
+            println!("[{}] failed to store income: {:#}", section.name, e);
+        }
+        for endpoint in self.rpc.endpoint_status().iter().filter(|e| !e.healthy) {
+            println!(
+                "[{}] Unhealthy RPC endpoint {}: {} consecutive failures, {} blocks behind",
+                section.name, endpoint.url, endpoint.consecutive_failures, endpoint.head_lag
+            );
+        }
+        Ok(())
+    }
+
+    /// Re-centres and compounds the positions once `onchain_secs` elapsed since the last check
+    ///
+    /// Both re-hedge the changed LP amount themselves. A failed re-mint holds safe mode, since
+    /// the withdrawn tokens wait in the wallet where the snapshots do not count them.
+    async fn onchain_actions(&self, state: &mut LoopState) -> Result<()> {
+        let section = &self.section;
+        if !section.has_onchain_actions() || Instant::now() < state.next_onchain {
+            return Ok(());
+        }
+        state.next_onchain = Instant::now() + Duration::from_secs(section.intervals.onchain_secs);
+
+        for report in state.monitor.rebalance().await? {
+            match (report.minted_token_id, &report.mint_error) {
+                (Some(minted_token_id), _) => {
+                    self.alert(&format!(
+                        "re-centred position {} into {} with range [{}, {})",
+                        report.closed_token_id,
+                        minted_token_id,
+                        report.tick_lower,
+                        report.tick_upper
+                    ))
+                    .await
+                }
+                (None, error) => {
+                    let reason = format!(
+                        "position {} was closed but its replacement was not minted, tokens are in the wallet: {}",
+                        report.closed_token_id,
+                        error.as_deref().unwrap_or("unknown error")
+                    );
+                    println!("[{}] entered safe mode: {}", section.name, reason);
+                    self.alert(&format!(
+                        "entered safe mode until restart, orders suspended: {}",
+                        reason
+                    ))
+                    .await;
+                    state.safe_mode.hold(reason);
+                    return Ok(());
+                }
+            }
+        }
+
+        let compounded = state.monitor.compound().await?;
+        if !compounded.is_empty() {
+            println!(
+                "[{}] compounded the fees of {} positions",
+                section.name,
+                compounded.len()
+            );
+        }
+        Ok(())
+    }
+
+    /// Pushes an alert prefixed with the strategy name; a failed push is only logged
+    async fn alert(&self, text: &str) {
+        let message = format!("[{}] {}", self.name(), text);
+        if let Err(e) = self.telegram.push_message(&message).await {
+            println!("[{}] failed to push alert: {:#}", self.name(), e);
+        }
+    }
+
+    /// Retry backoff from the resilience settings
+    fn backoff(&self) -> Backoff {
+        Backoff::new(
+            Duration::from_secs(self.resilience.initial_backoff_secs),
+            Duration::from_secs(self.resilience.max_backoff_secs),
+        )
+    }
+}
@@ -5,10 +5,9 @@
 //! token0 = USD, token1 = BNB.
 
 use alloy::network::Ethereum;
-use alloy::primitives::U256;
 use alloy::providers::{Provider, RootProvider};
 use clients_binance::BinancePerpsClient;
-use clients_uniswapv3::UniswapV3PositionManager;
+use clients_uniswapv3::{AmmConfig, UniswapV3PositionManager};
 use reqwest::Client;
 use serde::Deserialize;
 use std::str::FromStr;
@@ -23,12 +22,6 @@ struct PremiumIndexResponse {
     mark_price: String,
 }
 
-fn format_amount_18(value: U256) -> String {
-    let divisor = U256::from(10u64).pow(U256::from(18u64));
-    let (integer, frac) = value.div_rem(divisor);
-    format!("{}.{:0>18}", integer, frac)
-}
-
 async fn fetch_bnb_mark_price(client: &Client) -> Result<f64, Box<dyn std::error::Error>> {
     let resp = client
         .get(BINANCE_PREMIUM_INDEX_URL)
@@ -81,42 +74,72 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("BNB mark price (BNBUSDT): {}", bnb_mark_price);
 
     let provider = Arc::new(RootProvider::<Ethereum>::new_http(rpc_url.parse()?).erased());
+    let chain_id = provider.get_chain_id().await?;
     let uniswap_config = clients_uniswapv3::UniswapV3PositionManagerConfig {
         address: contract_address,
+        swap_router: None,
+        confirmations: 0,
+        amm: AmmConfig::known(chain_id, contract_address).unwrap_or_default(),
     };
     let mut manager = UniswapV3PositionManager::new(uniswap_config, provider);
-    manager.sync_lp(owner).await?;
+    let synced = manager.sync_lp(owner).await?;
 
     let positions = manager.positions();
-    println!("Owner: {} | Positions: {}", owner, positions.len());
+    println!(
+        "Owner: {} | Positions: {} | Block: {} ({})",
+        owner,
+        positions.len(),
+        synced.number,
+        synced.hash
+    );
     for (token_id, pos) in positions {
-        let w0 = utils::u256_to_f64(pos.withdrawable_amount0, 18);
-        let w1 = utils::u256_to_f64(pos.withdrawable_amount1, 18);
-        let c0 = utils::u256_to_f64(pos.collectable_amount0, 18);
-        let c1 = utils::u256_to_f64(pos.collectable_amount1, 18);
+        let d0 = pos.token0_metadata.decimals;
+        let d1 = pos.token1_metadata.decimals;
+        let w0 = utils::u256_to_f64(pos.withdrawable_amount0, d0);
+        let w1 = utils::u256_to_f64(pos.withdrawable_amount1, d1);
+        let c0 = utils::u256_to_f64(pos.collectable_amount0, d0);
+        let c1 = utils::u256_to_f64(pos.collectable_amount1, d1);
         let withdrawable_usd = w0 + w1 * bnb_mark_price;
         let collectable_usd = c0 + c1 * bnb_mark_price;
 
         println!("---");
         println!("  token_id: {}", token_id);
-        println!("  token0:  {} (USD)", pos.token0);
-        println!("  token1:  {} (BNB)", pos.token1);
+        println!(
+            "  token0:  {} ({}, {} decimals)",
+            pos.token0, pos.token0_metadata.symbol, d0
+        );
+        println!(
+            "  token1:  {} ({}, {} decimals)",
+            pos.token1, pos.token1_metadata.symbol, d1
+        );
         println!("  liquidity: {}", pos.liquidity);
+        println!("  sync_status: {}", pos.sync_status);
+        if let Some(farm) = pos.staked_in {
+            println!("  staked_in: {}", farm);
+            let reward = manager
+                .reward_token()
+                .and_then(|token| manager.token_metadata(token));
+            println!(
+                "  pending_reward: {} {}",
+                utils::format_units(pos.pending_reward, reward.map_or(18, |m| m.decimals)),
+                reward.map_or("", |m| m.symbol.as_str())
+            );
+        }
         println!(
-            "  withdrawable_amount0 (18 decimals): {}",
-            format_amount_18(pos.withdrawable_amount0)
+            "  withdrawable_amount0: {}",
+            utils::format_units(pos.withdrawable_amount0, d0)
         );
         println!(
-            "  withdrawable_amount1 (18 decimals): {}",
-            format_amount_18(pos.withdrawable_amount1)
+            "  withdrawable_amount1: {}",
+            utils::format_units(pos.withdrawable_amount1, d1)
         );
         println!(
-            "  collectable_amount0 (18 decimals): {}",
-            format_amount_18(pos.collectable_amount0)
+            "  collectable_amount0: {}",
+            utils::format_units(pos.collectable_amount0, d0)
         );
         println!(
-            "  collectable_amount1 (18 decimals): {}",
-            format_amount_18(pos.collectable_amount1)
+            "  collectable_amount1: {}",
+            utils::format_units(pos.collectable_amount1, d1)
         );
         println!("  withdrawable (USD): {}", withdrawable_usd);
         println!("  collectable (USD): {}", collectable_usd);
@@ -0,0 +1,93 @@
+//! Uniswap V4 positions example: sync and print all positions for an owner.
+//! Usage: uniswapv4-positions <owner_address> <position_manager> <state_view> <rpc_url> [start_block] [native_symbol]
+//! Positions are discovered from PositionManager `Transfer` logs starting at `start_block`
+//! (default 0; pass the PositionManager deployment block to keep the scan short).
+
+use alloy::network::Ethereum;
+use alloy::primitives::Address;
+use alloy::providers::{Provider, RootProvider};
+use clients_uniswapv4::{UniswapV4PositionManager, UniswapV4PositionManagerConfig};
+use std::str::FromStr;
+use std::sync::Arc;
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let args: Vec<String> = std::env::args().collect();
+    if args.len() < 5 {
+        eprintln!(
+            "Usage: {} <owner_address> <position_manager> <state_view> <rpc_url> [start_block] [native_symbol]",
+            args.first()
+                .map(|s| s.as_str())
+                .unwrap_or("uniswapv4-positions")
+        );
+        std::process::exit(1);
+    }
+
+    let owner = Address::from_str(args[1].trim())?;
+    let position_manager = Address::from_str(args[2].trim())?;
+    let state_view = Address::from_str(args[3].trim())?;
+    let rpc_url = args[4].trim();
+
+    let mut config = UniswapV4PositionManagerConfig::new(position_manager, state_view);
+    if let Some(start_block) = args.get(5) {
+        config.start_block = start_block.trim().parse()?;
+    }
+    if let Some(native_symbol) = args.get(6) {
+        config.native_symbol = native_symbol.trim().to_string();
+    }
+
+    let provider = Arc::new(RootProvider::<Ethereum>::new_http(rpc_url.parse()?).erased());
+    let mut manager = UniswapV4PositionManager::new(config, provider);
+    let synced = manager.sync_lp(owner).await?;
+
+    let positions = manager.positions();
+    println!(
+        "Owner: {} | Positions: {} | Block: {} ({})",
+        owner,
+        positions.len(),
+        synced.number,
+        synced.hash
+    );
+    for (token_id, pos) in positions {
+        let d0 = pos.token0_metadata.decimals;
+        let d1 = pos.token1_metadata.decimals;
+        println!("---");
+        println!("  token_id: {}", token_id);
+        if let Some(pool_id) = pos.pool_id {
+            println!("  pool_id: {}", pool_id);
+        }
+        println!(
+            "  token0:  {} ({}, {} decimals)",
+            pos.token0, pos.token0_metadata.symbol, d0
+        );
+        println!(
+            "  token1:  {} ({}, {} decimals)",
+            pos.token1, pos.token1_metadata.symbol, d1
+        );
+        println!("  fee: {}", pos.fee);
+        println!(
+            "  range: [{}, {}) | tick: {}",
+            pos.tick_lower, pos.tick_upper, pos.tick
+        );
+        println!("  liquidity: {}", pos.liquidity);
+        println!("  sync_status: {}", pos.sync_status);
+        println!(
+            "  withdrawable_amount0: {}",
+            utils::format_units(pos.withdrawable_amount0, d0)
+        );
+        println!(
+            "  withdrawable_amount1: {}",
+            utils::format_units(pos.withdrawable_amount1, d1)
+        );
+        println!(
+            "  collectable_amount0: {}",
+            utils::format_units(pos.collectable_amount0, d0)
+        );
+        println!(
+            "  collectable_amount1: {}",
+            utils::format_units(pos.collectable_amount1, d1)
+        );
+    }
+
+    Ok(())
+}
@@ -0,0 +1,725 @@
+//! Backtesting of the hedge thresholds against historical prices.
+//!
+//! Pool prices are replayed through the V3 amounts of one LP position (`LpGreeks`), and the
+//! futures hedge follows the same decision as `LPHStrategy::execute`. Orders fill at the mark
+//! price moved by the configured slippage and pay the trading fee; funding is settled on the
+//! position held at each funding time.
+//!
+//! LP fee income is estimated from the replayed path only: between two pool prices the token
+//! flowing into the position pays the pool fee (`lp_fee_bps`). Swaps that net out between
+//! samples are not seen, so the estimate is a lower bound; with `lp_fee_bps = 0` fee income is
+//! excluded.
+
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::decision;
+use crate::greeks::LpGreeks;
+
+/// Price observation
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct PriceSample {
+    /// Time of the observation, in seconds since Unix epoch
+    pub timestamp: u64,
+    /// BASE price in USDT
+    pub price: f64,
+}
+
+/// Funding settlement of the perpetual
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct FundingSample {
+    /// Settlement time, in seconds since Unix epoch
+    pub timestamp: u64,
+    /// Funding rate of the period (positive: longs pay shorts)
+    pub funding_rate: f64,
+}
+
+/// Historical market data replayed by a backtest
+#[derive(Debug, Clone, Default)]
+pub struct BacktestData {
+    /// BASE price in USDT implied by the pool, e.g. after each Swap event
+    pub pool_prices: Vec<PriceSample>,
+    /// Perpetual mark prices; before the first one the pool price is used
+    pub mark_prices: Vec<PriceSample>,
+    /// Funding settlements
+    pub funding: Vec<FundingSample>,
+}
+
+impl BacktestData {
+    /// Sorts every series by timestamp, keeping the order of equal timestamps
+    pub fn sort(&mut self) {
+        self.pool_prices.sort_by_key(|sample| sample.timestamp);
+        self.mark_prices.sort_by_key(|sample| sample.timestamp);
+        self.funding.sort_by_key(|sample| sample.timestamp);
+    }
+}
+
+/// LP position replayed by a backtest
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BacktestPosition {
+    /// Lower tick of the range
+    pub tick_lower: i32,
+    /// Upper tick of the range
+    pub tick_upper: i32,
+    /// Position liquidity
+    pub liquidity: f64,
+    /// Whether BASE is token0 of the pool
+    pub base_is_token0: bool,
+    /// Decimals of the BASE token
+    pub base_decimals: u32,
+    /// Decimals of the USDT token
+    pub usdt_decimals: u32,
+}
+
+impl BacktestPosition {
+    /// Delta model of the position
+    pub fn greeks(&self) -> LpGreeks {
+        LpGreeks::from_range(
+            self.tick_lower,
+            self.tick_upper,
+            self.liquidity,
+            self.base_is_token0,
+            self.base_decimals,
+            self.usdt_decimals,
+        )
+    }
+}
+
+/// Hedge parameters and execution model of a backtest
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BacktestConfig {
+    /// Threshold for base_delta_ratio (n), as in `LPHStrategyConfig`
+    pub base_delta_ratio_threshold: f64,
+    /// Threshold for |base_delta| (m) and quantity step, as in `LPHStrategyConfig`
+    pub base_delta_threshold: f64,
+    /// Trading fee charged on every fill, in basis points of the notional
+    #[serde(default = "default_fee_bps")]
+    pub fee_bps: f64,
+    /// Fill price distance from the mark price against the order, in basis points
+    #[serde(default)]
+    pub slippage_bps: f64,
+    /// Minimum seconds between hedge decisions (the status interval of the live strategy)
+    #[serde(default = "default_decision_interval_secs")]
+    pub decision_interval_secs: u64,
+    /// Signed futures position at the start, in BASE units
+    #[serde(default)]
+    pub initial_futures_position: f64,
+    /// Seconds between points of the reported series
+    #[serde(default = "default_report_interval_secs")]
+    pub report_interval_secs: u64,
+    /// Pool fee tier earned by the LP position, in basis points (e.g. 5 for a 0.05% pool);
+    /// 0 excludes LP fee income
+    #[serde(default)]
+    pub lp_fee_bps: f64,
+}
+
+fn default_fee_bps() -> f64 {
+    2.0
+}
+
+fn default_decision_interval_secs() -> u64 {
+    90
+}
+
+fn default_report_interval_secs() -> u64 {
+    3_600
+}
+
+impl BacktestConfig {
+    /// Checks the parameters for values the backtest cannot run with
+    pub fn validate(&self) -> Result<()> {
+        for (field, value) in [
+            (
+                "base_delta_ratio_threshold",
+                self.base_delta_ratio_threshold,
+            ),
+            ("fee_bps", self.fee_bps),
+            ("slippage_bps", self.slippage_bps),
+            ("lp_fee_bps", self.lp_fee_bps),
+        ] {
+            if !value.is_finite() || value < 0.0 {
+                bail!("{}: must be a non-negative number, got {}", field, value);
+            }
+        }
+        if !self.base_delta_threshold.is_finite() || self.base_delta_threshold <= 0.0 {
+            bail!(
+                "base_delta_threshold: must be a positive number, got {}",
+                self.base_delta_threshold
+            );
+        }
+        if !self.initial_futures_position.is_finite() {
+            bail!("initial_futures_position: must be a finite number");
+        }
+        if self.report_interval_secs == 0 {
+            bail!("report_interval_secs: must be positive");
+        }
+        Ok(())
+    }
+}
+
+/// State of the backtest at one point of the series
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BacktestPoint {
+    /// Time of the point, in seconds since Unix epoch
+    pub timestamp: u64,
+    /// BASE price in USDT implied by the pool
+    pub pool_price: f64,
+    /// Perpetual mark price
+    pub mark_price: f64,
+    /// BASE amount of the LP position
+    pub amm_base_amount: f64,
+    /// Value of the LP position, in USDT
+    pub amm_value_usdt: f64,
+    /// Signed futures position, in BASE units
+    pub futures_position: f64,
+    /// Net BASE exposure left unhedged (`amm_base_amount + futures_position`)
+    pub hedge_error: f64,
+    /// Change of the LP value since the start, in USDT
+    pub lp_pnl_usdt: f64,
+    /// LP fee income estimated from the price path, in USDT
+    pub lp_fees_usdt: f64,
+    /// PnL of the futures trades at the mark price, before fees and funding, in USDT
+    pub futures_pnl_usdt: f64,
+    /// Trading fees paid, in USDT
+    pub trading_fees_usdt: f64,
+    /// Funding received (negative: paid), in USDT
+    pub funding_usdt: f64,
+    /// `lp_pnl_usdt + lp_fees_usdt + futures_pnl_usdt - trading_fees_usdt + funding_usdt`
+    pub hedged_pnl_usdt: f64,
+    /// Notional of every fill, in USDT
+    pub turnover_usdt: f64,
+    /// Number of fills
+    pub trades: u64,
+}
+
+/// Totals of one backtest run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BacktestSummary {
+    /// Threshold for base_delta_ratio (n) of the run
+    pub base_delta_ratio_threshold: f64,
+    /// Threshold for |base_delta| (m) of the run
+    pub base_delta_threshold: f64,
+    /// Time of the first pool price, in seconds since Unix epoch
+    pub start_timestamp: u64,
+    /// Time of the last pool price, in seconds since Unix epoch
+    pub end_timestamp: u64,
+    /// Change of the LP value (the unhedged PnL), in USDT
+    pub lp_pnl_usdt: f64,
+    /// LP fee income estimated from the price path, in USDT (0 when excluded)
+    pub lp_fees_usdt: f64,
+    /// PnL of the futures trades at the final mark price, in USDT
+    pub futures_pnl_usdt: f64,
+    /// Trading fees paid, in USDT
+    pub trading_fees_usdt: f64,
+    /// Funding received (negative: paid), in USDT
+    pub funding_usdt: f64,
+    /// `lp_pnl_usdt + lp_fees_usdt + futures_pnl_usdt - trading_fees_usdt + funding_usdt`
+    pub hedged_pnl_usdt: f64,
+    /// Notional of every fill, in USDT
+    pub turnover_usdt: f64,
+    /// Number of fills
+    pub trades: u64,
+    /// Time-weighted mean of |hedge_error| valued at the mark price, in USDT
+    pub mean_abs_hedge_error_usdt: f64,
+    /// Largest |hedge_error| valued at the mark price, in USDT
+    pub max_abs_hedge_error_usdt: f64,
+    /// Signed futures position at the end, in BASE units
+    pub final_futures_position: f64,
+}
+
+/// Series and totals of one backtest run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BacktestResult {
+    /// Totals of the run
+    pub summary: BacktestSummary,
+    /// State every `report_interval_secs`, plus the last sample
+    pub series: Vec<BacktestPoint>,
+}
+
+impl BacktestResult {
+    /// Serializes the result as pretty-printed JSON
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    /// Series as CSV, one row per point
+    pub fn series_csv(&self) -> String {
+        let mut csv = String::from(
+            "timestamp,pool_price,mark_price,amm_base_amount,amm_value_usdt,futures_position,hedge_error,lp_pnl_usdt,lp_fees_usdt,futures_pnl_usdt,trading_fees_usdt,funding_usdt,hedged_pnl_usdt,turnover_usdt,trades\n",
+        );
+        for point in &self.series {
+            csv.push_str(&format!(
+                "{},{},{},{},{},{},{},{},{},{},{},{},{},{},{}\n",
+                point.timestamp,
+                point.pool_price,
+                point.mark_price,
+                point.amm_base_amount,
+                point.amm_value_usdt,
+                point.futures_position,
+                point.hedge_error,
+                point.lp_pnl_usdt,
+                point.lp_fees_usdt,
+                point.futures_pnl_usdt,
+                point.trading_fees_usdt,
+                point.funding_usdt,
+                point.hedged_pnl_usdt,
+                point.turnover_usdt,
+                point.trades
+            ));
+        }
+        csv
+    }
+}
+
+/// Summaries of a parameter sweep as CSV, one row per run
+pub fn summaries_csv(summaries: &[BacktestSummary]) -> String {
+    let mut csv = String::from(
+        "base_delta_ratio_threshold,base_delta_threshold,lp_pnl_usdt,lp_fees_usdt,futures_pnl_usdt,trading_fees_usdt,funding_usdt,hedged_pnl_usdt,turnover_usdt,trades,mean_abs_hedge_error_usdt,max_abs_hedge_error_usdt,final_futures_position\n",
+    );
+    for summary in summaries {
+        csv.push_str(&format!(
+            "{},{},{},{},{},{},{},{},{},{},{},{},{}\n",
+            summary.base_delta_ratio_threshold,
+            summary.base_delta_threshold,
+            summary.lp_pnl_usdt,
+            summary.lp_fees_usdt,
+            summary.futures_pnl_usdt,
+            summary.trading_fees_usdt,
+            summary.funding_usdt,
+            summary.hedged_pnl_usdt,
+            summary.turnover_usdt,
+            summary.trades,
+            summary.mean_abs_hedge_error_usdt,
+            summary.max_abs_hedge_error_usdt,
+            summary.final_futures_position
+        ));
+    }
+    csv
+}
+
+/// Replays the data with one set of hedge parameters
+///
+/// At each pool price the latest mark price at or before it is used, and funding settled
+/// since the previous pool price is applied first. A hedge decision is taken at the first
+/// pool price at least `decision_interval_secs` after the previous decision. LP fees accrue
+/// on the BASE or USDT amount the position gained since the previous pool price.
+///
+/// # Arguments
+/// * `position` - Delta model of the LP position
+/// * `data` - Market data, sorted by timestamp (see `BacktestData::sort`)
+/// * `config` - Hedge parameters and execution model
+///
+/// # Returns
+/// The series and totals, or an error if there is no pool price or the config is invalid
+pub fn run_backtest(
+    position: &LpGreeks,
+    data: &BacktestData,
+    config: &BacktestConfig,
+) -> Result<BacktestResult> {
+    config.validate()?;
+    let Some(first) = data.pool_prices.first() else {
+        bail!("backtest needs at least one pool price");
+    };
+
+    let initial_value = position.value(first.price);
+    let mut marks = data.mark_prices.iter().peekable();
+    let mut mark_price = None;
+    let mut funding = data.funding.iter().peekable();
+    let mut futures_position = config.initial_futures_position;
+    // Cash of the futures trades: futures PnL is `cash + position * mark`
+    let mut cash = None;
+    let mut trading_fees = 0.0;
+    let mut lp_fees = 0.0;
+    let mut amounts = position.amounts(first.price);
+    let mut funding_usdt = 0.0;
+    let mut turnover = 0.0;
+    let mut trades = 0u64;
+    let mut last_decision: Option<u64> = None;
+    let mut next_report = first.timestamp;
+    let mut weighted_error = 0.0;
+    let mut weighted_time = 0.0;
+    let mut max_error = 0.0f64;
+    let mut previous: Option<(u64, f64)> = None;
+    let mut series = Vec::new();
+
+    for (i, sample) in data.pool_prices.iter().enumerate() {
+        let timestamp = sample.timestamp;
+        while let Some(settlement) = funding.next_if(|f| f.timestamp <= timestamp) {
+            while let Some(mark) = marks.next_if(|m| m.timestamp <= settlement.timestamp) {
+                mark_price = Some(mark.price);
+            }
+            let price = mark_price.unwrap_or(sample.price);
+            funding_usdt -= futures_position * price * settlement.funding_rate;
+        }
+        while let Some(mark) = marks.next_if(|m| m.timestamp <= timestamp) {
+            mark_price = Some(mark.price);
+        }
+        let mark = mark_price.unwrap_or(sample.price);
+        let cash = cash.get_or_insert(-futures_position * mark);
+
+        if let Some((previous_timestamp, error)) = previous {
+            let elapsed = timestamp.saturating_sub(previous_timestamp) as f64;
+            weighted_error += error * elapsed;
+            weighted_time += elapsed;
+        }
+
+        let decision_due =
+            last_decision.is_none_or(|last| timestamp >= last + config.decision_interval_secs);
+        if decision_due {
+            last_decision = Some(timestamp);
+            let amm_base_amount = position.delta(sample.price);
+            let base_delta = amm_base_amount + futures_position;
+            let base_delta_ratio = decision::base_delta_ratio(amm_base_amount, futures_position);
+            if let Some(quantity) = decision::order_quantity(
+                base_delta_ratio,
+                base_delta,
+                config.base_delta_ratio_threshold,
+                config.base_delta_threshold,
+            ) {
+                // Selling for a positive delta makes the futures position more negative
+                let signed = -quantity.copysign(base_delta);
+                let fill = mark * (1.0 + signed.signum() * config.slippage_bps / 10_000.0);
+                *cash -= signed * fill;
+                futures_position += signed;
+                trading_fees += quantity * fill * config.fee_bps / 10_000.0;
+                turnover += quantity * fill;
+                trades += 1;
+            }
+        }
+
+        // Swaps moving the price pay the pool fee on the token they sell to the position
+        let (base_amount, usdt_amount) = position.amounts(sample.price);
+        lp_fees += ((base_amount - amounts.0).max(0.0) * sample.price
+            + (usdt_amount - amounts.1).max(0.0))
+            * config.lp_fee_bps
+            / 10_000.0;
+        amounts = (base_amount, usdt_amount);
+
+        let amm_base_amount = position.delta(sample.price);
+        let hedge_error = amm_base_amount + futures_position;
+        let error_usdt = (hedge_error * mark).abs();
+        max_error = max_error.max(error_usdt);
+        previous = Some((timestamp, error_usdt));
+
+        let last = i + 1 == data.pool_prices.len();
+        if timestamp >= next_report || last {
+            while next_report <= timestamp {
+                next_report += config.report_interval_secs;
+            }
+            let amm_value = position.value(sample.price);
+            let futures_pnl = *cash + futures_position * mark;
+            let lp_pnl = amm_value - initial_value;
+            series.push(BacktestPoint {
+                timestamp,
+                pool_price: sample.price,
+                mark_price: mark,
+                amm_base_amount,
+                amm_value_usdt: amm_value,
+                futures_position,
+                hedge_error,
+                lp_pnl_usdt: lp_pnl,
+                lp_fees_usdt: lp_fees,
+                futures_pnl_usdt: futures_pnl,
+                trading_fees_usdt: trading_fees,
+                funding_usdt,
+                hedged_pnl_usdt: lp_pnl + lp_fees + futures_pnl - trading_fees + funding_usdt,
+                turnover_usdt: turnover,
+                trades,
+            });
+        }
+    }
+
+    let Some(end) = series.last().cloned() else {
+        bail!("backtest produced no series point");
+    };
+    let summary = BacktestSummary {
+        base_delta_ratio_threshold: config.base_delta_ratio_threshold,
+        base_delta_threshold: config.base_delta_threshold,
+        start_timestamp: first.timestamp,
+        end_timestamp: end.timestamp,
+        lp_pnl_usdt: end.lp_pnl_usdt,
+        lp_fees_usdt: end.lp_fees_usdt,
+        futures_pnl_usdt: end.futures_pnl_usdt,
+        trading_fees_usdt: end.trading_fees_usdt,
+        funding_usdt: end.funding_usdt,
+        hedged_pnl_usdt: end.hedged_pnl_usdt,
+        turnover_usdt: end.turnover_usdt,
+        trades: end.trades,
+        mean_abs_hedge_error_usdt: if weighted_time > 0.0 {
+            weighted_error / weighted_time
+        } else {
+            max_error
+        },
+        max_abs_hedge_error_usdt: max_error,
+        final_futures_position: end.futures_position,
+    };
+    Ok(BacktestResult { summary, series })
+}
+
+/// Runs the backtest for every combination of thresholds
+///
+/// # Arguments
+/// * `position` - Delta model of the LP position
+/// * `data` - Market data, sorted by timestamp
+/// * `config` - Execution model; its thresholds are replaced by each combination
+/// * `ratio_thresholds` - Values of `base_delta_ratio_threshold` (n)
+/// * `delta_thresholds` - Values of `base_delta_threshold` (m)
+///
+/// # Returns
+/// One summary per combination, ratio thresholds in the outer loop
+pub fn sweep(
+    position: &LpGreeks,
+    data: &BacktestData,
+    config: &BacktestConfig,
+    ratio_thresholds: &[f64],
+    delta_thresholds: &[f64],
+) -> Result<Vec<BacktestSummary>> {
+    let mut summaries = Vec::with_capacity(ratio_thresholds.len() * delta_thresholds.len());
+    for &ratio_threshold in ratio_thresholds {
+        for &delta_threshold in delta_thresholds {
+            let run = BacktestConfig {
+                base_delta_ratio_threshold: ratio_threshold,
+                base_delta_threshold: delta_threshold,
+                ..config.clone()
+            };
+            summaries.push(run_backtest(position, data, &run)?.summary);
+        }
+    }
+    Ok(summaries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// BASE as token0 with 18 decimals on both sides, range about 54.6 to 181.3 USDT
+    fn position() -> LpGreeks {
+        LpGreeks::from_range(40_000, 52_000, 4e20, true, 18, 18)
+    }
+
+    fn prices(samples: &[(u64, f64)]) -> Vec<PriceSample> {
+        samples
+            .iter()
+            .map(|&(timestamp, price)| PriceSample { timestamp, price })
+            .collect()
+    }
+
+    fn config(ratio_threshold: f64, delta_threshold: f64) -> BacktestConfig {
+        BacktestConfig {
+            base_delta_ratio_threshold: ratio_threshold,
+            base_delta_threshold: delta_threshold,
+            fee_bps: 0.0,
+            slippage_bps: 0.0,
+            decision_interval_secs: 0,
+            initial_futures_position: 0.0,
+            report_interval_secs: 1,
+            lp_fee_bps: 0.0,
+        }
+    }
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() < 1e-9 * expected.abs().max(1.0),
+            "{} != {}",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn hedge_sells_the_lp_delta_and_keeps_an_over_hedge() {
+        let position = position();
+        let data = BacktestData {
+            pool_prices: prices(&[(0, 100.0), (100, 90.0), (200, 100.0), (300, 80.0)]),
+            ..Default::default()
+        };
+        let step = 0.1;
+        let result = run_backtest(&position, &data, &config(0.0, step)).unwrap();
+        let series = &result.series;
+        assert_eq!(series.len(), 4);
+
+        // Falling prices grow the LP delta and sell more; the rise back leaves it over-hedged
+        let positions: Vec<f64> = series.iter().map(|p| p.futures_position).collect();
+        assert!(positions[0] < 0.0);
+        assert!(positions[1] < positions[0]);
+        assert_eq!(positions[2], positions[1]);
+        assert!(series[2].hedge_error < -step / 2.0);
+        assert!(positions[3] < positions[2]);
+        assert_eq!(result.summary.trades, 3);
+
+        for point in [&series[0], &series[1], &series[3]] {
+            assert!(point.hedge_error.abs() <= step / 2.0 + 1e-9);
+            let steps = point.futures_position / step;
+            assert!((steps - steps.round()).abs() < 1e-9);
+        }
+
+        // Without fees or slippage every sale fills at the pool price it was decided on
+        let end = series.last().unwrap();
+        let mut expected_pnl = 0.0;
+        let mut held = 0.0;
+        let mut turnover = 0.0;
+        for point in series {
+            let sold = held - point.futures_position;
+            expected_pnl += sold * (point.pool_price - end.pool_price);
+            turnover += sold.abs() * point.pool_price;
+            held = point.futures_position;
+        }
+        assert_close(result.summary.futures_pnl_usdt, expected_pnl);
+        assert_close(result.summary.turnover_usdt, turnover);
+        assert_close(
+            result.summary.lp_pnl_usdt,
+            position.value(80.0) - position.value(100.0),
+        );
+    }
+
+    #[test]
+    fn fills_pay_slippage_and_trading_fee() {
+        let position = position();
+        let data = BacktestData {
+            pool_prices: prices(&[(0, 100.0)]),
+            ..Default::default()
+        };
+        let run = BacktestConfig {
+            fee_bps: 2.0,
+            slippage_bps: 10.0,
+            ..config(0.0, 0.1)
+        };
+        let summary = run_backtest(&position, &data, &run).unwrap().summary;
+        let quantity = -summary.final_futures_position;
+        assert!(quantity > 0.0);
+
+        let fill = 100.0 * (1.0 - 10.0 / 10_000.0);
+        assert_close(summary.turnover_usdt, quantity * fill);
+        assert_close(summary.trading_fees_usdt, quantity * fill * 2.0 / 10_000.0);
+        assert_close(summary.futures_pnl_usdt, quantity * (fill - 100.0));
+    }
+
+    #[test]
+    fn funding_settles_on_the_held_position_at_the_latest_mark() {
+        let position = position();
+        let data = BacktestData {
+            pool_prices: prices(&[(0, 100.0), (100, 100.0), (200, 100.0), (300, 100.0)]),
+            mark_prices: prices(&[(0, 100.0), (150, 110.0)]),
+            funding: vec![
+                FundingSample {
+                    timestamp: 100,
+                    funding_rate: 0.001,
+                },
+                FundingSample {
+                    timestamp: 200,
+                    funding_rate: -0.0005,
+                },
+            ],
+        };
+        // Thresholds no delta reaches, so the initial short is held throughout
+        let run = BacktestConfig {
+            initial_futures_position: -2.0,
+            ..config(1e9, 1e9)
+        };
+        let result = run_backtest(&position, &data, &run).unwrap();
+        let summary = &result.summary;
+        assert_eq!(summary.trades, 0);
+
+        // The short receives 2 * 100 * 0.001 and pays 2 * 110 * 0.0005
+        assert_close(summary.funding_usdt, 0.2 - 0.11);
+        assert_close(result.series[1].funding_usdt, 0.2);
+        // Opened at the first mark of 100, valued at the last mark of 110
+        assert_close(summary.futures_pnl_usdt, -20.0);
+        assert_close(summary.lp_pnl_usdt, 0.0);
+        assert_close(summary.hedged_pnl_usdt, -20.0 + 0.09);
+    }
+
+    #[test]
+    fn lp_fees_accrue_on_the_token_sold_to_the_position() {
+        let position = position();
+        let data = BacktestData {
+            pool_prices: prices(&[(0, 100.0), (100, 90.0), (200, 100.0)]),
+            ..Default::default()
+        };
+        let run = BacktestConfig {
+            lp_fee_bps: 30.0,
+            ..config(1e9, 1e9)
+        };
+        let summary = run_backtest(&position, &data, &run).unwrap().summary;
+
+        let (base_100, usdt_100) = position.amounts(100.0);
+        let (base_90, usdt_90) = position.amounts(90.0);
+        // The fall sells BASE to the position, the rise sells USDT back
+        let expected = 0.003 * ((base_90 - base_100) * 90.0 + (usdt_100 - usdt_90));
+        assert!(expected > 0.0);
+        assert_close(summary.lp_fees_usdt, expected);
+        assert_close(summary.lp_pnl_usdt, 0.0);
+        assert_close(summary.hedged_pnl_usdt, expected);
+
+        let excluded = run_backtest(&position, &data, &config(1e9, 1e9))
+            .unwrap()
+            .summary;
+        assert_eq!(excluded.lp_fees_usdt, 0.0);
+    }
+
+    #[test]
+    fn lp_fees_do_not_accrue_out_of_range() {
+        let position = position();
+        let data = BacktestData {
+            pool_prices: prices(&[(0, 200.0), (100, 250.0), (200, 200.0)]),
+            ..Default::default()
+        };
+        let run = BacktestConfig {
+            lp_fee_bps: 30.0,
+            ..config(1e9, 1e9)
+        };
+        let summary = run_backtest(&position, &data, &run).unwrap().summary;
+        assert_eq!(summary.lp_fees_usdt, 0.0);
+    }
+
+    #[test]
+    fn sweep_runs_ratio_thresholds_in_the_outer_loop() {
+        let position = position();
+        let data = BacktestData {
+            pool_prices: prices(&[(0, 100.0), (100, 90.0)]),
+            ..Default::default()
+        };
+        let summaries = sweep(
+            &position,
+            &data,
+            &config(0.0, 1.0),
+            &[0.0, 0.5],
+            &[0.1, 1.0],
+        )
+        .unwrap();
+        let thresholds: Vec<(f64, f64)> = summaries
+            .iter()
+            .map(|s| (s.base_delta_ratio_threshold, s.base_delta_threshold))
+            .collect();
+        assert_eq!(thresholds, [(0.0, 0.1), (0.0, 1.0), (0.5, 0.1), (0.5, 1.0)]);
+
+        // Each summary matches a single run with its thresholds
+        for summary in &summaries {
+            let single = run_backtest(
+                &position,
+                &data,
+                &config(
+                    summary.base_delta_ratio_threshold,
+                    summary.base_delta_threshold,
+                ),
+            )
+            .unwrap()
+            .summary;
+            assert_eq!(summary.trades, single.trades);
+            assert_eq!(
+                summary.final_futures_position,
+                single.final_futures_position
+            );
+        }
+        assert_eq!(summaries_csv(&summaries).lines().count(), 5);
+    }
+
+    #[test]
+    fn config_rejects_a_negative_lp_fee_tier() {
+        let run = BacktestConfig {
+            lp_fee_bps: -1.0,
+            ..config(0.0, 0.1)
+        };
+        assert!(run.validate().is_err());
+    }
+}
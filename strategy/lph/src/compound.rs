@@ -0,0 +1,19 @@
+//! Gas-aware threshold for compounding LP fees.
+
+/// Wei per unit of the native gas token.
+const WEI_PER_NATIVE: f64 = 1e18;
+
+/// Estimates the USDT cost of spending `gas_units` at `gas_price` wei per gas
+pub(crate) fn estimated_gas_usdt(gas_price: u128, gas_units: u64, native_price_usdt: f64) -> f64 {
+    gas_price as f64 * gas_units as f64 / WEI_PER_NATIVE * native_price_usdt
+}
+
+/// Returns true if fees worth `fee_value_usdt` justify a compounding action costing
+/// `gas_usdt`, i.e. they exceed `min_fee_to_gas_ratio` times the gas cost
+pub(crate) fn should_compound(
+    fee_value_usdt: f64,
+    gas_usdt: f64,
+    min_fee_to_gas_ratio: f64,
+) -> bool {
+    fee_value_usdt > 0.0 && fee_value_usdt > gas_usdt * min_fee_to_gas_ratio
+}
@@ -1,8 +1,19 @@
 //! Configuration types for LPH Monitor.
 
-use alloy::primitives::Address;
+use alloy::primitives::{Address, U256};
+use anyhow::{bail, Result};
+use clients_uniswapv3::{LiquidityTxConfig, PositionEventTrackerConfig};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// Default relative price shocks used for the exposure ladder (±1%, ±5%, ±10%).
+pub const DEFAULT_PRICE_SHOCKS: [f64; 6] = [-0.10, -0.05, -0.01, 0.01, 0.05, 0.10];
 
 /// Configuration for LPHStrategy (parameters only; clients are passed to `LPHStrategy::new`).
+///
+/// Deserializable so it can be embedded in a config file; call `validate` on the result.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct LPHStrategyConfig {
     /// Ethereum address that owns the Uniswap V3 LP positions
     pub owner: Address,
@@ -16,4 +27,352 @@ pub struct LPHStrategyConfig {
     pub base_delta_ratio_threshold: f64,
     /// Threshold for base_delta magnitude (m): execute only when |base_delta| > m; also used as quantity step for rounding
     pub base_delta_threshold: f64,
+    /// LP position NFT IDs to hedge; empty hedges every position of `owner` on the BASE/USDT pair
+    #[serde(default)]
+    pub lp_position_ids: Vec<U256>,
+    /// Relative BASE price shocks evaluated for the exposure ladder (e.g. `0.05` for +5%)
+    #[serde(default = "default_price_shocks")]
+    pub price_shocks: Vec<f64>,
+    /// Automatic re-centering of out-of-range positions; `None` leaves ranges untouched
+    #[serde(default)]
+    pub rebalance: Option<RebalanceConfig>,
+    /// Automatic fee compounding; `None` leaves fees in the positions
+    #[serde(default)]
+    pub compound: Option<CompoundConfig>,
+    /// Event-driven position tracking; `None` relies on the fixed status interval only
+    #[serde(default)]
+    pub event_tracking: Option<PositionEventTrackerConfig>,
+    /// Binance futures symbol pricing the farm reward token of staked positions (e.g. "CAKEUSDT");
+    /// `None` reports pending rewards without a USDT value
+    #[serde(default)]
+    pub reward_symbol: Option<String>,
+    /// Pre-trade limits on hedge orders
+    #[serde(default)]
+    pub risk: RiskLimits,
+    /// Paper trading: hedge orders fill on a simulated account instead of Binance; `None` trades live
+    #[serde(default)]
+    pub paper_trading: Option<PaperTradingConfig>,
+}
+
+fn default_price_shocks() -> Vec<f64> {
+    DEFAULT_PRICE_SHOCKS.to_vec()
+}
+
+/// Pre-trade limits checked by the risk module before every hedge order; `None` disables a limit
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RiskLimits {
+    /// Maximum quantity of a single hedge order, in BASE units
+    #[serde(default)]
+    pub max_order_size: Option<f64>,
+    /// Maximum notional of a single hedge order at its limit price, in USDT
+    #[serde(default)]
+    pub max_order_notional: Option<f64>,
+    /// Maximum absolute notional of the futures position after an order at the mark price, in USDT
+    #[serde(default)]
+    pub max_hedge_notional: Option<f64>,
+    /// Lowest signed futures position allowed after an order, in BASE units (e.g. `-50` caps the short)
+    #[serde(default)]
+    pub min_position: Option<f64>,
+    /// Highest signed futures position allowed after an order, in BASE units (e.g. `0` forbids a long)
+    #[serde(default)]
+    pub max_position: Option<f64>,
+    /// Maximum cumulative notional of the orders placed within `turnover_window_secs`, in USDT
+    #[serde(default)]
+    pub max_turnover_notional: Option<f64>,
+    /// Rolling window of `max_turnover_notional`, in seconds
+    #[serde(default = "default_turnover_window_secs")]
+    pub turnover_window_secs: u64,
+    /// Maximum number of orders placed within `order_window_secs`
+    #[serde(default)]
+    pub max_orders_per_window: Option<u32>,
+    /// Rolling window of `max_orders_per_window`, in seconds
+    #[serde(default = "default_order_window_secs")]
+    pub order_window_secs: u64,
+    /// Maximum deviation of an order's limit price from the mark price, in basis points
+    #[serde(default)]
+    pub max_price_deviation_bps: Option<u32>,
+    /// Kill switch: no order is placed while this file exists
+    #[serde(default)]
+    pub kill_switch_file: Option<PathBuf>,
+}
+
+impl Default for RiskLimits {
+    fn default() -> Self {
+        Self {
+            max_order_size: None,
+            max_order_notional: None,
+            max_hedge_notional: None,
+            min_position: None,
+            max_position: None,
+            max_turnover_notional: None,
+            turnover_window_secs: default_turnover_window_secs(),
+            max_orders_per_window: None,
+            order_window_secs: default_order_window_secs(),
+            max_price_deviation_bps: None,
+            kill_switch_file: None,
+        }
+    }
+}
+
+fn default_turnover_window_secs() -> u64 {
+    86_400
+}
+
+fn default_order_window_secs() -> u64 {
+    3_600
+}
+
+/// Simulated futures account used instead of the Binance account
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PaperTradingConfig {
+    /// Signed futures position the account starts with, in BASE units
+    #[serde(default)]
+    pub initial_position: f64,
+    /// Entry price of `initial_position`; `None` uses the first mark price read
+    #[serde(default)]
+    pub initial_entry_price: Option<f64>,
+    /// Maker fee charged on simulated fills, in basis points
+    #[serde(default = "default_maker_fee_bps")]
+    pub maker_fee_bps: f64,
+    /// JSON file the account is persisted to and resumed from; `None` keeps it in memory
+    #[serde(default)]
+    pub state_file: Option<PathBuf>,
+}
+
+fn default_maker_fee_bps() -> f64 {
+    2.0
+}
+
+/// Settings for re-centering LP positions whose range the pool price has left
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RebalanceConfig {
+    /// Width of the new range in ticks, centred on the current pool tick
+    pub range_width_ticks: i32,
+    /// Ticks the pool price must move past a range bound before the position is re-centred
+    pub out_of_range_buffer_ticks: i32,
+    /// Swap the withdrawn tokens to the ratio of the new range before minting
+    #[serde(default)]
+    pub swap_to_target_ratio: bool,
+    /// Slippage and deadline settings for the withdraw, swap and mint transactions
+    #[serde(default)]
+    pub tx: LiquidityTxConfig,
+}
+
+/// What to do with collected LP fees
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CompoundMode {
+    /// Add the fees back to the position via `increaseLiquidity`, swapping to the range ratio
+    Reinvest,
+    /// Collect the fees to `CompoundConfig::treasury`
+    Sweep,
+}
+
+/// Settings for collecting LP fees once they are worth more than the gas to act on them
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CompoundConfig {
+    /// Whether fees are reinvested or swept
+    pub mode: CompoundMode,
+    /// Address that receives swept fees; required with `mode = "sweep"` only
+    #[serde(default)]
+    pub treasury: Option<Address>,
+    /// Fees are compounded only when their USDT value exceeds this multiple of the estimated gas cost
+    pub min_fee_to_gas_ratio: f64,
+    /// Estimated gas units of one compounding action (collect, optional swap and increase)
+    pub gas_units: u64,
+    /// Fixed price of the native gas token in USDT; one of this and `native_symbol` is required
+    #[serde(default)]
+    pub native_price_usdt: Option<f64>,
+    /// Binance futures symbol pricing the native gas token (e.g. `BNBUSDT`); the hedged
+    /// `symbol` when BASE is the native token
+    #[serde(default)]
+    pub native_symbol: Option<String>,
+    /// Slippage and deadline settings for the compounding transactions
+    #[serde(default)]
+    pub tx: LiquidityTxConfig,
+}
+
+impl LPHStrategyConfig {
+    /// Checks the parameters for values the strategy cannot act on
+    ///
+    /// # Returns
+    /// An error naming the first offending field, e.g. `base_delta_threshold: must be a positive number, got 0`
+    pub fn validate(&self) -> Result<()> {
+        if self.owner.is_zero() {
+            bail!("owner: must not be the zero address");
+        }
+        if self.symbol.trim().is_empty() {
+            bail!("symbol: must not be empty");
+        }
+        if self.base_token_address == self.usdt_token_address {
+            bail!(
+                "usdt_token_address: must differ from base_token_address {}",
+                self.base_token_address
+            );
+        }
+        ensure_non_negative(
+            "base_delta_ratio_threshold",
+            self.base_delta_ratio_threshold,
+        )?;
+        ensure_positive("base_delta_threshold", self.base_delta_threshold)?;
+        for (i, shock) in self.price_shocks.iter().enumerate() {
+            if !shock.is_finite() || *shock <= -1.0 {
+                bail!(
+                    "price_shocks[{}]: must be a finite number above -1, got {}",
+                    i,
+                    shock
+                );
+            }
+        }
+        if let Some(rebalance) = &self.rebalance {
+            if rebalance.range_width_ticks <= 0 {
+                bail!(
+                    "rebalance.range_width_ticks: must be positive, got {}",
+                    rebalance.range_width_ticks
+                );
+            }
+            if rebalance.out_of_range_buffer_ticks < 0 {
+                bail!(
+                    "rebalance.out_of_range_buffer_ticks: must not be negative, got {}",
+                    rebalance.out_of_range_buffer_ticks
+                );
+            }
+            validate_tx("rebalance.tx", &rebalance.tx)?;
+        }
+        if let Some(compound) = &self.compound {
+            ensure_non_negative(
+                "compound.min_fee_to_gas_ratio",
+                compound.min_fee_to_gas_ratio,
+            )?;
+            match (compound.native_price_usdt, &compound.native_symbol) {
+                (Some(price), None) => ensure_positive("compound.native_price_usdt", price)?,
+                (None, Some(symbol)) if symbol.trim().is_empty() => {
+                    bail!("compound.native_symbol: must not be empty when set")
+                }
+                (None, Some(_)) => {}
+                (Some(_), Some(_)) => {
+                    bail!("compound.native_symbol: set either native_price_usdt or native_symbol, not both")
+                }
+                // Guessing that the gas token is BASE misprices gas on any other pair
+                (None, None) => bail!(
+                    "compound.native_symbol: required unless native_price_usdt is set (use the hedged symbol when BASE is the native token)"
+                ),
+            }
+            match (compound.mode, compound.treasury) {
+                (CompoundMode::Sweep, None) => {
+                    bail!("compound.treasury: required with mode = \"sweep\"")
+                }
+                (CompoundMode::Sweep, Some(treasury)) if treasury.is_zero() => {
+                    bail!("compound.treasury: must not be the zero address")
+                }
+                (CompoundMode::Reinvest, Some(_)) => {
+                    bail!("compound.treasury: only used with mode = \"sweep\"")
+                }
+                _ => {}
+            }
+            validate_tx("compound.tx", &compound.tx)?;
+        }
+        if let Some(tracking) = &self.event_tracking {
+            if tracking.max_block_range == 0 {
+                bail!("event_tracking.max_block_range: must be positive");
+            }
+        }
+        if let Some(symbol) = &self.reward_symbol {
+            if symbol.trim().is_empty() {
+                bail!("reward_symbol: must not be empty when set");
+            }
+        }
+        if let Some(paper) = &self.paper_trading {
+            if !paper.initial_position.is_finite() {
+                bail!("paper_trading.initial_position: must be a finite number");
+            }
+            if let Some(price) = paper.initial_entry_price {
+                ensure_positive("paper_trading.initial_entry_price", price)?;
+            }
+            ensure_non_negative("paper_trading.maker_fee_bps", paper.maker_fee_bps)?;
+        }
+        self.risk.validate()
+    }
+}
+
+impl RiskLimits {
+    /// Checks the limits for values that would reject every order or none
+    ///
+    /// # Returns
+    /// An error naming the first offending field, prefixed with `risk.`
+    pub fn validate(&self) -> Result<()> {
+        let limits = [
+            ("risk.max_order_size", self.max_order_size),
+            ("risk.max_order_notional", self.max_order_notional),
+            ("risk.max_hedge_notional", self.max_hedge_notional),
+            ("risk.max_turnover_notional", self.max_turnover_notional),
+        ];
+        for (field, limit) in limits {
+            if let Some(limit) = limit {
+                ensure_positive(field, limit)?;
+            }
+        }
+        for (field, bound) in [
+            ("risk.min_position", self.min_position),
+            ("risk.max_position", self.max_position),
+        ] {
+            if bound.is_some_and(|bound| !bound.is_finite()) {
+                bail!("{}: must be a finite number", field);
+            }
+        }
+        if let (Some(min), Some(max)) = (self.min_position, self.max_position) {
+            if min > max {
+                bail!(
+                    "risk.min_position: must not exceed max_position {}, got {}",
+                    max,
+                    min
+                );
+            }
+        }
+        if self.turnover_window_secs == 0 {
+            bail!("risk.turnover_window_secs: must be positive");
+        }
+        if self.order_window_secs == 0 {
+            bail!("risk.order_window_secs: must be positive");
+        }
+        if self.max_orders_per_window == Some(0) {
+            bail!("risk.max_orders_per_window: must be positive");
+        }
+        if self.max_price_deviation_bps == Some(0) {
+            bail!("risk.max_price_deviation_bps: must be positive");
+        }
+        Ok(())
+    }
+}
+
+fn ensure_positive(field: &str, value: f64) -> Result<()> {
+    if !value.is_finite() || value <= 0.0 {
+        bail!("{}: must be a positive number, got {}", field, value);
+    }
+    Ok(())
+}
+
+fn ensure_non_negative(field: &str, value: f64) -> Result<()> {
+    if !value.is_finite() || value < 0.0 {
+        bail!("{}: must be a non-negative number, got {}", field, value);
+    }
+    Ok(())
+}
+
+fn validate_tx(field: &str, tx: &LiquidityTxConfig) -> Result<()> {
+    if tx.slippage_bps > 10_000 {
+        bail!(
+            "{}.slippage_bps: must be at most 10000, got {}",
+            field,
+            tx.slippage_bps
+        );
+    }
+    if tx.deadline_secs == 0 {
+        bail!("{}.deadline_secs: must be positive", field);
+    }
+    Ok(())
 }
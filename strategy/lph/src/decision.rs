@@ -0,0 +1,43 @@
+//! Hedge decision shared by `LPHStrategy::execute` and the backtester.
+
+/// Floor of the reference amount so an empty LP and futures position does not divide by zero
+const EPSILON: f64 = 1e-8;
+
+/// Relative deviation of the net BASE exposure: `base_delta` over the larger of the AMM and
+/// futures amounts
+pub(crate) fn base_delta_ratio(amm_base_amount: f64, futures_position: f64) -> f64 {
+    let base_delta = amm_base_amount + futures_position;
+    let base_reference = amm_base_amount
+        .abs()
+        .max(futures_position.abs())
+        .max(EPSILON);
+    base_delta / base_reference
+}
+
+/// Order quantity for a delta: `None` unless base_delta_ratio > n and |base_delta| > m,
+/// otherwise |base_delta| rounded to step m
+///
+/// # Arguments
+/// * `base_delta_ratio` - Ratio used in the trigger condition
+/// * `base_delta` - Net BASE exposure the order offsets
+/// * `ratio_threshold` - `base_delta_ratio_threshold` (n)
+/// * `delta_threshold` - `base_delta_threshold` (m), also the quantity step
+pub(crate) fn order_quantity(
+    base_delta_ratio: f64,
+    base_delta: f64,
+    ratio_threshold: f64,
+    delta_threshold: f64,
+) -> Option<f64> {
+    if base_delta_ratio <= ratio_threshold || base_delta.abs() <= delta_threshold {
+        return None;
+    }
+    Some(round_to_step(base_delta.abs(), delta_threshold))
+}
+
+/// Rounds a value to the nearest multiple of step (precision m per spec).
+fn round_to_step(value: f64, step: f64) -> f64 {
+    if step <= 0.0 {
+        return value;
+    }
+    (value / step).round() * step
+}
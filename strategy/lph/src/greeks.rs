@@ -0,0 +1,147 @@
+//! Analytic delta and gamma of a concentrated liquidity position.
+//!
+//! Prices are expressed as USDT per BASE (human units) and amounts in BASE
+//! (human units), so the results can be compared directly with the futures
+//! position and mark price.
+
+use alloy::primitives::{Address, U256};
+use clients_uniswapv3::{math, PositionData};
+
+/// Delta/gamma model of a single Uniswap V3 position
+#[derive(Debug, Clone)]
+pub struct LpGreeks {
+    /// Position liquidity
+    liquidity: f64,
+    /// Raw sqrt price at the lower tick
+    sqrt_lower: f64,
+    /// Raw sqrt price at the upper tick
+    sqrt_upper: f64,
+    /// Whether BASE is token0 of the pool
+    base_is_token0: bool,
+    /// `10^(base_decimals - usdt_decimals)`, converts raw pool prices into USDT per BASE
+    price_scale: f64,
+    /// `10^base_decimals`
+    base_unit: f64,
+    /// `10^usdt_decimals`
+    usdt_unit: f64,
+}
+
+impl LpGreeks {
+    /// Builds the model from synced position data
+    ///
+    /// # Arguments
+    /// * `position` - Position data read by `UniswapV3PositionManager::sync_lp`
+    /// * `base_token_address` - Address of the BASE token; the other token is treated as USDT
+    /// * `base_decimals` - Decimals of the BASE token
+    /// * `usdt_decimals` - Decimals of the USDT token
+    pub fn new(
+        position: &PositionData,
+        base_token_address: Address,
+        base_decimals: u32,
+        usdt_decimals: u32,
+    ) -> Self {
+        Self::from_range(
+            position.tick_lower,
+            position.tick_upper,
+            position.liquidity as f64,
+            position.token0 == base_token_address,
+            base_decimals,
+            usdt_decimals,
+        )
+    }
+
+    /// Builds the model of a position that is not on chain, e.g. in a backtest
+    ///
+    /// # Arguments
+    /// * `tick_lower` - Lower tick of the range
+    /// * `tick_upper` - Upper tick of the range
+    /// * `liquidity` - Position liquidity
+    /// * `base_is_token0` - Whether BASE is token0 of the pool
+    /// * `base_decimals` - Decimals of the BASE token
+    /// * `usdt_decimals` - Decimals of the USDT token
+    pub fn from_range(
+        tick_lower: i32,
+        tick_upper: i32,
+        liquidity: f64,
+        base_is_token0: bool,
+        base_decimals: u32,
+        usdt_decimals: u32,
+    ) -> Self {
+        Self {
+            liquidity,
+            sqrt_lower: math::sqrt_price_at_tick(tick_lower),
+            sqrt_upper: math::sqrt_price_at_tick(tick_upper),
+            base_is_token0,
+            price_scale: 10f64.powi(base_decimals as i32 - usdt_decimals as i32),
+            base_unit: 10f64.powi(base_decimals as i32),
+            usdt_unit: 10f64.powi(usdt_decimals as i32),
+        }
+    }
+
+    /// Converts a pool `sqrtPriceX96` into a BASE price in USDT
+    pub fn price_from_sqrt_price_x96(&self, sqrt_price_x96: U256) -> f64 {
+        let raw = math::sqrt_price_x96_to_f64(sqrt_price_x96).powi(2);
+        if self.base_is_token0 {
+            raw * self.price_scale
+        } else {
+            self.price_scale / raw
+        }
+    }
+
+    /// Converts a BASE price in USDT into a raw pool sqrt price (token1 per token0)
+    fn sqrt_raw_price(&self, price: f64) -> f64 {
+        if self.base_is_token0 {
+            (price / self.price_scale).sqrt()
+        } else {
+            (self.price_scale / price).sqrt()
+        }
+    }
+
+    /// Returns `(base_amount, usdt_amount)` held by the position at `price`
+    pub(crate) fn amounts(&self, price: f64) -> (f64, f64) {
+        let (amount0, amount1) = math::amounts_for_liquidity(
+            self.liquidity,
+            self.sqrt_raw_price(price),
+            self.sqrt_lower,
+            self.sqrt_upper,
+        );
+        if self.base_is_token0 {
+            (amount0 / self.base_unit, amount1 / self.usdt_unit)
+        } else {
+            (amount1 / self.base_unit, amount0 / self.usdt_unit)
+        }
+    }
+
+    /// Position delta at `price`: the BASE amount held, i.e. `dV/dP`
+    pub fn delta(&self, price: f64) -> f64 {
+        self.amounts(price).0
+    }
+
+    /// Position gamma at `price`: `d(delta)/dP` in BASE per USDT of price move
+    ///
+    /// Zero outside the range and negative inside it.
+    pub fn gamma(&self, price: f64) -> f64 {
+        let sqrt_p = self.sqrt_raw_price(price);
+        let (lower, upper) = if self.sqrt_lower <= self.sqrt_upper {
+            (self.sqrt_lower, self.sqrt_upper)
+        } else {
+            (self.sqrt_upper, self.sqrt_lower)
+        };
+        if sqrt_p <= lower || sqrt_p >= upper {
+            return 0.0;
+        }
+        if self.base_is_token0 {
+            // x = L (1/sqrt(p) - 1/sqrt(pb)), p = P / scale
+            -self.liquidity / (2.0 * sqrt_p.powi(3)) / self.price_scale / self.base_unit
+        } else {
+            // y = L (sqrt(p) - sqrt(pa)), p = scale / P
+            -self.liquidity * sqrt_p / (2.0 * price) / self.base_unit
+        }
+    }
+
+    /// Position value in USDT at `price`
+    pub fn value(&self, price: f64) -> f64 {
+        let (base, usdt) = self.amounts(price);
+        base * price + usdt
+    }
+}
@@ -3,10 +3,37 @@
 //! Provides monitoring for LP hedging setups that combine CEX futures
 //! with on-chain AMM positions.
 
+mod backtest;
+mod compound;
 pub mod config;
+mod decision;
+mod greeks;
 mod lph;
+mod paper;
+mod pnl;
+mod rebalance;
+mod risk;
+mod store;
 mod types;
 
-pub use config::LPHStrategyConfig;
+pub use backtest::{
+    run_backtest, summaries_csv, sweep, BacktestConfig, BacktestData, BacktestPoint,
+    BacktestPosition, BacktestResult, BacktestSummary, FundingSample, PriceSample,
+};
+pub use config::{
+    CompoundConfig, CompoundMode, LPHStrategyConfig, PaperTradingConfig, RebalanceConfig,
+    RiskLimits, DEFAULT_PRICE_SHOCKS,
+};
+pub use greeks::LpGreeks;
 pub use lph::LPHStrategy;
-pub use types::MonitoringSnapshot;
+pub use paper::{PaperAccount, PaperFill, PaperOrder};
+pub use pnl::{build_pnl_report, PnlEvent, PnlReport};
+pub use risk::{ProposedOrder, RiskManager, RiskRejection};
+pub use store::{
+    DecisionAction, DecisionRecord, FillRecord, OrderReconciliation, OrderRecord, OrderStatus,
+    SqliteStore, StateStore,
+};
+pub use types::{
+    CompoundAction, CompoundReport, MonitoringSnapshot, PaperAccountSnapshot, PositionSnapshot,
+    PriceShock, RebalanceReport, StrategyCounts,
+};
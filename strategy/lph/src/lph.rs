@@ -3,23 +3,46 @@
 //! This module provides monitoring functionality for LP hedging setups that combine
 //! centralized exchange (CEX) futures accounts with on-chain AMM positions.
 
-use alloy::primitives::Address;
-use anyhow::{anyhow, Result};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
 
-use clients_binance::BinancePerpsClient;
-use clients_uniswapv3::UniswapV3PositionManager;
+use alloy::primitives::{Address, U256};
+use anyhow::{anyhow, Context, Result};
 
-use crate::config::LPHStrategyConfig;
-use crate::types::MonitoringSnapshot;
+use clients_binance::{BinanceApiError, BinancePerpsClient};
+use clients_uniswapv3::math::{centered_range, sqrt_price_at_tick, sqrt_price_x96_to_f64};
+use clients_uniswapv3::{
+    EventPoll, LiquidityReceipt, LiquidityTxConfig, MintRequest, PositionData,
+    PositionEventTracker, PositionSource, SwapRequest, SyncedBlock, UniswapV3PositionManager,
+};
+
+use crate::compound::{estimated_gas_usdt, should_compound};
+use crate::config::{CompoundConfig, CompoundMode, LPHStrategyConfig, RebalanceConfig};
+use crate::decision;
+use crate::greeks::LpGreeks;
+use crate::paper::{now_ms, PaperAccount, PaperOrder};
+use crate::rebalance::{needs_recentering, target_swap};
+use crate::risk::{ProposedOrder, RiskManager};
+use crate::store::{
+    DecisionAction, DecisionRecord, FillRecord, OrderReconciliation, OrderRecord, OrderStatus,
+    StateStore,
+};
+use crate::types::{
+    CompoundAction, CompoundReport, MonitoringSnapshot, PositionSnapshot, PriceShock,
+    RebalanceReport, StrategyCounts,
+};
 
 /// LP Hedging Monitor
 ///
 /// Monitors the overall account state for an LP hedging setup that combines:
 /// - A centralized exchange (CEX) futures account
 /// - An on-chain AMM position
-pub struct LPHStrategy {
-    /// Uniswap V3 client instance
-    uniswap_client: UniswapV3PositionManager,
+///
+/// Hedging works with any `PositionSource` (e.g. Uniswap V4 positions); rebalancing,
+/// compounding and event tracking require the Uniswap V3 client.
+pub struct LPHStrategy<P = UniswapV3PositionManager> {
+    /// Client reading the LP positions (Uniswap V3 by default)
+    uniswap_client: P,
     /// Binance futures client instance
     binance_client: BinancePerpsClient,
     /// Ethereum address that owns the Uniswap V3 LP positions
@@ -34,24 +57,90 @@ pub struct LPHStrategy {
     base_delta_ratio_threshold: f64,
     /// Threshold for |base_delta| (m) and quantity step
     base_delta_threshold: f64,
+    /// Relative price shocks evaluated for the exposure ladder
+    price_shocks: Vec<f64>,
+    /// LP position IDs to hedge; `None` hedges every position on the BASE/USDT pair. IDs
+    /// burned by `rebalance` are removed, so the set can become empty.
+    lp_position_ids: Option<Vec<U256>>,
+    /// Range re-centering settings; `None` disables `rebalance`
+    rebalance: Option<RebalanceConfig>,
+    /// Fee compounding settings; `None` disables `compound`
+    compound: Option<CompoundConfig>,
+    /// Log-based position tracker; `None` disables `poll_events`
+    tracker: Option<PositionEventTracker>,
+    /// Binance futures symbol pricing the farm reward token
+    reward_symbol: Option<String>,
+    /// Pre-trade risk checks every hedge order passes through
+    risk: RiskManager,
+    /// Simulated futures account replacing the Binance account; `None` trades live
+    paper: Option<PaperAccount>,
+    /// Store receiving snapshots, decisions, orders, fills and income; `None` keeps nothing
+    store: Option<StoreHandle>,
+    /// Decisions, orders and sync errors since the strategy was created
+    counts: StrategyCounts,
 }
 
-impl LPHStrategy {
+/// State store together with the name the strategy's records are kept under
+struct StoreHandle {
+    /// Name of the strategy in the store
+    strategy: String,
+    /// Shared store
+    store: Arc<dyn StateStore>,
+}
+
+/// How far back income is fetched when the store holds none for the symbol yet
+const INCOME_LOOKBACK_MS: i64 = 7 * 24 * 60 * 60 * 1000;
+
+/// Sequence making client order IDs created in the same millisecond unique
+static CLIENT_ORDER_SEQUENCE: AtomicU64 = AtomicU64::new(0);
+
+/// Orderbook levels per side paper orders are matched against
+const PAPER_ORDERBOOK_DEPTH: u16 = 20;
+
+/// Futures account state read before a snapshot or an order
+struct FuturesState {
+    /// Signed position in BASE units (negative = short)
+    position: f64,
+    /// Unrealized PnL of the position, in USDT
+    unrealized_pnl: f64,
+    /// Mark price of the symbol
+    mark_price: f64,
+    /// Time of the data, in milliseconds since Unix epoch
+    timestamp: i64,
+}
+
+/// Token amounts after a swap towards a range ratio
+struct RatioSwap {
+    /// Raw token0 amount after the swap
+    amount0: U256,
+    /// Raw token1 amount after the swap
+    amount1: U256,
+    /// Gas cost of the swap transaction, in wei
+    gas_cost: U256,
+}
+
+impl<P: PositionSource> LPHStrategy<P> {
     /// Creates a new `LPHStrategy` instance
     ///
     /// # Arguments
     /// * `config` - A `LPHStrategyConfig` instance containing configuration parameters
-    /// * `uniswap_client` - Uniswap V3 client instance
+    /// * `uniswap_client` - Position client instance (`UniswapV3PositionManager` or another `PositionSource`)
     /// * `binance_client` - Binance futures client instance
     ///
     /// # Returns
-    /// A new `LPHStrategy` instance with both clients and configuration parameters configured
+    /// A new `LPHStrategy` instance with both clients and configuration parameters configured,
+    /// or an error if the paper trading account cannot be resumed from its state file
     pub fn new(
         config: LPHStrategyConfig,
-        uniswap_client: UniswapV3PositionManager,
+        uniswap_client: P,
         binance_client: BinancePerpsClient,
-    ) -> Self {
-        Self {
+    ) -> Result<Self> {
+        let paper = config
+            .paper_trading
+            .as_ref()
+            .map(PaperAccount::open)
+            .transpose()?;
+        Ok(Self {
             uniswap_client,
             binance_client,
             owner: config.owner,
@@ -60,173 +149,608 @@ impl LPHStrategy {
             usdt_token_address: config.usdt_token_address,
             base_delta_ratio_threshold: config.base_delta_ratio_threshold,
             base_delta_threshold: config.base_delta_threshold,
-        }
+            price_shocks: config.price_shocks,
+            lp_position_ids: (!config.lp_position_ids.is_empty()).then_some(config.lp_position_ids),
+            rebalance: config.rebalance,
+            compound: config.compound,
+            tracker: config
+                .event_tracking
+                .map(|tracking| PositionEventTracker::new(config.owner, tracking)),
+            reward_symbol: config.reward_symbol,
+            risk: RiskManager::new(config.risk),
+            paper,
+            store: None,
+            counts: StrategyCounts::default(),
+        })
+    }
+
+    /// Records snapshots, decisions, orders, fills and income in a state store
+    ///
+    /// # Arguments
+    /// * `strategy` - Name the records are kept under, unique among the store's strategies
+    /// * `store` - Store shared with other strategies
+    pub fn with_store(mut self, strategy: impl Into<String>, store: Arc<dyn StateStore>) -> Self {
+        self.store = Some(StoreHandle {
+            strategy: strategy.into(),
+            store,
+        });
+        self
+    }
+
+    /// Replaces the risk manager built from `config.risk`
+    ///
+    /// Pass a clone of a manager that outlives the strategy to keep the turnover and
+    /// order-count windows when the strategy is rebuilt, e.g. after a restart.
+    ///
+    /// # Arguments
+    /// * `risk` - Risk manager whose order history the strategy shares
+    pub fn with_risk_manager(mut self, risk: RiskManager) -> Self {
+        self.risk = risk;
+        self
     }
 
     /// Executes the LPH strategy: when base_delta_ratio > n and |base_delta| > m,
     /// computes quantity from base_delta (absolute value rounded to step m) and
-    /// calls open_sell (if base_delta > 0) or close_sell (if base_delta < 0).
+    /// places the open_sell (if base_delta > 0) or close_sell (if base_delta < 0) order
+    /// after it passed the risk checks.
+    ///
+    /// The order is checked against the current futures position and mark price, read from
+    /// Binance right before the order is built.
     ///
     /// # Arguments
     /// * `base_delta_ratio` - Ratio used in the trigger condition
     /// * `base_delta` - Delta used in the trigger and as the source value for order quantity
     ///
     /// # Returns
-    /// Ok(()) when no order is placed or when the order is placed successfully; Err on client failure.
+    /// Ok(()) when no order is placed or when the order is placed successfully; Err on client
+    /// failure or a `RiskRejection` (no order placed).
     pub async fn execute(&mut self, base_delta_ratio: f64, base_delta: f64) -> Result<()> {
-        let n = self.base_delta_ratio_threshold;
-        let m = self.base_delta_threshold;
-
-        if base_delta_ratio <= n || base_delta.abs() <= m {
-            return Ok(());
-        }
-
-        let value = base_delta;
-        if value == 0.0 {
+        let quantity = self.order_quantity(base_delta_ratio, base_delta);
+        let mut decision = DecisionRecord {
+            timestamp_ms: now_ms(),
+            symbol: self.symbol.clone(),
+            base_delta_ratio,
+            base_delta,
+            quantity,
+            action: DecisionAction::Hold,
+            reason: None,
+        };
+        let Some(quantity) = quantity else {
+            self.record_decision(&decision);
             return Ok(());
+        };
+        if let Err(rejection) = self.risk.check_kill_switch() {
+            decision.action = DecisionAction::KillSwitch;
+            decision.reason = Some(rejection.to_string());
+            self.record_decision(&decision);
+            return Err(rejection.into());
         }
+        let futures = self.futures_state().await?;
+        let quantity_str = format_quantity(quantity, self.base_delta_threshold);
 
-        let quantity = round_to_step(value.abs(), m);
-        let quantity_str = format_quantity(quantity, m);
-
-        if value > 0.0 {
+        let mut request = if base_delta > 0.0 {
             self.binance_client
-                .open_sell(&self.symbol, &quantity_str)
-                .await?;
+                .open_sell_request(&self.symbol, &quantity_str)
+                .await?
         } else {
             self.binance_client
-                .close_sell(&self.symbol, &quantity_str)
-                .await?;
+                .close_sell_request(&self.symbol, &quantity_str)
+                .await?
+        };
+        let price = parse_field("order price", request.price.as_deref().unwrap_or_default())?;
+        let order = ProposedOrder {
+            // Selling for a positive delta makes the futures position more negative
+            quantity: -quantity.copysign(base_delta),
+            price,
+            mark_price: futures.mark_price,
+            position: futures.position,
+        };
+        if let Err(rejection) = self.risk.check(&order) {
+            decision.action = DecisionAction::Rejected;
+            decision.reason = Some(rejection.to_string());
+            self.record_decision(&decision);
+            return Err(rejection.into());
         }
+        self.risk.record(&order);
+        decision.action = if base_delta > 0.0 {
+            DecisionAction::OpenSell
+        } else {
+            DecisionAction::CloseSell
+        };
+        self.record_decision(&decision);
+
+        let mut journal = OrderRecord {
+            client_order_id: String::new(),
+            symbol: self.symbol.clone(),
+            side: request.side,
+            quantity,
+            price,
+            order_id: None,
+            status: OrderStatus::Pending,
+            executed_quantity: 0.0,
+            paper: false,
+            created_at_ms: now_ms(),
+        };
+        if let Some(paper) = &mut self.paper {
+            let order_id = paper.place(&request)?;
+            println!(
+                "execute: symbol={} quantity={} price={} paper_order_id={} paper order placed",
+                self.symbol, quantity_str, price, order_id
+            );
+            if let Some(placed) = paper.open_orders().iter().find(|o| o.order_id == order_id) {
+                journal.client_order_id = paper_client_order_id(placed);
+                journal.order_id = Some(order_id as i64);
+                journal.status = OrderStatus::Open;
+                journal.paper = true;
+                self.counts.count_order(OrderStatus::Open);
+                self.record("paper order", |store, strategy| {
+                    store.record_order(strategy, &journal)
+                });
+            }
+            return Ok(());
+        }
+
+        // Journal the order before sending it, so its outcome can be reconciled after a crash
+        journal.client_order_id = new_client_order_id();
+        request.client_order_id = Some(journal.client_order_id.clone());
+        if let Some(handle) = &self.store {
+            handle
+                .store
+                .record_order(&handle.strategy, &journal)
+                .context("failed to journal order, not placed")?;
+        }
+        let response = match self
+            .binance_client
+            .place_order(&self.symbol, &request)
+            .await
+        {
+            Ok(response) => response,
+            Err(e) => {
+                // A Binance error means the order was refused, unless Binance could not tell
+                // (-1007); otherwise it stays pending for reconciliation
+                let refused = e
+                    .downcast_ref::<BinanceApiError>()
+                    .is_some_and(|api_error| !api_error.is_execution_unknown());
+                if refused {
+                    self.counts.count_order(OrderStatus::Rejected);
+                    self.record("order status", |store, strategy| {
+                        store.update_order(
+                            strategy,
+                            &journal.client_order_id,
+                            OrderStatus::Rejected,
+                            None,
+                        )
+                    });
+                } else {
+                    self.counts.count_order(OrderStatus::Pending);
+                }
+                return Err(e);
+            }
+        };
+        let status = OrderStatus::from_binance(&response.status);
+        self.counts.count_order(status);
+        self.record("order status", |store, strategy| {
+            store.update_order(
+                strategy,
+                &journal.client_order_id,
+                status,
+                Some(response.order_id),
+            )
+        });
+        println!(
+            "execute: symbol={} quantity={} price={} order_id={} client_order_id={} order placed",
+            self.symbol, quantity_str, price, response.order_id, journal.client_order_id
+        );
         Ok(())
     }
 
-    /// Performs a complete monitoring cycle by reading data from both clients and computing monitoring metrics
+    /// Reconciles the journaled orders still pending or resting with the exchange
+    ///
+    /// Live orders are looked up among Binance's open orders, then by client order ID; fills
+    /// found on the exchange and not journaled yet are recorded. An order Binance does not
+    /// know was never accepted and is marked rejected. In paper trading mode, journaled
+    /// orders no longer resting on the simulated account are marked cancelled.
     ///
     /// # Returns
-    /// A `MonitoringSnapshot` structure containing all monitoring metrics, or an error if data reading or computation fails
-    pub async fn status(&mut self) -> Result<MonitoringSnapshot> {
-        // Step 1: Read AMM LP Position Data
-        self.uniswap_client.sync_lp(self.owner).await?;
+    /// Counts of the reconciled orders; nothing is done without a store
+    pub async fn reconcile_orders(&mut self) -> Result<OrderReconciliation> {
+        let mut report = OrderReconciliation::default();
+        let Some(handle) = &self.store else {
+            return Ok(report);
+        };
+        let (strategy, store) = (handle.strategy.clone(), Arc::clone(&handle.store));
+        let paper_mode = self.paper.is_some();
+        let orders: Vec<OrderRecord> = store
+            .active_orders(&strategy)?
+            .into_iter()
+            .filter(|order| order.symbol == self.symbol && order.paper == paper_mode)
+            .collect();
+        if orders.is_empty() {
+            return Ok(report);
+        }
 
-        // Find the position matching base_token_address and usdt_token_address
-        let position_data = self
-            .uniswap_client
-            .positions()
-            .values()
-            .find(|pos| {
-                (pos.token0 == self.base_token_address && pos.token1 == self.usdt_token_address)
-                    || (pos.token0 == self.usdt_token_address
-                        && pos.token1 == self.base_token_address)
-            })
-            .ok_or_else(|| {
-                anyhow!(
-                    "No matching Uniswap position found for base_token={:?} and usdt_token={:?}",
-                    self.base_token_address,
-                    self.usdt_token_address
-                )
-            })?;
+        if let Some(paper) = &self.paper {
+            for order in orders {
+                report.checked += 1;
+                let resting = paper
+                    .open_orders()
+                    .iter()
+                    .any(|o| paper_client_order_id(o) == order.client_order_id);
+                if resting {
+                    report.open += 1;
+                } else {
+                    store.update_order(
+                        &strategy,
+                        &order.client_order_id,
+                        OrderStatus::Cancelled,
+                        None,
+                    )?;
+                    report.missing += 1;
+                }
+            }
+            return Ok(report);
+        }
 
-        // Determine which token is BASE and which is USDT
-        let (amm_base_amount_raw, amm_usdt_amount_raw) =
-            if position_data.token0 == self.base_token_address {
-                (
-                    position_data.withdrawable_amount0,
-                    position_data.withdrawable_amount1,
-                )
-            } else {
-                (
-                    position_data.withdrawable_amount1,
-                    position_data.withdrawable_amount0,
-                )
+        let open_orders = self.binance_client.get_open_orders(&self.symbol).await?;
+        for order in orders {
+            report.checked += 1;
+            let info = match open_orders
+                .iter()
+                .find(|o| o.client_order_id == order.client_order_id)
+            {
+                Some(info) => Some(info.clone()),
+                None => {
+                    self.binance_client
+                        .get_order(&self.symbol, &order.client_order_id)
+                        .await?
+                }
             };
-
-        let (amm_collectable_base_raw, amm_collectable_usdt_raw) =
-            if position_data.token0 == self.base_token_address {
-                (
-                    position_data.collectable_amount0,
-                    position_data.collectable_amount1,
-                )
-            } else {
-                (
-                    position_data.collectable_amount1,
-                    position_data.collectable_amount0,
-                )
+            let Some(info) = info else {
+                store.update_order(
+                    &strategy,
+                    &order.client_order_id,
+                    OrderStatus::Rejected,
+                    None,
+                )?;
+                report.missing += 1;
+                continue;
             };
+            let executed = parse_field("executed_qty", &info.executed_qty)?;
+            let unrecorded = executed - order.executed_quantity;
+            if unrecorded > 1e-12 {
+                store.record_fill(
+                    &strategy,
+                    &FillRecord {
+                        timestamp_ms: info.update_time,
+                        client_order_id: order.client_order_id.clone(),
+                        symbol: self.symbol.clone(),
+                        side: info.side,
+                        quantity: unrecorded,
+                        price: parse_field("avg_price", &info.avg_price)?,
+                        fee: None,
+                        paper: false,
+                    },
+                )?;
+            }
+            let status = OrderStatus::from_binance(&info.status);
+            store.update_order(
+                &strategy,
+                &order.client_order_id,
+                status,
+                Some(info.order_id),
+            )?;
+            if status.is_active() {
+                report.open += 1;
+            } else {
+                report.closed += 1;
+            }
+        }
+        Ok(report)
+    }
 
-        // Convert U256 amounts to f64 using 18 decimals for both tokens (spec 0101: Uniswap tokens use 18 decimals)
-        const UNISWAP_TOKEN_DECIMALS: u32 = 18;
-        let amm_base_amount = utils::u256_to_f64(amm_base_amount_raw, UNISWAP_TOKEN_DECIMALS);
-        let amm_usdt_amount = utils::u256_to_f64(amm_usdt_amount_raw, UNISWAP_TOKEN_DECIMALS);
-        let amm_collectable_base =
-            utils::u256_to_f64(amm_collectable_base_raw, UNISWAP_TOKEN_DECIMALS);
-        let amm_collectable_usdt =
-            utils::u256_to_f64(amm_collectable_usdt_raw, UNISWAP_TOKEN_DECIMALS);
+    /// Stores the Binance income (realized PnL, funding, commissions) of the symbol received
+    /// since the latest stored entry, or over the last 7 days for a new store
+    ///
+    /// # Returns
+    /// Number of new income entries; nothing is done without a store or in paper trading mode
+    pub async fn sync_income(&self) -> Result<usize> {
+        let Some(handle) = &self.store else {
+            return Ok(0);
+        };
+        if self.paper.is_some() {
+            return Ok(0);
+        }
+        let now = now_ms();
+        // Entries at the latest stored time are fetched again and ignored by the store
+        let start = handle
+            .store
+            .last_income_time(&handle.strategy, &self.symbol)?
+            .unwrap_or(now - INCOME_LOOKBACK_MS);
+        let incomes = self
+            .binance_client
+            .get_income_history(&self.symbol, start, now)
+            .await?;
+        handle.store.record_income(&handle.strategy, &incomes)
+    }
 
-        // Get current block number
-        let block_number = self.uniswap_client.get_block_number().await?;
+    /// Counts a decision and records it in the store, if any
+    fn record_decision(&mut self, decision: &DecisionRecord) {
+        self.counts.count_decision(decision.action);
+        self.record("decision", |store, strategy| {
+            store.record_decision(strategy, decision)
+        });
+    }
 
-        // Step 2: Read Binance Futures Position Data
-        let positions = self.binance_client.get_position(&self.symbol).await?;
+    /// Decisions, orders and sync errors counted since the strategy was created
+    pub fn counts(&self) -> &StrategyCounts {
+        &self.counts
+    }
 
-        let binance_position = positions
+    /// Writes to the store, if any; a failed write is logged and does not fail the caller
+    fn record(&self, what: &str, write: impl FnOnce(&dyn StateStore, &str) -> Result<()>) {
+        if let Some(handle) = &self.store {
+            if let Err(e) = write(handle.store.as_ref(), &handle.strategy) {
+                println!(
+                    "store: symbol={} failed to record {}: {:#}",
+                    self.symbol, what, e
+                );
+            }
+        }
+    }
+
+    /// Whether the kill switch is engaged; no order is placed while it is
+    pub fn kill_switch_engaged(&self) -> bool {
+        self.risk.kill_switch_engaged()
+    }
+
+    /// Whether orders fill on a simulated account instead of Binance
+    pub fn is_paper_trading(&self) -> bool {
+        self.paper.is_some()
+    }
+
+    /// Cancels the open orders of the symbol, on the simulated account in paper trading mode,
+    /// then reconciles the order journal if a store is set
+    pub async fn cancel_open_orders(&mut self) -> Result<()> {
+        match &mut self.paper {
+            Some(paper) => {
+                let cancelled = paper.cancel_all()?;
+                println!(
+                    "cancel_open_orders: symbol={} cancelled {} paper orders",
+                    self.symbol, cancelled
+                );
+            }
+            None => {
+                self.binance_client
+                    .cancel_all_open_orders(&self.symbol)
+                    .await?
+            }
+        }
+        self.reconcile_journal("cancel_open_orders").await;
+        Ok(())
+    }
+
+    /// Reconciles the order journal if a store is set, logging a failure instead of
+    /// returning it
+    ///
+    /// # Arguments
+    /// * `caller` - Name of the calling step, used as the log prefix
+    async fn reconcile_journal(&mut self, caller: &str) {
+        if self.store.is_none() {
+            return;
+        }
+        match self.reconcile_orders().await {
+            Ok(report) if report.closed + report.missing > 0 => println!(
+                "{}: symbol={} journal reconciled: {}",
+                caller, self.symbol, report
+            ),
+            Ok(_) => {}
+            Err(e) => println!(
+                "{}: symbol={} failed to reconcile journal: {:#}",
+                caller, self.symbol, e
+            ),
+        }
+    }
+
+    /// Order quantity for a delta: `None` unless base_delta_ratio > n and |base_delta| > m,
+    /// otherwise |base_delta| rounded to step m
+    fn order_quantity(&self, base_delta_ratio: f64, base_delta: f64) -> Option<f64> {
+        decision::order_quantity(
+            base_delta_ratio,
+            base_delta,
+            self.base_delta_ratio_threshold,
+            self.base_delta_threshold,
+        )
+    }
+
+    /// Adjusts the Binance hedge to a snapshot with `execute`, after checking that the
+    /// block the snapshot was read at is still canonical
+    ///
+    /// # Arguments
+    /// * `snapshot` - Snapshot returned by `status`
+    ///
+    /// # Returns
+    /// An error without placing an order if the snapshot's block was reorged out
+    /// (`BlockReorged`) or the risk module rejected the order (`RiskRejection`)
+    pub async fn hedge(&mut self, snapshot: &MonitoringSnapshot) -> Result<()> {
+        self.uniswap_client
+            .ensure_canonical(&SyncedBlock {
+                number: snapshot.block_number,
+                hash: snapshot.block_hash,
+            })
+            .await?;
+        self.execute(snapshot.base_delta_ratio, snapshot.base_delta)
+            .await
+    }
+
+    /// Performs a complete monitoring cycle by reading data from both clients and computing monitoring metrics
+    ///
+    /// # Returns
+    /// A `MonitoringSnapshot` structure containing all monitoring metrics, or an error if data reading or computation fails
+    pub async fn status(&mut self) -> Result<MonitoringSnapshot> {
+        // Step 1: Read AMM LP Position Data, pinned to one confirmed block; with event
+        // tracking only the logs since the last poll are applied to the cache
+        let synced = match self.tracker.as_mut() {
+            Some(tracker) => {
+                self.uniswap_client
+                    .sync_tracked(self.owner, tracker)
+                    .await?
+            }
+            None => self.uniswap_client.sync_lp(self.owner).await?,
+        };
+
+        // Every hedged ID was burned by a rebalance without replacement; matching nothing would
+        // report zero AMM exposure and unwind the short
+        if self.lp_position_ids.as_ref().is_some_and(Vec::is_empty) {
+            return Err(anyhow!(
+                "Every configured Uniswap position was closed without a replacement for owner={:?}; update lp_position_ids",
+                self.owner
+            ));
+        }
+
+        // Collect the positions on the BASE/USDT pair, restricted to the configured IDs if any
+        let matched: Vec<&PositionData> = self
+            .uniswap_client
+            .positions_of(self.owner)
+            .filter(|pos| self.is_hedged_position(pos))
+            .collect();
+
+        if let Some(missing) = self
+            .lp_position_ids
             .iter()
-            .find(|p| p.symbol == self.symbol)
-            .ok_or_else(|| {
-                anyhow!(
-                    "No matching Binance position found for symbol={}",
-                    self.symbol
-                )
-            })?;
+            .flatten()
+            .find(|&&token_id| !matched.iter().any(|pos| pos.token_id == token_id))
+        {
+            return Err(anyhow!(
+                "Configured Uniswap position {} not found for owner={:?}, base_token={:?} and usdt_token={:?}",
+                missing,
+                self.owner,
+                self.base_token_address,
+                self.usdt_token_address
+            ));
+        }
 
-        // Extract futures position (convert from string to f64, preserving sign)
-        let futures_position = binance_position
-            .position_amt
-            .parse::<f64>()
-            .map_err(|e| anyhow!("Failed to parse position_amt: {}", e))?;
+        // Without a position the snapshot would report zero AMM exposure and `hedge` would
+        // close the whole short; a sync gap must not look like a withdrawn LP
+        if matched.is_empty() {
+            return Err(anyhow!(
+                "No Uniswap position found for owner={:?}, base_token={:?} and usdt_token={:?}",
+                self.owner,
+                self.base_token_address,
+                self.usdt_token_address
+            ));
+        }
 
-        // Extract unrealized PnL from Binance position (in USDT)
-        let unrealized_pnl = binance_position
-            .unrealized_pnl
-            .parse::<f64>()
-            .map_err(|e| anyhow!("Failed to parse unrealized_pnl: {}", e))?;
+        // A failed or stale read reports zero or outdated amounts; hedging on it would
+        // unwind the short, so refuse to produce a snapshot.
+        if let Some(position_data) = matched.iter().find(|pos| !pos.sync_status.is_ok()) {
+            self.counts.sync_errors += 1;
+            return Err(anyhow!(
+                "Uniswap position {} did not sync: {}",
+                position_data.token_id,
+                position_data.sync_status
+            ));
+        }
+
+        // Convert U256 amounts to f64 using each token's ERC-20 decimals
+        let amm_positions: Vec<(PositionSnapshot, LpGreeks)> = matched
+            .iter()
+            .map(|pos| self.position_snapshot(pos))
+            .collect();
 
-        // Extract base price
-        let base_price_usdt = binance_position
-            .mark_price
-            .parse::<f64>()
-            .map_err(|e| anyhow!("Failed to parse mark_price: {}", e))?;
+        // Aggregate exposure and fees across all matched positions
+        let amm_base_amount: f64 = amm_positions.iter().map(|(pos, _)| pos.base_amount).sum();
+        let amm_usdt_amount: f64 = amm_positions.iter().map(|(pos, _)| pos.usdt_amount).sum();
+        let amm_collectable_base: f64 = amm_positions
+            .iter()
+            .map(|(pos, _)| pos.collectable_base)
+            .sum();
+        let amm_collectable_usdt: f64 = amm_positions
+            .iter()
+            .map(|(pos, _)| pos.collectable_usdt)
+            .sum();
+        let amm_base_gamma: f64 = amm_positions.iter().map(|(pos, _)| pos.gamma).sum();
+        let amm_pending_reward: f64 = amm_positions
+            .iter()
+            .map(|(pos, _)| pos.pending_reward)
+            .sum();
 
-        // Extract timestamp
-        let futures_timestamp = binance_position.update_time;
+        // Step 2: Read Binance Futures Position Data (the simulated account in paper trading mode)
+        let futures = self.futures_state().await?;
+        let futures_position = futures.position;
+        let unrealized_pnl = futures.unrealized_pnl;
+        let base_price_usdt = futures.mark_price;
+        let futures_timestamp = futures.timestamp;
 
         // Step 3: Compute Monitoring Metrics
         let base_delta = amm_base_amount + futures_position;
 
-        // Compute base_reference with epsilon to avoid division by zero
-        const EPSILON: f64 = 1e-8;
-        let base_reference = amm_base_amount
-            .abs()
-            .max(futures_position.abs())
-            .max(EPSILON);
-
-        let base_delta_ratio = base_delta / base_reference;
+        let base_delta_ratio = decision::base_delta_ratio(amm_base_amount, futures_position);
 
         let amm_base_value_usdt = amm_base_amount * base_price_usdt;
         let amm_total_value_usdt = amm_base_value_usdt + amm_usdt_amount;
+        // Value farm rewards (e.g. CAKE of MasterChef-staked positions) at their futures mark price
+        let amm_reward_value_usdt = match &self.reward_symbol {
+            Some(reward_symbol) if amm_pending_reward > 0.0 => {
+                let reward_price = self
+                    .binance_client
+                    .get_mark_price(reward_symbol)
+                    .await?
+                    .mark_price
+                    .parse::<f64>()
+                    .map_err(|e| anyhow!("Failed to parse {} mark_price: {}", reward_symbol, e))?;
+                amm_pending_reward * reward_price
+            }
+            _ => 0.0,
+        };
         let amm_collectable_value_usdt =
-            amm_collectable_base * base_price_usdt + amm_collectable_usdt;
+            amm_collectable_base * base_price_usdt + amm_collectable_usdt + amm_reward_value_usdt;
         let total_value_usdt = amm_total_value_usdt + unrealized_pnl;
 
+        // Reference the pool price of the first position
+        let amm_price_usdt = amm_positions
+            .first()
+            .map(|(pos, _)| pos.price_usdt)
+            .unwrap_or(base_price_usdt);
+
+        // Project exposure under each configured price shock, moving every position's pool price
+        let price_shocks = self
+            .price_shocks
+            .iter()
+            .map(|&shock| {
+                let (shocked_base_amount, amm_value_change) = amm_positions.iter().fold(
+                    (0.0, 0.0),
+                    |(base_amount, value_change), (pos, greeks)| {
+                        let price = pos.price_usdt * (1.0 + shock);
+                        (
+                            base_amount + greeks.delta(price),
+                            value_change + greeks.value(price) - greeks.value(pos.price_usdt),
+                        )
+                    },
+                );
+                let price = amm_price_usdt * (1.0 + shock);
+                PriceShock {
+                    shock,
+                    price,
+                    amm_base_amount: shocked_base_amount,
+                    base_delta: shocked_base_amount + futures_position,
+                    value_change_usdt: amm_value_change
+                        + futures_position * (price - amm_price_usdt),
+                }
+            })
+            .collect();
+
         // Step 4: Build and Return Monitoring Snapshot
-        Ok(MonitoringSnapshot {
-            block_number,
+        let snapshot = MonitoringSnapshot {
+            block_number: synced.number,
+            block_hash: synced.hash,
             symbol: self.symbol.clone(),
             amm_base_amount,
             amm_usdt_amount,
             amm_collectable_base,
             amm_collectable_usdt,
             amm_collectable_value_usdt,
+            amm_pending_reward,
+            amm_reward_value_usdt,
             futures_position,
             unrealized_pnl,
             futures_timestamp,
@@ -235,16 +759,634 @@ impl LPHStrategy {
             base_delta_ratio,
             amm_total_value_usdt,
             total_value_usdt,
-        })
+            amm_price_usdt,
+            amm_base_gamma,
+            price_shocks,
+            positions: amm_positions.into_iter().map(|(pos, _)| pos).collect(),
+            paper: self
+                .paper
+                .as_ref()
+                .map(|paper| paper.snapshot(base_price_usdt)),
+        };
+        self.record("snapshot", |store, strategy| {
+            store.record_snapshot(strategy, &snapshot)
+        });
+        Ok(snapshot)
+    }
+
+    /// Reads the futures position of the configured symbol
+    ///
+    /// Live, first reconciles the journaled orders so fills since the previous cycle are
+    /// recorded. In paper trading mode, first fills the resting paper orders the live
+    /// orderbook has reached, then values the simulated position at the live mark price.
+    async fn futures_state(&mut self) -> Result<FuturesState> {
+        if self.paper.is_none() {
+            self.reconcile_journal("futures_state").await;
+        }
+        let Some(paper) = &mut self.paper else {
+            let position = self
+                .binance_client
+                .get_position(&self.symbol)
+                .await?
+                .into_iter()
+                .find(|p| p.symbol == self.symbol)
+                .ok_or_else(|| {
+                    anyhow!(
+                        "No matching Binance position found for symbol={}",
+                        self.symbol
+                    )
+                })?;
+            return Ok(FuturesState {
+                position: parse_field("position_amt", &position.position_amt)?,
+                unrealized_pnl: parse_field("unrealized_pnl", &position.unrealized_pnl)?,
+                mark_price: parse_field("mark_price", &position.mark_price)?,
+                timestamp: position.update_time,
+            });
+        };
+        let mut journal = Vec::new();
+        if !paper.open_orders().is_empty() {
+            let orderbook = self
+                .binance_client
+                .get_orderbook(&self.symbol, Some(PAPER_ORDERBOOK_DEPTH))
+                .await?;
+            let resting = paper.open_orders().to_vec();
+            let fills = paper.match_orderbook(&orderbook)?;
+            for fill in fills {
+                println!(
+                    "paper fill: symbol={} paper_order_id={} side={} quantity={} price={} fee={:.6}",
+                    self.symbol,
+                    fill.order_id,
+                    fill.side.as_api_str(),
+                    fill.quantity,
+                    fill.price,
+                    fill.fee
+                );
+                let Some(order) = resting.iter().find(|o| o.order_id == fill.order_id) else {
+                    continue;
+                };
+                let status = if paper
+                    .open_orders()
+                    .iter()
+                    .any(|o| o.order_id == fill.order_id)
+                {
+                    OrderStatus::PartiallyFilled
+                } else {
+                    OrderStatus::Filled
+                };
+                let record = FillRecord {
+                    timestamp_ms: now_ms(),
+                    client_order_id: paper_client_order_id(order),
+                    symbol: self.symbol.clone(),
+                    side: fill.side,
+                    quantity: fill.quantity,
+                    price: fill.price,
+                    fee: Some(fill.fee),
+                    paper: true,
+                };
+                journal.push((record, status));
+            }
+        }
+        let mark = self.binance_client.get_mark_price(&self.symbol).await?;
+        let mark_price = parse_field("mark_price", &mark.mark_price)?;
+        paper.seed_entry_price(mark_price);
+        let state = FuturesState {
+            position: paper.position(),
+            unrealized_pnl: paper.unrealized_pnl(mark_price),
+            mark_price,
+            timestamp: mark.time,
+        };
+        for (fill, status) in journal {
+            self.record("paper fill", |store, strategy| {
+                store.record_fill(strategy, &fill)?;
+                store.update_order(strategy, &fill.client_order_id, status, None)
+            });
+        }
+        Ok(state)
+    }
+
+    /// Returns true if the position is on the BASE/USDT pair in either token order
+    fn is_hedged_pair(&self, pos: &PositionData) -> bool {
+        (pos.token0 == self.base_token_address && pos.token1 == self.usdt_token_address)
+            || (pos.token0 == self.usdt_token_address && pos.token1 == self.base_token_address)
+    }
+
+    /// Returns true if the position is on the BASE/USDT pair and among the hedged IDs, if any
+    fn is_hedged_position(&self, pos: &PositionData) -> bool {
+        self.is_hedged_pair(pos)
+            && self
+                .lp_position_ids
+                .as_ref()
+                .is_none_or(|ids| ids.contains(&pos.token_id))
+    }
+
+    /// Converts a synced position into BASE/USDT terms and builds its greeks model
+    fn position_snapshot(&self, pos: &PositionData) -> (PositionSnapshot, LpGreeks) {
+        // Determine which token is BASE and which is USDT
+        let base_is_token0 = pos.token0 == self.base_token_address;
+        let (base_metadata, usdt_metadata) = if base_is_token0 {
+            (&pos.token0_metadata, &pos.token1_metadata)
+        } else {
+            (&pos.token1_metadata, &pos.token0_metadata)
+        };
+        let base_decimals = base_metadata.decimals;
+        let usdt_decimals = usdt_metadata.decimals;
+        let (base_amount_raw, usdt_amount_raw, collectable_base_raw, collectable_usdt_raw) =
+            if base_is_token0 {
+                (
+                    pos.withdrawable_amount0,
+                    pos.withdrawable_amount1,
+                    pos.collectable_amount0,
+                    pos.collectable_amount1,
+                )
+            } else {
+                (
+                    pos.withdrawable_amount1,
+                    pos.withdrawable_amount0,
+                    pos.collectable_amount1,
+                    pos.collectable_amount0,
+                )
+            };
+
+        // Model the position's delta and gamma at its pool price
+        let greeks = LpGreeks::new(
+            pos,
+            self.base_token_address,
+            base_decimals.into(),
+            usdt_decimals.into(),
+        );
+        let price_usdt = greeks.price_from_sqrt_price_x96(pos.sqrt_price_x96);
+
+        let reward_decimals = self
+            .uniswap_client
+            .reward_token()
+            .and_then(|token| self.uniswap_client.token_metadata(token))
+            .map(|metadata| metadata.decimals)
+            .unwrap_or(18);
+
+        let snapshot = PositionSnapshot {
+            token_id: pos.token_id,
+            tick_lower: pos.tick_lower,
+            tick_upper: pos.tick_upper,
+            in_range: pos.tick_lower <= pos.tick && pos.tick < pos.tick_upper,
+            price_usdt,
+            base_amount: utils::u256_to_f64(base_amount_raw, base_decimals),
+            usdt_amount: utils::u256_to_f64(usdt_amount_raw, usdt_decimals),
+            collectable_base: utils::u256_to_f64(collectable_base_raw, base_decimals),
+            collectable_usdt: utils::u256_to_f64(collectable_usdt_raw, usdt_decimals),
+            staked: pos.staked_in.is_some(),
+            pending_reward: utils::u256_to_f64(pos.pending_reward, reward_decimals),
+            gamma: greeks.gamma(price_usdt),
+        };
+        (snapshot, greeks)
     }
 }
 
-/// Rounds a value to the nearest multiple of step (precision m per spec).
-fn round_to_step(value: f64, step: f64) -> f64 {
-    if step <= 0.0 {
-        return value;
+impl LPHStrategy {
+    /// Re-centres every hedged position whose range the pool price left by more than the
+    /// configured buffer, then adjusts the Binance hedge to the new AMM exposure
+    ///
+    /// Each such position is closed (liquidity removed, tokens collected, NFT burned),
+    /// the tokens are optionally swapped to the ratio of the new range, and a new
+    /// position centred on the current tick is minted with the withdrawn amounts.
+    /// Replaced IDs in `lp_position_ids` are updated to the minted IDs.
+    ///
+    /// A failed mint stops the loop and skips the re-hedge, since the withdrawn tokens stay in
+    /// the wallet where the snapshot does not count them. Once no configured ID is left,
+    /// `status` returns an error instead of a snapshot without AMM exposure.
+    ///
+    /// # Returns
+    /// One `RebalanceReport` per re-centred position; empty when rebalancing is disabled
+    /// or every position is within its range plus buffer; a `RiskRejection` while the kill
+    /// switch is engaged
+    pub async fn rebalance(&mut self) -> Result<Vec<RebalanceReport>> {
+        let Some(config) = self.rebalance.clone() else {
+            return Ok(Vec::new());
+        };
+        self.risk.check_kill_switch()?;
+        self.ensure_wallet_is_owner()?;
+        // Fail before any position is closed, not with the tokens withdrawn to the wallet
+        self.ensure_uniswap_abi("Re-centering")?;
+
+        let synced = self.uniswap_client.sync_lp(self.owner).await?;
+        let targets: Vec<PositionData> = self
+            .uniswap_client
+            .positions_of(self.owner)
+            .filter(|pos| {
+                self.is_hedged_position(pos)
+                    && pos.sync_status.is_ok()
+                    && pos.staked_in.is_none()
+                    && pos.liquidity > 0
+                    && needs_recentering(pos, config.out_of_range_buffer_ticks)
+            })
+            .cloned()
+            .collect();
+
+        // Never withdraw positions based on state that has since been orphaned
+        if !targets.is_empty() {
+            self.uniswap_client.ensure_canonical(&synced).await?;
+        }
+
+        let mut reports = Vec::with_capacity(targets.len());
+        let mut mint_failed = false;
+        for pos in &targets {
+            let closed = self
+                .uniswap_client
+                .close_position(pos.token_id, self.owner, &config.tx)
+                .await?;
+            // The NFT is burned: stop hedging it even if no replacement can be minted
+            if let Some(ids) = &mut self.lp_position_ids {
+                ids.retain(|id| *id != pos.token_id);
+            }
+            let report = self.recenter(pos, &closed, &config).await;
+            match (report.minted_token_id, &report.mint_error) {
+                (Some(minted_token_id), _) => {
+                    println!(
+                        "Re-centred position {} into {} with range [{}, {})",
+                        report.closed_token_id,
+                        minted_token_id,
+                        report.tick_lower,
+                        report.tick_upper
+                    );
+                    if let Some(ids) = &mut self.lp_position_ids {
+                        ids.push(minted_token_id);
+                    }
+                }
+                (None, error) => println!(
+                    "Closed position {} but no replacement was minted, tokens stay in the wallet: {}",
+                    report.closed_token_id,
+                    error.as_deref().unwrap_or("unknown error")
+                ),
+            }
+            mint_failed = report.minted_token_id.is_none();
+            reports.push(report);
+            // Withdraw nothing more until the wallet funds are dealt with
+            if mint_failed {
+                break;
+            }
+        }
+
+        // The snapshot does not count the tokens left in the wallet: re-hedging now would buy
+        // back the short of BASE that is still held, so leave the hedge as it is
+        if mint_failed {
+            println!("Skipping re-hedge after the failed mint, the withdrawn BASE is still hedged");
+        } else if !reports.is_empty() {
+            // The new ranges change the BASE amount; hedge it in the same cycle
+            let snapshot = self.status().await?;
+            self.hedge(&snapshot).await?;
+        }
+        Ok(reports)
+    }
+
+    /// Mints a replacement centred on the current pool tick with the tokens a closed position
+    /// returned, swapping them to the new range's ratio first if configured
+    ///
+    /// A failure is recorded in the report instead of returned, since the closed position is
+    /// gone either way.
+    async fn recenter(
+        &self,
+        pos: &PositionData,
+        closed: &LiquidityReceipt,
+        config: &RebalanceConfig,
+    ) -> RebalanceReport {
+        let mut report = RebalanceReport {
+            closed_token_id: pos.token_id,
+            minted_token_id: None,
+            tick_lower: pos.tick_lower,
+            tick_upper: pos.tick_upper,
+            swapped: false,
+            gas_cost: closed.tx.gas_cost(),
+            mint_error: None,
+        };
+        if let Err(e) = self
+            .mint_replacement(pos, closed, config, &mut report)
+            .await
+        {
+            report.mint_error = Some(format!("{:#}", e));
+        }
+        report
     }
-    (value / step).round() * step
+
+    /// Swap and mint steps of `recenter`, filling the report as they complete
+    async fn mint_replacement(
+        &self,
+        pos: &PositionData,
+        closed: &LiquidityReceipt,
+        config: &RebalanceConfig,
+        report: &mut RebalanceReport,
+    ) -> Result<()> {
+        let (mut amount0, mut amount1) = closed
+            .collected
+            .iter()
+            .fold((U256::ZERO, U256::ZERO), |(amount0, amount1), event| {
+                (amount0 + event.amount0, amount1 + event.amount1)
+            });
+
+        let tick_spacing = self.uniswap_client.tick_spacing(pos.pool).await?;
+        let (tick_lower, tick_upper) =
+            centered_range(pos.tick, tick_spacing, config.range_width_ticks);
+        report.tick_lower = tick_lower;
+        report.tick_upper = tick_upper;
+
+        if config.swap_to_target_ratio {
+            if let Some(swap) = self
+                .swap_to_range_ratio(pos, tick_lower, tick_upper, amount0, amount1, &config.tx)
+                .await?
+            {
+                (amount0, amount1) = (swap.amount0, swap.amount1);
+                report.gas_cost += swap.gas_cost;
+                report.swapped = true;
+            }
+        }
+
+        let minted = self
+            .uniswap_client
+            .mint(
+                &MintRequest {
+                    token0: pos.token0,
+                    token1: pos.token1,
+                    fee: pos.fee,
+                    tick_lower,
+                    tick_upper,
+                    amount0_desired: amount0,
+                    amount1_desired: amount1,
+                    recipient: self.owner,
+                },
+                &config.tx,
+            )
+            .await?;
+        report.gas_cost += minted.tx.gas_cost();
+        let minted_token_id = minted.minted.first().copied().ok_or_else(|| {
+            anyhow!(
+                "Mint transaction {} did not report a token ID",
+                minted.tx.tx_hash
+            )
+        })?;
+        report.minted_token_id = Some(minted_token_id);
+        Ok(())
+    }
+
+    /// Price of the native gas token in USDT: the configured price, the BASE mark price of the
+    /// snapshot when `native_symbol` is the hedged symbol, or the mark price of `native_symbol`
+    async fn native_price_usdt(
+        &self,
+        config: &CompoundConfig,
+        snapshot: &MonitoringSnapshot,
+    ) -> Result<f64> {
+        match (config.native_price_usdt, &config.native_symbol) {
+            (Some(price), _) => Ok(price),
+            (None, Some(symbol)) if *symbol == self.symbol => Ok(snapshot.base_price_usdt),
+            (None, Some(symbol)) => self
+                .binance_client
+                .get_mark_price(symbol)
+                .await?
+                .mark_price
+                .parse::<f64>()
+                .map_err(|e| anyhow!("Failed to parse {} mark_price: {}", symbol, e)),
+            (None, None) => Err(anyhow!(
+                "compound.native_symbol: required unless native_price_usdt is set"
+            )),
+        }
+    }
+
+    /// Collects the fees of every hedged position whose fees are worth more than the
+    /// configured multiple of the estimated gas cost, then reinvests or sweeps them
+    ///
+    /// In `Reinvest` mode the fees are swapped towards the ratio of the position's range
+    /// and added back with `increaseLiquidity`, and the Binance hedge is adjusted to the
+    /// larger position in the same cycle. In `Sweep` mode they are collected to the treasury.
+    ///
+    /// # Returns
+    /// One `CompoundReport` per compounded position; empty when compounding is disabled
+    /// or no position has accrued enough fees; a `RiskRejection` while the kill switch is
+    /// engaged
+    pub async fn compound(&mut self) -> Result<Vec<CompoundReport>> {
+        let Some(config) = self.compound.clone() else {
+            return Ok(Vec::new());
+        };
+        self.risk.check_kill_switch()?;
+        self.ensure_wallet_is_owner()?;
+        // Sweeping only collects; reinvesting swaps, so check before any fees are collected
+        if config.mode == CompoundMode::Reinvest {
+            self.ensure_uniswap_abi("Reinvesting fees")?;
+        }
+
+        let snapshot = self.status().await?;
+        let gas_price = self.uniswap_client.get_gas_price().await?;
+        let native_price_usdt = self.native_price_usdt(&config, &snapshot).await?;
+        let gas_usdt = estimated_gas_usdt(gas_price, config.gas_units, native_price_usdt);
+
+        let mut reports = Vec::new();
+        // Staked positions are held by the farm and cannot be changed by the owner
+        for position in snapshot.positions.iter().filter(|pos| !pos.staked) {
+            let fee_value_usdt =
+                position.collectable_base * snapshot.base_price_usdt + position.collectable_usdt;
+            if !should_compound(fee_value_usdt, gas_usdt, config.min_fee_to_gas_ratio) {
+                continue;
+            }
+            let pos = self
+                .uniswap_client
+                .positions()
+                .get(&position.token_id)
+                .cloned()
+                .ok_or_else(|| anyhow!("Uniswap position {} not cached", position.token_id))?;
+
+            let report = self
+                .compound_position(&pos, &config, fee_value_usdt, gas_usdt)
+                .await?;
+            println!(
+                "{:?} fees of position {}: {:.4} USDT (amount0={}, amount1={}), gas spent {} wei (estimated {:.4} USDT)",
+                report.action,
+                report.token_id,
+                report.fee_value_usdt,
+                report.amount0,
+                report.amount1,
+                report.gas_cost,
+                report.estimated_gas_usdt
+            );
+            reports.push(report);
+        }
+
+        // Reinvested fees add BASE to the positions; hedge it in the same cycle
+        if reports
+            .iter()
+            .any(|report| report.action == CompoundAction::Reinvested)
+        {
+            let snapshot = self.status().await?;
+            self.hedge(&snapshot).await?;
+        }
+        Ok(reports)
+    }
+
+    /// Collects one position's fees and reinvests or sweeps them
+    async fn compound_position(
+        &self,
+        pos: &PositionData,
+        config: &CompoundConfig,
+        fee_value_usdt: f64,
+        estimated_gas_usdt: f64,
+    ) -> Result<CompoundReport> {
+        let (action, recipient) = match config.mode {
+            CompoundMode::Reinvest => (CompoundAction::Reinvested, self.owner),
+            CompoundMode::Sweep => (
+                CompoundAction::Swept,
+                config
+                    .treasury
+                    .ok_or_else(|| anyhow!("compound.treasury: required with mode = \"sweep\""))?,
+            ),
+        };
+        let collected = self.uniswap_client.collect(pos.token_id, recipient).await?;
+        let mut gas_cost = collected.tx.gas_cost();
+        let (amount0, amount1) = collected
+            .collected
+            .iter()
+            .fold((U256::ZERO, U256::ZERO), |(amount0, amount1), event| {
+                (amount0 + event.amount0, amount1 + event.amount1)
+            });
+
+        if action == CompoundAction::Reinvested {
+            let (mut deposit0, mut deposit1) = (amount0, amount1);
+            if let Some(swap) = self
+                .swap_to_range_ratio(
+                    pos,
+                    pos.tick_lower,
+                    pos.tick_upper,
+                    amount0,
+                    amount1,
+                    &config.tx,
+                )
+                .await?
+            {
+                (deposit0, deposit1) = (swap.amount0, swap.amount1);
+                gas_cost += swap.gas_cost;
+            }
+            let increased = self
+                .uniswap_client
+                .increase_liquidity(pos.token_id, deposit0, deposit1, &config.tx)
+                .await?;
+            gas_cost += increased.tx.gas_cost();
+        }
+
+        Ok(CompoundReport {
+            token_id: pos.token_id,
+            action,
+            fee_value_usdt,
+            estimated_gas_usdt,
+            amount0,
+            amount1,
+            gas_cost,
+        })
+    }
+
+    /// Swaps `(amount0, amount1)` through the position's pool towards the token ratio of
+    /// the range `[tick_lower, tick_upper)` at the synced pool price
+    ///
+    /// # Returns
+    /// The amounts after the swap and its gas cost, or `None` if no swap was needed
+    async fn swap_to_range_ratio(
+        &self,
+        pos: &PositionData,
+        tick_lower: i32,
+        tick_upper: i32,
+        amount0: U256,
+        amount1: U256,
+        tx: &LiquidityTxConfig,
+    ) -> Result<Option<RatioSwap>> {
+        let Some((zero_for_one, amount_in)) = target_swap(
+            f64::from(amount0),
+            f64::from(amount1),
+            sqrt_price_x96_to_f64(pos.sqrt_price_x96),
+            sqrt_price_at_tick(tick_lower),
+            sqrt_price_at_tick(tick_upper),
+        ) else {
+            return Ok(None);
+        };
+
+        let (token_in, token_out, available) = if zero_for_one {
+            (pos.token0, pos.token1, amount0)
+        } else {
+            (pos.token1, pos.token0, amount1)
+        };
+        let amount_in = U256::from(amount_in as u128).min(available);
+        let receipt = self
+            .uniswap_client
+            .swap_exact_input_single(
+                &SwapRequest {
+                    token_in,
+                    token_out,
+                    fee: pos.fee,
+                    amount_in,
+                },
+                tx,
+            )
+            .await?;
+        let (amount0, amount1) = if zero_for_one {
+            (amount0 - receipt.amount_in, amount1 + receipt.amount_out)
+        } else {
+            (amount0 + receipt.amount_out, amount1 - receipt.amount_in)
+        };
+        Ok(Some(RatioSwap {
+            amount0,
+            amount1,
+            gas_cost: receipt.tx.gas_cost(),
+        }))
+    }
+
+    /// Returns an error unless the attached wallet owns the hedged positions
+    fn ensure_wallet_is_owner(&self) -> Result<()> {
+        let signer = self.uniswap_client.signer_address().ok_or_else(|| {
+            anyhow!("Sending LP transactions requires a wallet attached to the Uniswap client")
+        })?;
+        if signer != self.owner {
+            return Err(anyhow!(
+                "Wallet {:?} is not the position owner {:?}",
+                signer,
+                self.owner
+            ));
+        }
+        Ok(())
+    }
+
+    /// Returns an error unless the AMM takes the Uniswap V3 `mint` and SwapRouter ABI;
+    /// Algebra and Slipstream positions can be read and collected but not minted or swapped
+    fn ensure_uniswap_abi(&self, action: &str) -> Result<()> {
+        let kind = self.uniswap_client.amm().kind;
+        if !kind.uniswap_abi() {
+            return Err(anyhow!("{} is not supported for {:?} pools", action, kind));
+        }
+        Ok(())
+    }
+
+    /// Polls position and pool logs since the last call and updates the cached positions
+    ///
+    /// # Returns
+    /// The poll result; `EventPoll::should_reevaluate` tells whether the pool price moved
+    /// or the positions changed enough to run `status` before the next fixed interval.
+    /// Without event tracking configured an empty poll is returned.
+    pub async fn poll_events(&mut self) -> Result<EventPoll> {
+        match self.tracker.as_mut() {
+            Some(tracker) => tracker.poll(&mut self.uniswap_client).await,
+            None => Ok(EventPoll::default()),
+        }
+    }
+}
+
+/// Parses a numeric string field of a Binance response.
+fn parse_field(name: &str, value: &str) -> Result<f64> {
+    value
+        .parse::<f64>()
+        .map_err(|e| anyhow!("Failed to parse {}: {}", name, e))
+}
+
+/// Client order ID of a live order: `lph-<milliseconds>-<sequence>`, unique per process and
+/// across restarts
+fn new_client_order_id() -> String {
+    let sequence = CLIENT_ORDER_SEQUENCE.fetch_add(1, Ordering::Relaxed);
+    format!("lph-{}-{}", now_ms(), sequence)
+}
+
+/// Client order ID a paper order is journaled under
+fn paper_client_order_id(order: &PaperOrder) -> String {
+    format!("paper-{}-{}", order.placed_at_ms, order.order_id)
 }
 
 /// Formats a quantity with decimal places derived from step m.
@@ -0,0 +1,505 @@
+//! Simulated futures account for paper trading.
+//!
+//! Hedge orders are kept as resting limit orders and filled against the live Binance orderbook:
+//! a sell fills once the best bid reaches its price, a buy once the best ask does, up to the
+//! quantity quoted at or through the limit. Fills happen at the limit price and pay the maker fee.
+//! The account is optionally persisted to a JSON file so a paper run survives restarts.
+
+use anyhow::{anyhow, Context, Result};
+use clients_binance::{Orderbook, PlaceOrderRequest, Side};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use crate::config::PaperTradingConfig;
+use crate::types::PaperAccountSnapshot;
+
+/// Resting limit order of the simulated account
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PaperOrder {
+    /// Identifier of the order, increasing per account
+    pub order_id: u64,
+    /// Side of the order
+    pub side: Side,
+    /// Limit price
+    pub price: f64,
+    /// Quantity not filled yet, in BASE units
+    pub remaining: f64,
+    /// Time the order was placed, in milliseconds since Unix epoch
+    pub placed_at_ms: i64,
+}
+
+/// Fill of a paper order
+#[derive(Debug, Clone, Copy)]
+pub struct PaperFill {
+    /// Identifier of the filled order
+    pub order_id: u64,
+    /// Side of the order
+    pub side: Side,
+    /// Filled quantity, in BASE units
+    pub quantity: f64,
+    /// Fill price
+    pub price: f64,
+    /// Fee paid, in USDT
+    pub fee: f64,
+}
+
+/// State of the simulated account, as persisted
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct PaperState {
+    /// Signed position in BASE units (negative = short)
+    position: f64,
+    /// Average entry price of the position
+    entry_price: f64,
+    /// PnL realized by reducing the position, in USDT
+    realized_pnl: f64,
+    /// Fees paid, in USDT
+    fees_paid: f64,
+    /// Notional of every fill, in USDT
+    filled_notional: f64,
+    /// Number of fills
+    fills: u64,
+    /// Resting orders, oldest first
+    open_orders: Vec<PaperOrder>,
+    /// Identifier of the next order
+    next_order_id: u64,
+    /// Time of the last fill or order, in milliseconds since Unix epoch
+    updated_at_ms: i64,
+}
+
+/// Simulated futures account fed by the live orderbook
+#[derive(Debug, Clone)]
+pub struct PaperAccount {
+    /// Account state
+    state: PaperState,
+    /// Maker fee charged on fills, in basis points
+    maker_fee_bps: f64,
+    /// File the state is written to after every change; `None` keeps it in memory
+    state_file: Option<PathBuf>,
+}
+
+impl PaperAccount {
+    /// Opens the simulated account, resuming from `state_file` if it exists
+    ///
+    /// # Arguments
+    /// * `config` - Paper trading settings
+    ///
+    /// # Returns
+    /// The account; a fresh one holds `initial_position` at `initial_entry_price`
+    pub fn open(config: &PaperTradingConfig) -> Result<Self> {
+        let state = match &config.state_file {
+            Some(path) if path.exists() => {
+                let text = std::fs::read_to_string(path)
+                    .with_context(|| format!("Failed to read paper account {}", path.display()))?;
+                serde_json::from_str(&text)
+                    .with_context(|| format!("Invalid paper account {}", path.display()))?
+            }
+            _ => PaperState {
+                position: config.initial_position,
+                entry_price: config.initial_entry_price.unwrap_or_default(),
+                realized_pnl: 0.0,
+                fees_paid: 0.0,
+                filled_notional: 0.0,
+                fills: 0,
+                open_orders: Vec::new(),
+                next_order_id: 1,
+                updated_at_ms: now_ms(),
+            },
+        };
+        Ok(Self {
+            state,
+            maker_fee_bps: config.maker_fee_bps,
+            state_file: config.state_file.clone(),
+        })
+    }
+
+    /// Signed position in BASE units (negative = short)
+    pub fn position(&self) -> f64 {
+        self.state.position
+    }
+
+    /// Resting orders, oldest first
+    pub fn open_orders(&self) -> &[PaperOrder] {
+        &self.state.open_orders
+    }
+
+    /// Time of the last fill or order, in milliseconds since Unix epoch
+    pub fn updated_at_ms(&self) -> i64 {
+        self.state.updated_at_ms
+    }
+
+    /// Unrealized PnL of the position at `mark_price`, in USDT
+    pub fn unrealized_pnl(&self, mark_price: f64) -> f64 {
+        self.state.position * (mark_price - self.state.entry_price)
+    }
+
+    /// Places a limit order on the simulated account
+    ///
+    /// # Arguments
+    /// * `request` - Order built by `open_sell_request` or `close_sell_request`
+    ///
+    /// # Returns
+    /// Identifier of the resting order
+    pub fn place(&mut self, request: &PlaceOrderRequest) -> Result<u64> {
+        let quantity = parse(&request.quantity, "quantity")?;
+        let price = parse(
+            request
+                .price
+                .as_deref()
+                .ok_or_else(|| anyhow!("paper orders need a limit price"))?,
+            "price",
+        )?;
+        let order_id = self.state.next_order_id;
+        self.state.next_order_id += 1;
+        self.state.open_orders.push(PaperOrder {
+            order_id,
+            side: request.side,
+            price,
+            remaining: quantity,
+            placed_at_ms: now_ms(),
+        });
+        self.state.updated_at_ms = now_ms();
+        self.save()?;
+        Ok(order_id)
+    }
+
+    /// Fills resting orders the orderbook has reached
+    ///
+    /// Orders are matched oldest first; each level's quantity is consumed once per call. Buys
+    /// close the short only: the part that would turn the position long is cancelled.
+    ///
+    /// # Arguments
+    /// * `orderbook` - Current orderbook of the symbol
+    ///
+    /// # Returns
+    /// The fills, in matching order
+    pub fn match_orderbook(&mut self, orderbook: &Orderbook) -> Result<Vec<PaperFill>> {
+        let mut bids = parse_levels(&orderbook.bids)?;
+        let mut asks = parse_levels(&orderbook.asks)?;
+        let mut fills = Vec::new();
+        let mut orders = std::mem::take(&mut self.state.open_orders);
+        for order in &mut orders {
+            if order.side == Side::Buy {
+                order.remaining = order.remaining.min(-self.state.position).max(0.0);
+            }
+            let levels = match order.side {
+                // A resting sell is lifted by bids at or above its price
+                Side::Sell => &mut bids,
+                Side::Buy => &mut asks,
+            };
+            let mut quantity = 0.0;
+            for (price, available) in levels.iter_mut() {
+                let reached = match order.side {
+                    Side::Sell => *price >= order.price,
+                    Side::Buy => *price <= order.price,
+                };
+                if !reached || quantity >= order.remaining {
+                    break;
+                }
+                let take = available.min(order.remaining - quantity);
+                *available -= take;
+                quantity += take;
+            }
+            if quantity <= 0.0 {
+                continue;
+            }
+            order.remaining -= quantity;
+            let fill = PaperFill {
+                order_id: order.order_id,
+                side: order.side,
+                quantity,
+                price: order.price,
+                fee: quantity * order.price * self.maker_fee_bps / 10_000.0,
+            };
+            self.apply(&fill);
+            fills.push(fill);
+        }
+        // Dust left by float arithmetic counts as filled
+        orders.retain(|order| order.remaining > 1e-12);
+        let changed = !fills.is_empty() || orders.len() != self.state.open_orders.len();
+        self.state.open_orders = orders;
+        if changed {
+            self.state.updated_at_ms = now_ms();
+            self.save()?;
+        }
+        Ok(fills)
+    }
+
+    /// Cancels every resting order
+    ///
+    /// # Returns
+    /// Number of cancelled orders
+    pub fn cancel_all(&mut self) -> Result<usize> {
+        let cancelled = self.state.open_orders.len();
+        if cancelled > 0 {
+            self.state.open_orders.clear();
+            self.state.updated_at_ms = now_ms();
+            self.save()?;
+        }
+        Ok(cancelled)
+    }
+
+    /// Summary of the account for reports
+    ///
+    /// # Arguments
+    /// * `mark_price` - Current mark price of the symbol
+    pub fn snapshot(&self, mark_price: f64) -> PaperAccountSnapshot {
+        let state = &self.state;
+        PaperAccountSnapshot {
+            position: state.position,
+            entry_price: state.entry_price,
+            unrealized_pnl: self.unrealized_pnl(mark_price),
+            realized_pnl: state.realized_pnl,
+            fees_paid: state.fees_paid,
+            net_pnl: state.realized_pnl + self.unrealized_pnl(mark_price) - state.fees_paid,
+            filled_notional: state.filled_notional,
+            fills: state.fills,
+            open_orders: state.open_orders.len(),
+        }
+    }
+
+    /// Applies a fill to the position, realizing PnL on the reduced part
+    fn apply(&mut self, fill: &PaperFill) {
+        let state = &mut self.state;
+        let signed = match fill.side {
+            Side::Buy => fill.quantity,
+            Side::Sell => -fill.quantity,
+        };
+        let position = state.position;
+        if position == 0.0 || position.signum() == signed.signum() {
+            // Increase: average the entry price
+            let size = position.abs() + fill.quantity;
+            state.entry_price =
+                (position.abs() * state.entry_price + fill.quantity * fill.price) / size;
+        } else {
+            let closed = fill.quantity.min(position.abs());
+            state.realized_pnl += closed * (fill.price - state.entry_price) * position.signum();
+            if fill.quantity > position.abs() {
+                // Flipped: the rest opens a new position at the fill price
+                state.entry_price = fill.price;
+            }
+        }
+        state.position = position + signed;
+        if state.position.abs() < 1e-12 {
+            state.position = 0.0;
+            state.entry_price = 0.0;
+        }
+        state.fees_paid += fill.fee;
+        state.filled_notional += fill.quantity * fill.price;
+        state.fills += 1;
+    }
+
+    /// Sets the entry price of an initial position that was configured without one
+    pub(crate) fn seed_entry_price(&mut self, mark_price: f64) {
+        if self.state.position != 0.0 && self.state.entry_price == 0.0 {
+            self.state.entry_price = mark_price;
+        }
+    }
+
+    /// Writes the state to the state file, if any
+    fn save(&self) -> Result<()> {
+        let Some(path) = &self.state_file else {
+            return Ok(());
+        };
+        write_atomic(path, &serde_json::to_string_pretty(&self.state)?)
+    }
+}
+
+/// Writes through a temporary file so a crash never leaves a truncated state file
+fn write_atomic(path: &Path, text: &str) -> Result<()> {
+    let tmp = path.with_extension("tmp");
+    std::fs::write(&tmp, text).with_context(|| format!("Failed to write {}", tmp.display()))?;
+    std::fs::rename(&tmp, path).with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// Parses `[price, quantity]` orderbook levels
+fn parse_levels(levels: &[[String; 2]]) -> Result<Vec<(f64, f64)>> {
+    levels
+        .iter()
+        .map(|[price, quantity]| {
+            Ok((
+                parse(price, "level price")?,
+                parse(quantity, "level quantity")?,
+            ))
+        })
+        .collect()
+}
+
+fn parse(value: &str, name: &str) -> Result<f64> {
+    value
+        .parse::<f64>()
+        .map_err(|e| anyhow!("Failed to parse paper {} {}: {}", name, value, e))
+}
+
+/// Current time in milliseconds since Unix epoch
+pub(crate) fn now_ms() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clients_binance::{OrderType, PositionSide, TimeInForce};
+
+    fn account(initial_position: f64, state_file: Option<PathBuf>) -> PaperAccount {
+        PaperAccount::open(&PaperTradingConfig {
+            initial_position,
+            initial_entry_price: Some(100.0),
+            maker_fee_bps: 2.0,
+            state_file,
+        })
+        .unwrap()
+    }
+
+    fn limit(side: Side, quantity: &str, price: &str) -> PlaceOrderRequest {
+        PlaceOrderRequest {
+            side,
+            position_side: PositionSide::Both,
+            order_type: OrderType::Limit,
+            quantity: quantity.to_string(),
+            price: Some(price.to_string()),
+            reduce_only: None,
+            time_in_force: TimeInForce::Gtc,
+            client_order_id: None,
+        }
+    }
+
+    fn book(bids: &[(&str, &str)], asks: &[(&str, &str)]) -> Orderbook {
+        let levels = |levels: &[(&str, &str)]| {
+            levels
+                .iter()
+                .map(|(price, quantity)| [price.to_string(), quantity.to_string()])
+                .collect()
+        };
+        Orderbook {
+            last_update_id: 1,
+            e: 0,
+            t: 0,
+            bids: levels(bids),
+            asks: levels(asks),
+        }
+    }
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() < 1e-9,
+            "{} != {}",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn resting_sell_fills_at_its_limit_up_to_the_crossing_bids() {
+        let mut paper = account(0.0, None);
+        let order_id = paper.place(&limit(Side::Sell, "3", "100")).unwrap();
+
+        let fills = paper
+            .match_orderbook(&book(
+                &[("101", "1"), ("100", "1.5"), ("99", "5")],
+                &[("102", "10")],
+            ))
+            .unwrap();
+        assert_eq!(fills.len(), 1);
+        let fill = fills[0];
+        assert_eq!(fill.order_id, order_id);
+        assert_close(fill.quantity, 2.5);
+        assert_close(fill.price, 100.0);
+        // Maker fee of 2 bps on the filled notional
+        assert_close(fill.fee, 2.5 * 100.0 * 2.0 / 10_000.0);
+        assert_close(paper.position(), -2.5);
+        assert_eq!(paper.open_orders().len(), 1);
+        assert_close(paper.open_orders()[0].remaining, 0.5);
+
+        let fills = paper
+            .match_orderbook(&book(&[("100", "1")], &[("101", "1")]))
+            .unwrap();
+        assert_close(fills[0].quantity, 0.5);
+        assert!(paper.open_orders().is_empty());
+
+        let snapshot = paper.snapshot(100.0);
+        assert_close(snapshot.position, -3.0);
+        assert_close(snapshot.entry_price, 100.0);
+        assert_close(snapshot.fees_paid, 3.0 * 100.0 * 2.0 / 10_000.0);
+        assert_close(snapshot.filled_notional, 300.0);
+        assert_eq!(snapshot.fills, 2);
+    }
+
+    #[test]
+    fn resting_sell_below_the_bids_stays_open() {
+        let mut paper = account(0.0, None);
+        paper.place(&limit(Side::Sell, "1", "100")).unwrap();
+        let fills = paper
+            .match_orderbook(&book(&[("99.9", "10")], &[("100.1", "10")]))
+            .unwrap();
+        assert!(fills.is_empty());
+        assert_eq!(paper.open_orders().len(), 1);
+        assert_close(paper.open_orders()[0].remaining, 1.0);
+        assert_eq!(paper.position(), 0.0);
+        assert_eq!(paper.snapshot(100.0).fills, 0);
+    }
+
+    #[test]
+    fn a_level_is_consumed_once_oldest_order_first() {
+        let mut paper = account(0.0, None);
+        let first = paper.place(&limit(Side::Sell, "1", "100")).unwrap();
+        let second = paper.place(&limit(Side::Sell, "1", "100")).unwrap();
+        let fills = paper
+            .match_orderbook(&book(&[("100", "1.5")], &[]))
+            .unwrap();
+        assert_eq!(fills.len(), 2);
+        assert_eq!((fills[0].order_id, fills[1].order_id), (first, second));
+        assert_close(fills[0].quantity, 1.0);
+        assert_close(fills[1].quantity, 0.5);
+    }
+
+    #[test]
+    fn buys_close_the_short_only_and_realize_pnl() {
+        let mut paper = account(-1.0, None);
+        paper.place(&limit(Side::Buy, "2", "99")).unwrap();
+        let fills = paper
+            .match_orderbook(&book(&[("97", "10")], &[("98", "10")]))
+            .unwrap();
+        assert_close(fills[0].quantity, 1.0);
+        assert_close(fills[0].price, 99.0);
+        // The part that would turn the account long is cancelled
+        assert!(paper.open_orders().is_empty());
+        let snapshot = paper.snapshot(98.0);
+        assert_eq!(snapshot.position, 0.0);
+        assert_close(snapshot.realized_pnl, 1.0);
+        assert_close(snapshot.net_pnl, 1.0 - 99.0 * 2.0 / 10_000.0);
+    }
+
+    #[test]
+    fn reopening_resumes_the_written_state() {
+        let path = std::env::temp_dir().join(format!("lph-paper-{}.json", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        let mut paper = account(0.0, Some(path.clone()));
+        paper.place(&limit(Side::Sell, "2", "100")).unwrap();
+        paper
+            .match_orderbook(&book(&[("100", "0.5")], &[]))
+            .unwrap();
+        // Written through a temporary file that is renamed over the state file
+        assert!(path.exists());
+        assert!(!path.with_extension("tmp").exists());
+
+        let mut resumed = account(-5.0, Some(path.clone()));
+        let state = resumed.snapshot(100.0);
+        assert_close(state.position, -0.5);
+        assert_eq!(state.fills, 1);
+        assert_eq!(resumed.open_orders().len(), 1);
+        assert_close(resumed.open_orders()[0].remaining, 1.5);
+        assert_eq!(resumed.updated_at_ms(), paper.updated_at_ms());
+        // Order IDs continue after the resumed ones
+        assert_eq!(resumed.place(&limit(Side::Sell, "1", "101")).unwrap(), 2);
+
+        resumed.cancel_all().unwrap();
+        let cancelled = account(0.0, Some(path.clone()));
+        std::fs::remove_file(&path).unwrap();
+        assert!(cancelled.open_orders().is_empty());
+        assert_close(cancelled.position(), -0.5);
+    }
+}
@@ -0,0 +1,573 @@
+//! LP PnL attribution versus holding, combined with the futures hedge.
+//!
+//! A position's history is replayed in BASE/USDT terms:
+//! - **Price exposure** is the PnL of holding the deposited tokens instead (HODL).
+//!   Every liquidity decrease realizes the same fraction of the HODL basket.
+//! - **Impermanent loss** is the LP principal PnL minus the HODL PnL.
+//! - **Fees** are collected amounts in excess of removed principal, plus fees still
+//!   collectable at the end block.
+//!
+//! `lp_pnl = price_exposure + impermanent_loss + fees`. The hedge PnL comes from the
+//! Binance income history over the same time window.
+
+use alloy::primitives::{Address, U256};
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+
+use clients_binance::BinancePerpsClient;
+use clients_uniswapv3::math::sqrt_price_x96_to_f64;
+use clients_uniswapv3::{HistoryEventKind, PositionHistory, UniswapV3PositionManager};
+
+/// One replayed position event in BASE/USDT terms
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PnlEvent {
+    /// Block the event was emitted in
+    pub block_number: u64,
+    /// Timestamp of the block, in seconds since Unix epoch
+    pub timestamp: u64,
+    /// Event kind
+    pub kind: HistoryEventKind,
+    /// BASE price in USDT at the event block
+    pub price_usdt: f64,
+    /// BASE amount of the event
+    pub base_amount: f64,
+    /// USDT amount of the event
+    pub usdt_amount: f64,
+    /// Value of the event amounts in USDT at `price_usdt`
+    pub value_usdt: f64,
+    /// Fee part of a `Collect`, in USDT at `price_usdt` (zero for other kinds)
+    pub fees_usdt: f64,
+}
+
+/// Hedged PnL attribution of one LP position
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PnlReport {
+    /// The position NFT token ID
+    pub token_id: U256,
+    /// Binance futures symbol of the hedge
+    pub symbol: String,
+    /// Block the position was minted in
+    pub mint_block: u64,
+    /// Last block of the report
+    pub end_block: u64,
+    /// Start of the report window, in milliseconds since Unix epoch
+    pub start_time_ms: i64,
+    /// End of the report window, in milliseconds since Unix epoch
+    pub end_time_ms: i64,
+    /// Whether the position NFT was burned
+    pub burned: bool,
+    /// BASE price in USDT at the end block
+    pub end_price_usdt: f64,
+    /// Value of all deposits at deposit time, in USDT
+    pub deposits_usdt: f64,
+    /// Value of all removed principal at removal time, in USDT
+    pub withdrawals_usdt: f64,
+    /// Value of the principal still in the position at the end block, in USDT
+    pub position_value_usdt: f64,
+    /// Fees earned (collected at collect time plus collectable at the end block), in USDT
+    pub fees_usdt: f64,
+    /// PnL of holding the deposited tokens instead of providing liquidity, in USDT
+    pub price_exposure_usdt: f64,
+    /// LP principal PnL minus the HODL PnL, in USDT (negative is a loss)
+    pub impermanent_loss_usdt: f64,
+    /// `price_exposure + impermanent_loss + fees`, in USDT
+    pub lp_pnl_usdt: f64,
+    /// Realized futures PnL over the window
+    pub hedge_realized_pnl_usdt: f64,
+    /// Funding fees over the window
+    pub hedge_funding_usdt: f64,
+    /// Trading commissions over the window
+    pub hedge_commission_usdt: f64,
+    /// Unrealized PnL of the open futures position (zero once the LP was burned)
+    pub hedge_unrealized_pnl_usdt: f64,
+    /// Sum of the hedge components
+    pub hedge_pnl_usdt: f64,
+    /// `lp_pnl + hedge_pnl`
+    pub net_pnl_usdt: f64,
+    /// Replayed position events
+    pub events: Vec<PnlEvent>,
+}
+
+/// LP-side attribution computed from a position history
+#[derive(Debug, Clone)]
+struct LpAttribution {
+    end_price_usdt: f64,
+    deposits_usdt: f64,
+    withdrawals_usdt: f64,
+    position_value_usdt: f64,
+    fees_usdt: f64,
+    price_exposure_usdt: f64,
+    impermanent_loss_usdt: f64,
+    events: Vec<PnlEvent>,
+}
+
+/// Builds the hedged PnL attribution report of a position
+///
+/// The futures side covers every income entry of `symbol` between the mint block and the
+/// end block, so it includes hedges of other positions on the same symbol. Binance keeps
+/// only the most recent three months of income history.
+///
+/// # Arguments
+/// * `uniswap_client` - Source of the position history (requires an archive node)
+/// * `binance_client` - Source of the futures income history and open position
+/// * `token_id` - The position NFT token ID
+/// * `base_token_address` - Address of the BASE token; the other token is treated as USDT
+/// * `symbol` - Binance futures symbol of the hedge
+/// * `mint_block` - Block the position was minted in, if known (required once burned)
+/// * `max_block_range` - Maximum number of blocks covered by one `eth_getLogs` request
+pub async fn build_pnl_report(
+    uniswap_client: &mut UniswapV3PositionManager,
+    binance_client: &BinancePerpsClient,
+    token_id: U256,
+    base_token_address: Address,
+    symbol: &str,
+    mint_block: Option<u64>,
+    max_block_range: u64,
+) -> Result<PnlReport> {
+    let history = uniswap_client
+        .position_history(token_id, mint_block, max_block_range)
+        .await?;
+    let lp = attribute_lp(&history, base_token_address)?;
+
+    let start_time_ms = history.start_timestamp as i64 * 1000;
+    let end_time_ms = history.end_timestamp as i64 * 1000 + 999;
+    let incomes = binance_client
+        .get_income_history(symbol, start_time_ms, end_time_ms)
+        .await?;
+    let (mut realized, mut funding, mut commission) = (0.0, 0.0, 0.0);
+    for income in &incomes {
+        let amount = income
+            .income
+            .parse::<f64>()
+            .map_err(|e| anyhow!("Failed to parse income {}: {}", income.income, e))?;
+        match income.income_type.as_str() {
+            "REALIZED_PNL" => realized += amount,
+            "FUNDING_FEE" => funding += amount,
+            "COMMISSION" => commission += amount,
+            _ => {}
+        }
+    }
+    let unrealized = if history.burned {
+        0.0
+    } else {
+        binance_client
+            .get_position(symbol)
+            .await?
+            .iter()
+            .filter(|p| p.symbol == symbol)
+            .map(|p| p.unrealized_pnl.parse::<f64>())
+            .sum::<Result<f64, _>>()
+            .map_err(|e| anyhow!("Failed to parse unrealized_pnl: {}", e))?
+    };
+
+    let lp_pnl_usdt = lp.price_exposure_usdt + lp.impermanent_loss_usdt + lp.fees_usdt;
+    let hedge_pnl_usdt = realized + funding + commission + unrealized;
+    Ok(PnlReport {
+        token_id,
+        symbol: symbol.to_string(),
+        mint_block: history.mint_block,
+        end_block: history.end_block,
+        start_time_ms,
+        end_time_ms,
+        burned: history.burned,
+        end_price_usdt: lp.end_price_usdt,
+        deposits_usdt: lp.deposits_usdt,
+        withdrawals_usdt: lp.withdrawals_usdt,
+        position_value_usdt: lp.position_value_usdt,
+        fees_usdt: lp.fees_usdt,
+        price_exposure_usdt: lp.price_exposure_usdt,
+        impermanent_loss_usdt: lp.impermanent_loss_usdt,
+        lp_pnl_usdt,
+        hedge_realized_pnl_usdt: realized,
+        hedge_funding_usdt: funding,
+        hedge_commission_usdt: commission,
+        hedge_unrealized_pnl_usdt: unrealized,
+        hedge_pnl_usdt,
+        net_pnl_usdt: lp_pnl_usdt + hedge_pnl_usdt,
+        events: lp.events,
+    })
+}
+
+/// Replays a position history in BASE/USDT terms and splits its PnL
+fn attribute_lp(history: &PositionHistory, base_token_address: Address) -> Result<LpAttribution> {
+    let base_is_token0 = history.token0_metadata.address == base_token_address;
+    if !base_is_token0 && history.token1_metadata.address != base_token_address {
+        return Err(anyhow!(
+            "Position {} is not on a pair with BASE token {:?}",
+            history.token_id,
+            base_token_address
+        ));
+    }
+    let (base_decimals, usdt_decimals) = if base_is_token0 {
+        (
+            history.token0_metadata.decimals,
+            history.token1_metadata.decimals,
+        )
+    } else {
+        (
+            history.token1_metadata.decimals,
+            history.token0_metadata.decimals,
+        )
+    };
+    let price_scale = 10f64.powi(base_decimals as i32 - usdt_decimals as i32);
+    let price_usdt = |sqrt_price_x96: U256| {
+        let raw = sqrt_price_x96_to_f64(sqrt_price_x96).powi(2);
+        if base_is_token0 {
+            raw * price_scale
+        } else {
+            price_scale / raw
+        }
+    };
+    // Raw (amount0, amount1) to human (base, usdt)
+    let to_base_usdt = |amount0: U256, amount1: U256| {
+        let (base, usdt) = if base_is_token0 {
+            (amount0, amount1)
+        } else {
+            (amount1, amount0)
+        };
+        (
+            utils::u256_to_f64(base, base_decimals),
+            utils::u256_to_f64(usdt, usdt_decimals),
+        )
+    };
+
+    let mut deposits_usdt = 0.0;
+    let mut withdrawals_usdt = 0.0;
+    let mut fees_usdt = 0.0;
+    // HODL basket and the value it realized on liquidity decreases
+    let (mut hodl_base, mut hodl_usdt, mut hodl_realized_usdt) = (0.0, 0.0, 0.0);
+    let mut liquidity: u128 = 0;
+    // Removed principal not collected yet, in raw token units
+    let (mut owed0, mut owed1) = (U256::ZERO, U256::ZERO);
+
+    let mut events = Vec::with_capacity(history.events.len());
+    for event in &history.events {
+        let price = price_usdt(event.sqrt_price_x96);
+        let (base_amount, usdt_amount) = to_base_usdt(event.amount0, event.amount1);
+        let value_usdt = base_amount * price + usdt_amount;
+        let mut event_fees_usdt = 0.0;
+        match event.kind {
+            HistoryEventKind::IncreaseLiquidity => {
+                deposits_usdt += value_usdt;
+                hodl_base += base_amount;
+                hodl_usdt += usdt_amount;
+                liquidity += event.liquidity;
+            }
+            HistoryEventKind::DecreaseLiquidity => {
+                withdrawals_usdt += value_usdt;
+                owed0 += event.amount0;
+                owed1 += event.amount1;
+                if liquidity > 0 {
+                    let fraction = (event.liquidity as f64 / liquidity as f64).min(1.0);
+                    hodl_realized_usdt += fraction * (hodl_base * price + hodl_usdt);
+                    hodl_base *= 1.0 - fraction;
+                    hodl_usdt *= 1.0 - fraction;
+                }
+                liquidity = liquidity.saturating_sub(event.liquidity);
+            }
+            HistoryEventKind::Collect => {
+                let principal0 = event.amount0.min(owed0);
+                let principal1 = event.amount1.min(owed1);
+                owed0 -= principal0;
+                owed1 -= principal1;
+                let (fee_base, fee_usdt) =
+                    to_base_usdt(event.amount0 - principal0, event.amount1 - principal1);
+                event_fees_usdt = fee_base * price + fee_usdt;
+                fees_usdt += event_fees_usdt;
+            }
+        }
+        events.push(PnlEvent {
+            block_number: event.block_number,
+            timestamp: event.timestamp,
+            kind: event.kind,
+            price_usdt: price,
+            base_amount,
+            usdt_amount,
+            value_usdt,
+            fees_usdt: event_fees_usdt,
+        });
+    }
+
+    // Value what remains at the end block; collectable amounts beyond owed principal are fees
+    let end_price_usdt = price_usdt(history.end_sqrt_price_x96);
+    let (base_amount, usdt_amount) =
+        to_base_usdt(history.withdrawable_amount0, history.withdrawable_amount1);
+    let position_value_usdt = base_amount * end_price_usdt + usdt_amount;
+    let (fee_base, fee_usdt) = to_base_usdt(
+        history.collectable_amount0.saturating_sub(owed0),
+        history.collectable_amount1.saturating_sub(owed1),
+    );
+    fees_usdt += fee_base * end_price_usdt + fee_usdt;
+
+    let price_exposure_usdt =
+        hodl_realized_usdt + hodl_base * end_price_usdt + hodl_usdt - deposits_usdt;
+    let lp_principal_pnl = withdrawals_usdt + position_value_usdt - deposits_usdt;
+    Ok(LpAttribution {
+        end_price_usdt,
+        deposits_usdt,
+        withdrawals_usdt,
+        position_value_usdt,
+        fees_usdt,
+        price_exposure_usdt,
+        impermanent_loss_usdt: lp_principal_pnl - price_exposure_usdt,
+        events,
+    })
+}
+
+impl PnlReport {
+    /// Serializes the full report, including the replayed events, as pretty-printed JSON
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    /// Formats the attribution summary as a two-column `metric,value` CSV
+    pub fn summary_csv(&self) -> String {
+        let rows: [(&str, String); 22] = [
+            ("token_id", self.token_id.to_string()),
+            ("symbol", self.symbol.clone()),
+            ("mint_block", self.mint_block.to_string()),
+            ("end_block", self.end_block.to_string()),
+            ("start_time_ms", self.start_time_ms.to_string()),
+            ("end_time_ms", self.end_time_ms.to_string()),
+            ("burned", self.burned.to_string()),
+            ("end_price_usdt", self.end_price_usdt.to_string()),
+            ("deposits_usdt", self.deposits_usdt.to_string()),
+            ("withdrawals_usdt", self.withdrawals_usdt.to_string()),
+            ("position_value_usdt", self.position_value_usdt.to_string()),
+            ("fees_usdt", self.fees_usdt.to_string()),
+            ("price_exposure_usdt", self.price_exposure_usdt.to_string()),
+            (
+                "impermanent_loss_usdt",
+                self.impermanent_loss_usdt.to_string(),
+            ),
+            ("lp_pnl_usdt", self.lp_pnl_usdt.to_string()),
+            (
+                "hedge_realized_pnl_usdt",
+                self.hedge_realized_pnl_usdt.to_string(),
+            ),
+            ("hedge_funding_usdt", self.hedge_funding_usdt.to_string()),
+            (
+                "hedge_commission_usdt",
+                self.hedge_commission_usdt.to_string(),
+            ),
+            (
+                "hedge_unrealized_pnl_usdt",
+                self.hedge_unrealized_pnl_usdt.to_string(),
+            ),
+            ("hedge_pnl_usdt", self.hedge_pnl_usdt.to_string()),
+            ("net_pnl_usdt", self.net_pnl_usdt.to_string()),
+            ("events", self.events.len().to_string()),
+        ];
+        let mut csv = String::from("metric,value\n");
+        for (metric, value) in rows {
+            csv.push_str(&format!("{},{}\n", metric, value));
+        }
+        csv
+    }
+
+    /// Formats the replayed events as CSV, one row per event
+    pub fn events_csv(&self) -> String {
+        let mut csv = String::from(
+            "block_number,timestamp,kind,price_usdt,base_amount,usdt_amount,value_usdt,fees_usdt\n",
+        );
+        for event in &self.events {
+            csv.push_str(&format!(
+                "{},{},{:?},{},{},{},{},{}\n",
+                event.block_number,
+                event.timestamp,
+                event.kind,
+                event.price_usdt,
+                event.base_amount,
+                event.usdt_amount,
+                event.value_usdt,
+                event.fees_usdt
+            ));
+        }
+        csv
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy::primitives::address;
+    use clients_uniswapv3::{HistoryEvent, TokenMetadata};
+
+    const BASE: Address = address!("0x00000000000000000000000000000000000000b1");
+    const USDT: Address = address!("0x00000000000000000000000000000000000000a1");
+
+    fn token(address: Address, decimals: u8) -> TokenMetadata {
+        TokenMetadata {
+            address,
+            decimals,
+            symbol: String::new(),
+            name: String::new(),
+        }
+    }
+
+    fn amount(value: &str, decimals: u8) -> U256 {
+        utils::parse_units(value, decimals).unwrap()
+    }
+
+    /// `sqrt(price) * 2^96` for an integer square root
+    fn sqrt_price(sqrt: u64) -> U256 {
+        U256::from(sqrt) << 96
+    }
+
+    fn event(
+        kind: HistoryEventKind,
+        liquidity: u128,
+        amounts: (U256, U256),
+        sqrt_price_x96: U256,
+    ) -> HistoryEvent {
+        HistoryEvent {
+            block_number: 0,
+            timestamp: 0,
+            kind,
+            liquidity,
+            amount0: amounts.0,
+            amount1: amounts.1,
+            sqrt_price_x96,
+        }
+    }
+
+    /// BASE/USDT position with both tokens at 18 decimals, BASE as token0
+    fn history(events: Vec<HistoryEvent>, end_sqrt_price_x96: U256) -> PositionHistory {
+        PositionHistory {
+            token_id: U256::from(1),
+            token0_metadata: token(BASE, 18),
+            token1_metadata: token(USDT, 18),
+            fee: 500,
+            tick_lower: -600,
+            tick_upper: 600,
+            pool: Address::ZERO,
+            mint_block: 0,
+            end_block: 10,
+            start_timestamp: 0,
+            end_timestamp: 10,
+            end_sqrt_price_x96,
+            burned: false,
+            withdrawable_amount0: U256::ZERO,
+            withdrawable_amount1: U256::ZERO,
+            collectable_amount0: U256::ZERO,
+            collectable_amount1: U256::ZERO,
+            events,
+        }
+    }
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() < 1e-9 * expected.abs().max(1.0),
+            "{} != {}",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn open_position_splits_price_exposure_impermanent_loss_and_fees() {
+        // 1 BASE + 100 USDT deposited at 100, valued at 400 as 0.25 BASE + 250 USDT
+        let mut history = history(
+            vec![event(
+                HistoryEventKind::IncreaseLiquidity,
+                1_000,
+                (amount("1", 18), amount("100", 18)),
+                sqrt_price(10),
+            )],
+            sqrt_price(20),
+        );
+        history.withdrawable_amount0 = amount("0.25", 18);
+        history.withdrawable_amount1 = amount("250", 18);
+        history.collectable_amount0 = amount("0.01", 18);
+        history.collectable_amount1 = amount("2", 18);
+
+        let lp = attribute_lp(&history, BASE).unwrap();
+        assert_close(lp.end_price_usdt, 400.0);
+        assert_close(lp.deposits_usdt, 200.0);
+        assert_close(lp.position_value_usdt, 350.0);
+        // Holding would be worth 1 * 400 + 100
+        assert_close(lp.price_exposure_usdt, 300.0);
+        assert_close(lp.impermanent_loss_usdt, -150.0);
+        assert_close(lp.fees_usdt, 6.0);
+        assert_eq!(lp.events.len(), 1);
+        assert_close(lp.events[0].value_usdt, 200.0);
+    }
+
+    #[test]
+    fn collected_principal_is_not_counted_as_fees() {
+        // Half the liquidity is removed at 400 and collected together with the fees
+        let history = {
+            let mut history = history(
+                vec![
+                    event(
+                        HistoryEventKind::IncreaseLiquidity,
+                        1_000,
+                        (amount("1", 18), amount("100", 18)),
+                        sqrt_price(10),
+                    ),
+                    event(
+                        HistoryEventKind::DecreaseLiquidity,
+                        500,
+                        (amount("0.125", 18), amount("125", 18)),
+                        sqrt_price(20),
+                    ),
+                    event(
+                        HistoryEventKind::Collect,
+                        0,
+                        (amount("0.135", 18), amount("127", 18)),
+                        sqrt_price(20),
+                    ),
+                ],
+                sqrt_price(20),
+            );
+            history.withdrawable_amount0 = amount("0.125", 18);
+            history.withdrawable_amount1 = amount("125", 18);
+            history
+        };
+
+        let lp = attribute_lp(&history, BASE).unwrap();
+        assert_close(lp.withdrawals_usdt, 175.0);
+        assert_close(lp.position_value_usdt, 175.0);
+        // Half the HODL basket realized at 400 (250) plus the other half at 400 (250)
+        assert_close(lp.price_exposure_usdt, 300.0);
+        assert_close(lp.impermanent_loss_usdt, -150.0);
+        assert_close(lp.fees_usdt, 6.0);
+        assert_close(lp.events[2].fees_usdt, 6.0);
+        assert_close(lp.events[1].fees_usdt, 0.0);
+    }
+
+    #[test]
+    fn base_as_token1_inverts_the_pool_price() {
+        // USDT (6 decimals) is token0: raw price 1e12 / 100 = (1e5)^2 means 100 USDT per BASE
+        let mut history = history(
+            vec![event(
+                HistoryEventKind::IncreaseLiquidity,
+                1_000,
+                (amount("100", 6), amount("1", 18)),
+                sqrt_price(100_000),
+            )],
+            sqrt_price(200_000),
+        );
+        history.token0_metadata = token(USDT, 6);
+        history.token1_metadata = token(BASE, 18);
+        history.withdrawable_amount0 = amount("125", 6);
+        history.withdrawable_amount1 = amount("2", 18);
+
+        let lp = attribute_lp(&history, BASE).unwrap();
+        assert_close(lp.events[0].price_usdt, 100.0);
+        assert_close(lp.events[0].base_amount, 1.0);
+        assert_close(lp.events[0].usdt_amount, 100.0);
+        assert_close(lp.end_price_usdt, 25.0);
+        assert_close(lp.position_value_usdt, 175.0);
+        // Holding 1 BASE + 100 USDT at 25 loses 75
+        assert_close(lp.price_exposure_usdt, -75.0);
+        assert_close(lp.impermanent_loss_usdt, 50.0);
+    }
+
+    #[test]
+    fn rejects_a_position_without_the_base_token() {
+        let history = history(Vec::new(), sqrt_price(1));
+        let other = address!("0x00000000000000000000000000000000000000c1");
+        assert!(attribute_lp(&history, other).is_err());
+    }
+}
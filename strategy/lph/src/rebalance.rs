@@ -0,0 +1,55 @@
+//! Range re-centering helpers for out-of-range LP positions.
+
+use clients_uniswapv3::math::amounts_for_liquidity;
+use clients_uniswapv3::PositionData;
+
+/// Swaps worth less than this fraction of the withdrawn value are skipped.
+const MIN_SWAP_FRACTION: f64 = 0.005;
+
+/// Returns true if the pool tick is more than `buffer` ticks outside the position range
+pub(crate) fn needs_recentering(pos: &PositionData, buffer: i32) -> bool {
+    pos.tick < pos.tick_lower.saturating_sub(buffer)
+        || pos.tick >= pos.tick_upper.saturating_add(buffer)
+}
+
+/// Computes the swap that brings `(amount0, amount1)` to the token ratio of a new range
+///
+/// Pool fees and price impact are ignored; `mint` deposits at most the desired amounts,
+/// so a small residual stays in the wallet.
+///
+/// # Arguments
+/// * `amount0` - Raw token0 amount available
+/// * `amount1` - Raw token1 amount available
+/// * `sqrt_price` - Raw sqrt price of the pool
+/// * `sqrt_lower` - Raw sqrt price at the lower tick of the new range
+/// * `sqrt_upper` - Raw sqrt price at the upper tick of the new range
+///
+/// # Returns
+/// `Some((zero_for_one, amount_in))` with the raw input amount, or `None` if the
+/// holdings are already close enough to the target ratio
+pub(crate) fn target_swap(
+    amount0: f64,
+    amount1: f64,
+    sqrt_price: f64,
+    sqrt_lower: f64,
+    sqrt_upper: f64,
+) -> Option<(bool, f64)> {
+    let price = sqrt_price * sqrt_price;
+    let total_value = amount0 * price + amount1;
+    if total_value <= 0.0 {
+        return None;
+    }
+
+    // Token ratio held by one unit of liquidity in the new range
+    let (unit0, unit1) = amounts_for_liquidity(1.0, sqrt_price, sqrt_lower, sqrt_upper);
+    let target0 = total_value * unit0 / (unit0 * price + unit1);
+
+    let (zero_for_one, amount_in, value_in) = if amount0 > target0 {
+        let amount_in = amount0 - target0;
+        (true, amount_in, amount_in * price)
+    } else {
+        let amount_in = (target0 - amount0) * price;
+        (false, amount_in, amount_in)
+    };
+    (value_in >= total_value * MIN_SWAP_FRACTION).then_some((zero_for_one, amount_in))
+}
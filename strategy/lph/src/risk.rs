@@ -0,0 +1,456 @@
+//! Pre-trade risk checks every hedge order passes through.
+//!
+//! `RiskManager` checks a proposed order against the configured `RiskLimits` and keeps the
+//! rolling history of placed orders used by the turnover and order-count limits. The history
+//! lives in memory and is shared by clones of the manager, so a clone kept outside the
+//! strategy carries the windows over to a strategy rebuilt after a restart.
+
+use std::collections::VecDeque;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use crate::config::RiskLimits;
+
+/// Reason the risk module refused an order; no order was placed
+#[derive(Debug, Clone, PartialEq)]
+pub enum RiskRejection {
+    /// The kill switch file exists
+    KillSwitch {
+        /// Path of the kill switch file
+        file: PathBuf,
+    },
+    /// The order was built from unusable data (e.g. a zero price)
+    InvalidOrder {
+        /// What was wrong with the order
+        reason: String,
+    },
+    /// The order would breach a configured limit
+    Limit {
+        /// Name of the breached limit (e.g. `max_order_size`)
+        limit: &'static str,
+        /// Value the order would have reached
+        value: f64,
+        /// Configured limit
+        bound: f64,
+    },
+}
+
+impl std::fmt::Display for RiskRejection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RiskRejection::KillSwitch { file } => {
+                write!(f, "kill switch engaged ({})", file.display())
+            }
+            RiskRejection::InvalidOrder { reason } => write!(f, "invalid order: {}", reason),
+            RiskRejection::Limit {
+                limit,
+                value,
+                bound,
+            } => write!(f, "{} breached: {} against limit {}", limit, value, bound),
+        }
+    }
+}
+
+impl std::error::Error for RiskRejection {}
+
+/// Hedge order about to be placed
+#[derive(Debug, Clone, Copy)]
+pub struct ProposedOrder {
+    /// Signed order quantity in BASE units (negative sells, positive buys)
+    pub quantity: f64,
+    /// Limit price of the order
+    pub price: f64,
+    /// Current mark price of the symbol
+    pub mark_price: f64,
+    /// Signed futures position before the order, in BASE units
+    pub position: f64,
+}
+
+impl ProposedOrder {
+    /// Notional of the order at its limit price, in USDT
+    pub fn notional(&self) -> f64 {
+        self.quantity.abs() * self.price
+    }
+}
+
+/// Checks orders against `RiskLimits` and tracks the orders placed within the rolling windows
+///
+/// Clones share the order history.
+#[derive(Debug, Clone)]
+pub struct RiskManager {
+    /// Configured limits
+    limits: RiskLimits,
+    /// Time and notional of the orders placed, oldest first
+    history: Arc<Mutex<VecDeque<(Instant, f64)>>>,
+}
+
+impl RiskManager {
+    /// Creates a risk manager with an empty order history
+    pub fn new(limits: RiskLimits) -> Self {
+        Self {
+            limits,
+            history: Arc::new(Mutex::new(VecDeque::new())),
+        }
+    }
+
+    /// Configured limits
+    pub fn limits(&self) -> &RiskLimits {
+        &self.limits
+    }
+
+    /// Whether the kill switch file exists
+    pub fn kill_switch_engaged(&self) -> bool {
+        self.limits
+            .kill_switch_file
+            .as_ref()
+            .is_some_and(|file| file.exists())
+    }
+
+    /// Returns an error if the kill switch is engaged
+    pub fn check_kill_switch(&self) -> Result<(), RiskRejection> {
+        match &self.limits.kill_switch_file {
+            Some(file) if file.exists() => Err(RiskRejection::KillSwitch { file: file.clone() }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Checks a proposed order against every limit
+    ///
+    /// # Arguments
+    /// * `order` - Order about to be placed
+    ///
+    /// # Returns
+    /// The first rejection, checked in order: kill switch, invalid data, order size and
+    /// notional, price band, position bounds and notional, turnover, order count
+    pub fn check(&mut self, order: &ProposedOrder) -> Result<(), RiskRejection> {
+        self.check_at(order, Instant::now())
+    }
+
+    /// `check` with the rolling windows evaluated at `now`
+    fn check_at(&mut self, order: &ProposedOrder, now: Instant) -> Result<(), RiskRejection> {
+        self.check_kill_switch()?;
+        let valid = |value: f64| value.is_finite() && value > 0.0;
+        if !valid(order.quantity.abs()) || !valid(order.price) || !valid(order.mark_price) {
+            return Err(RiskRejection::InvalidOrder {
+                reason: format!(
+                    "quantity {}, price {}, mark price {}",
+                    order.quantity, order.price, order.mark_price
+                ),
+            });
+        }
+        if !order.position.is_finite() {
+            return Err(RiskRejection::InvalidOrder {
+                reason: format!("position {}", order.position),
+            });
+        }
+
+        self.prune(now);
+        let limits = &self.limits;
+        check_max(
+            "max_order_size",
+            order.quantity.abs(),
+            limits.max_order_size,
+        )?;
+        check_max(
+            "max_order_notional",
+            order.notional(),
+            limits.max_order_notional,
+        )?;
+        let deviation_bps = (order.price - order.mark_price).abs() / order.mark_price * 10_000.0;
+        check_max(
+            "max_price_deviation_bps",
+            deviation_bps,
+            limits.max_price_deviation_bps.map(f64::from),
+        )?;
+
+        let position_after = order.position + order.quantity;
+        check_max("max_position", position_after, limits.max_position)?;
+        if let Some(min) = limits.min_position {
+            if position_after < min {
+                return Err(RiskRejection::Limit {
+                    limit: "min_position",
+                    value: position_after,
+                    bound: min,
+                });
+            }
+        }
+        check_max(
+            "max_hedge_notional",
+            position_after.abs() * order.mark_price,
+            limits.max_hedge_notional,
+        )?;
+
+        let history = self.history();
+        let turnover_window = Duration::from_secs(limits.turnover_window_secs);
+        let turnover: f64 = history
+            .iter()
+            .filter(|(at, _)| now.duration_since(*at) < turnover_window)
+            .map(|(_, notional)| notional)
+            .sum();
+        check_max(
+            "max_turnover_notional",
+            turnover + order.notional(),
+            limits.max_turnover_notional,
+        )?;
+        let order_window = Duration::from_secs(limits.order_window_secs);
+        let orders = history
+            .iter()
+            .filter(|(at, _)| now.duration_since(*at) < order_window)
+            .count();
+        check_max(
+            "max_orders_per_window",
+            (orders + 1) as f64,
+            limits.max_orders_per_window.map(f64::from),
+        )
+    }
+
+    /// Records an order that is being placed, counting it towards the turnover and order limits
+    ///
+    /// Called before the order request is sent, so an order whose outcome is unknown still counts.
+    pub fn record(&mut self, order: &ProposedOrder) {
+        self.record_at(order, Instant::now());
+    }
+
+    /// `record` with the order placed at `at`
+    fn record_at(&mut self, order: &ProposedOrder, at: Instant) {
+        self.history().push_back((at, order.notional()));
+    }
+
+    /// Locks the order history; a clone that panicked while holding it left it consistent,
+    /// since every update is a single push or pop
+    fn history(&self) -> MutexGuard<'_, VecDeque<(Instant, f64)>> {
+        self.history
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Drops orders older than both rolling windows at `now`
+    fn prune(&mut self, now: Instant) {
+        let keep = Duration::from_secs(
+            self.limits
+                .turnover_window_secs
+                .max(self.limits.order_window_secs),
+        );
+        let mut history = self.history();
+        while history
+            .front()
+            .is_some_and(|(at, _)| now.duration_since(*at) >= keep)
+        {
+            history.pop_front();
+        }
+    }
+}
+
+fn check_max(limit: &'static str, value: f64, bound: Option<f64>) -> Result<(), RiskRejection> {
+    match bound {
+        Some(bound) if value > bound => Err(RiskRejection::Limit {
+            limit,
+            value,
+            bound,
+        }),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Sells 1 BASE at the mark price of 100 from a 2 BASE short
+    fn order() -> ProposedOrder {
+        ProposedOrder {
+            quantity: -1.0,
+            price: 100.0,
+            mark_price: 100.0,
+            position: -2.0,
+        }
+    }
+
+    fn breached(result: Result<(), RiskRejection>) -> &'static str {
+        match result {
+            Err(RiskRejection::Limit { limit, .. }) => limit,
+            other => panic!("expected a breached limit, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn no_limits_accept_every_valid_order() {
+        let mut risk = RiskManager::new(RiskLimits::default());
+        assert_eq!(risk.check(&order()), Ok(()));
+    }
+
+    #[test]
+    fn kill_switch_file_rejects_every_order() {
+        let file = std::env::temp_dir().join(format!("lph-risk-kill-{}", std::process::id()));
+        let mut risk = RiskManager::new(RiskLimits {
+            kill_switch_file: Some(file.clone()),
+            ..RiskLimits::default()
+        });
+        assert!(!risk.kill_switch_engaged());
+        assert_eq!(risk.check(&order()), Ok(()));
+
+        std::fs::write(&file, "").unwrap();
+        let result = risk.check(&order());
+        std::fs::remove_file(&file).unwrap();
+        assert_eq!(result, Err(RiskRejection::KillSwitch { file }));
+        assert!(!risk.kill_switch_engaged());
+    }
+
+    #[test]
+    fn unusable_prices_are_invalid() {
+        let mut risk = RiskManager::new(RiskLimits::default());
+        for order in [
+            ProposedOrder {
+                price: 0.0,
+                ..order()
+            },
+            ProposedOrder {
+                quantity: f64::NAN,
+                ..order()
+            },
+            ProposedOrder {
+                position: f64::INFINITY,
+                ..order()
+            },
+        ] {
+            assert!(matches!(
+                risk.check(&order),
+                Err(RiskRejection::InvalidOrder { .. })
+            ));
+        }
+    }
+
+    #[test]
+    fn order_size_and_notional_are_capped() {
+        let mut risk = RiskManager::new(RiskLimits {
+            max_order_size: Some(1.0),
+            ..RiskLimits::default()
+        });
+        assert_eq!(risk.check(&order()), Ok(()));
+        let larger = ProposedOrder {
+            quantity: -1.5,
+            ..order()
+        };
+        assert_eq!(breached(risk.check(&larger)), "max_order_size");
+
+        let mut risk = RiskManager::new(RiskLimits {
+            max_order_notional: Some(99.0),
+            ..RiskLimits::default()
+        });
+        assert_eq!(breached(risk.check(&order())), "max_order_notional");
+    }
+
+    #[test]
+    fn limit_price_must_stay_near_the_mark() {
+        let mut risk = RiskManager::new(RiskLimits {
+            max_price_deviation_bps: Some(50),
+            ..RiskLimits::default()
+        });
+        let within = ProposedOrder {
+            price: 100.4,
+            ..order()
+        };
+        assert_eq!(risk.check(&within), Ok(()));
+        let outside = ProposedOrder {
+            price: 99.4,
+            ..order()
+        };
+        assert_eq!(breached(risk.check(&outside)), "max_price_deviation_bps");
+    }
+
+    #[test]
+    fn position_after_the_order_stays_within_its_bounds() {
+        let mut risk = RiskManager::new(RiskLimits {
+            min_position: Some(-3.0),
+            max_position: Some(0.0),
+            ..RiskLimits::default()
+        });
+        assert_eq!(risk.check(&order()), Ok(()));
+        let short = ProposedOrder {
+            quantity: -1.5,
+            ..order()
+        };
+        assert_eq!(breached(risk.check(&short)), "min_position");
+        let long = ProposedOrder {
+            quantity: 2.5,
+            ..order()
+        };
+        assert_eq!(breached(risk.check(&long)), "max_position");
+    }
+
+    #[test]
+    fn hedge_notional_is_valued_at_the_mark() {
+        let mut risk = RiskManager::new(RiskLimits {
+            max_hedge_notional: Some(300.0),
+            ..RiskLimits::default()
+        });
+        assert_eq!(risk.check(&order()), Ok(()));
+        let higher_mark = ProposedOrder {
+            mark_price: 101.0,
+            price: 101.0,
+            ..order()
+        };
+        assert_eq!(breached(risk.check(&higher_mark)), "max_hedge_notional");
+    }
+
+    #[test]
+    fn turnover_counts_the_orders_within_its_window() {
+        let mut risk = RiskManager::new(RiskLimits {
+            max_turnover_notional: Some(250.0),
+            turnover_window_secs: 60,
+            ..RiskLimits::default()
+        });
+        let start = Instant::now();
+        for _ in 0..2 {
+            assert_eq!(risk.check_at(&order(), start), Ok(()));
+            risk.record_at(&order(), start);
+        }
+        assert_eq!(
+            breached(risk.check_at(&order(), start + Duration::from_secs(59))),
+            "max_turnover_notional"
+        );
+        // Both orders left the window
+        assert_eq!(
+            risk.check_at(&order(), start + Duration::from_secs(60)),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn order_count_expires_with_its_window() {
+        let mut risk = RiskManager::new(RiskLimits {
+            max_orders_per_window: Some(2),
+            order_window_secs: 10,
+            ..RiskLimits::default()
+        });
+        let start = Instant::now();
+        risk.record_at(&order(), start);
+        risk.record_at(&order(), start + Duration::from_secs(5));
+        assert_eq!(
+            breached(risk.check_at(&order(), start + Duration::from_secs(9))),
+            "max_orders_per_window"
+        );
+        // The first order expired, the second still counts
+        assert_eq!(
+            risk.check_at(&order(), start + Duration::from_secs(10)),
+            Ok(())
+        );
+        risk.record_at(&order(), start + Duration::from_secs(10));
+        assert_eq!(
+            breached(risk.check_at(&order(), start + Duration::from_secs(14))),
+            "max_orders_per_window"
+        );
+    }
+
+    #[test]
+    fn clones_share_the_order_history() {
+        let mut risk = RiskManager::new(RiskLimits {
+            max_orders_per_window: Some(1),
+            ..RiskLimits::default()
+        });
+        let mut rebuilt = risk.clone();
+        risk.record(&order());
+        assert_eq!(breached(rebuilt.check(&order())), "max_orders_per_window");
+    }
+}
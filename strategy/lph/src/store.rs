@@ -0,0 +1,778 @@
+//! Persistent record of what the strategy observed and did.
+//!
+//! `StateStore` is the storage interface: monitoring snapshots, hedge decisions, orders,
+//! fills and Binance income are appended under the name of the strategy that produced them.
+//! Orders are journaled before they are sent, so orders whose outcome was never confirmed can
+//! be reloaded and reconciled with Binance after a restart. `SqliteStore` implements it on a
+//! single SQLite file shared by every strategy of a process.
+
+use std::path::Path;
+use std::sync::Mutex;
+
+use anyhow::{anyhow, bail, Context, Result};
+use clients_binance::{Income, Side};
+use rusqlite::{params, Connection};
+
+use crate::paper::now_ms;
+use crate::types::MonitoringSnapshot;
+
+/// Outcome of one hedge decision
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DecisionAction {
+    /// The trigger condition was not met; no order
+    Hold,
+    /// A sell was placed to extend the short
+    OpenSell,
+    /// A buy was placed to reduce the short
+    CloseSell,
+    /// The kill switch blocked the order
+    KillSwitch,
+    /// The risk module rejected the order
+    Rejected,
+}
+
+impl DecisionAction {
+    /// Name stored in the `action` column
+    pub fn as_str(self) -> &'static str {
+        match self {
+            DecisionAction::Hold => "hold",
+            DecisionAction::OpenSell => "open_sell",
+            DecisionAction::CloseSell => "close_sell",
+            DecisionAction::KillSwitch => "kill_switch",
+            DecisionAction::Rejected => "rejected",
+        }
+    }
+}
+
+/// One evaluation of the trigger condition by `execute`
+#[derive(Debug, Clone)]
+pub struct DecisionRecord {
+    /// Time of the decision, in milliseconds since Unix epoch
+    pub timestamp_ms: i64,
+    /// Futures symbol
+    pub symbol: String,
+    /// Ratio used in the trigger condition
+    pub base_delta_ratio: f64,
+    /// Net BASE exposure the order offsets
+    pub base_delta: f64,
+    /// Order quantity, when the trigger condition was met
+    pub quantity: Option<f64>,
+    /// What was done
+    pub action: DecisionAction,
+    /// Rejection reason for `KillSwitch` and `Rejected`
+    pub reason: Option<String>,
+}
+
+/// Lifecycle state of a journaled order
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OrderStatus {
+    /// Journaled, not yet acknowledged by the exchange; the outcome is unknown
+    Pending,
+    /// Resting on the book
+    Open,
+    /// Resting with part of the quantity filled
+    PartiallyFilled,
+    /// Completely filled
+    Filled,
+    /// Cancelled or expired
+    Cancelled,
+    /// Refused by the exchange, or never reached it
+    Rejected,
+}
+
+impl OrderStatus {
+    /// Name stored in the `status` column
+    pub fn as_str(self) -> &'static str {
+        match self {
+            OrderStatus::Pending => "pending",
+            OrderStatus::Open => "open",
+            OrderStatus::PartiallyFilled => "partially_filled",
+            OrderStatus::Filled => "filled",
+            OrderStatus::Cancelled => "cancelled",
+            OrderStatus::Rejected => "rejected",
+        }
+    }
+
+    /// Parses a name stored in the `status` column
+    pub fn parse(value: &str) -> Result<Self> {
+        Ok(match value {
+            "pending" => OrderStatus::Pending,
+            "open" => OrderStatus::Open,
+            "partially_filled" => OrderStatus::PartiallyFilled,
+            "filled" => OrderStatus::Filled,
+            "cancelled" => OrderStatus::Cancelled,
+            "rejected" => OrderStatus::Rejected,
+            other => bail!("unknown order status {}", other),
+        })
+    }
+
+    /// Maps a Binance order status (`NEW`, `PARTIALLY_FILLED`, `FILLED`, `CANCELED`, ...)
+    pub fn from_binance(status: &str) -> Self {
+        match status {
+            "NEW" => OrderStatus::Open,
+            "PARTIALLY_FILLED" => OrderStatus::PartiallyFilled,
+            "FILLED" => OrderStatus::Filled,
+            "REJECTED" => OrderStatus::Rejected,
+            // CANCELED, EXPIRED, EXPIRED_IN_MATCH
+            _ => OrderStatus::Cancelled,
+        }
+    }
+
+    /// Whether the order can still change: it is pending or resting
+    pub fn is_active(self) -> bool {
+        matches!(
+            self,
+            OrderStatus::Pending | OrderStatus::Open | OrderStatus::PartiallyFilled
+        )
+    }
+}
+
+/// Hedge order as journaled
+#[derive(Debug, Clone)]
+pub struct OrderRecord {
+    /// Client order ID, unique across strategies and restarts
+    pub client_order_id: String,
+    /// Futures symbol
+    pub symbol: String,
+    /// Side of the order
+    pub side: Side,
+    /// Order quantity, in BASE units
+    pub quantity: f64,
+    /// Limit price
+    pub price: f64,
+    /// Exchange order ID once acknowledged (paper order ID in paper trading)
+    pub order_id: Option<i64>,
+    /// Lifecycle state
+    pub status: OrderStatus,
+    /// Quantity filled so far, in BASE units
+    pub executed_quantity: f64,
+    /// Whether the order was placed on the simulated account
+    pub paper: bool,
+    /// Time the order was journaled, in milliseconds since Unix epoch
+    pub created_at_ms: i64,
+}
+
+/// Fill of a journaled order
+#[derive(Debug, Clone)]
+pub struct FillRecord {
+    /// Time of the fill (or of its discovery), in milliseconds since Unix epoch
+    pub timestamp_ms: i64,
+    /// Client order ID of the filled order
+    pub client_order_id: String,
+    /// Futures symbol
+    pub symbol: String,
+    /// Side of the order
+    pub side: Side,
+    /// Filled quantity, in BASE units
+    pub quantity: f64,
+    /// Fill price (average price for fills discovered by reconciliation)
+    pub price: f64,
+    /// Fee paid in USDT, when known
+    pub fee: Option<f64>,
+    /// Whether the fill happened on the simulated account
+    pub paper: bool,
+}
+
+/// Result of reconciling the journaled orders with the exchange
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct OrderReconciliation {
+    /// Journaled orders that were pending or resting
+    pub checked: usize,
+    /// Orders still resting
+    pub open: usize,
+    /// Orders found filled, cancelled or expired
+    pub closed: usize,
+    /// Orders the exchange does not know (never accepted, or gone from the paper account)
+    pub missing: usize,
+}
+
+impl std::fmt::Display for OrderReconciliation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} checked, {} open, {} closed, {} missing",
+            self.checked, self.open, self.closed, self.missing
+        )
+    }
+}
+
+/// Storage of snapshots, decisions, orders, fills and income
+///
+/// Every record is kept under the name of the strategy that produced it, so one store can be
+/// shared by the strategies of a daemon.
+pub trait StateStore: Send + Sync {
+    /// Appends a monitoring snapshot
+    fn record_snapshot(&self, strategy: &str, snapshot: &MonitoringSnapshot) -> Result<()>;
+
+    /// Appends a hedge decision
+    fn record_decision(&self, strategy: &str, decision: &DecisionRecord) -> Result<()>;
+
+    /// Journals an order, replacing a previous record with the same client order ID
+    fn record_order(&self, strategy: &str, order: &OrderRecord) -> Result<()>;
+
+    /// Updates the status, and the exchange order ID if given, of a journaled order
+    fn update_order(
+        &self,
+        strategy: &str,
+        client_order_id: &str,
+        status: OrderStatus,
+        order_id: Option<i64>,
+    ) -> Result<()>;
+
+    /// Appends a fill and adds its quantity to the order's executed quantity
+    fn record_fill(&self, strategy: &str, fill: &FillRecord) -> Result<()>;
+
+    /// Stores Binance income entries, ignoring entries already stored
+    ///
+    /// # Returns
+    /// Number of new entries
+    fn record_income(&self, strategy: &str, incomes: &[Income]) -> Result<usize>;
+
+    /// Time of the latest stored income entry of a symbol, in milliseconds since Unix epoch
+    fn last_income_time(&self, strategy: &str, symbol: &str) -> Result<Option<i64>>;
+
+    /// Orders that are pending or resting, oldest first
+    fn active_orders(&self, strategy: &str) -> Result<Vec<OrderRecord>>;
+}
+
+/// Tables created on open; existing tables are kept
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS snapshots (
+    id INTEGER PRIMARY KEY,
+    strategy TEXT NOT NULL,
+    recorded_at_ms INTEGER NOT NULL,
+    block_number INTEGER NOT NULL,
+    symbol TEXT NOT NULL,
+    base_price_usdt REAL NOT NULL,
+    amm_base_amount REAL NOT NULL,
+    futures_position REAL NOT NULL,
+    base_delta REAL NOT NULL,
+    base_delta_ratio REAL NOT NULL,
+    total_value_usdt REAL NOT NULL,
+    snapshot_json TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS snapshots_strategy_time ON snapshots (strategy, recorded_at_ms);
+CREATE TABLE IF NOT EXISTS decisions (
+    id INTEGER PRIMARY KEY,
+    strategy TEXT NOT NULL,
+    timestamp_ms INTEGER NOT NULL,
+    symbol TEXT NOT NULL,
+    base_delta_ratio REAL NOT NULL,
+    base_delta REAL NOT NULL,
+    quantity REAL,
+    action TEXT NOT NULL,
+    reason TEXT
+);
+CREATE INDEX IF NOT EXISTS decisions_strategy_time ON decisions (strategy, timestamp_ms);
+CREATE TABLE IF NOT EXISTS orders (
+    client_order_id TEXT PRIMARY KEY,
+    strategy TEXT NOT NULL,
+    symbol TEXT NOT NULL,
+    side TEXT NOT NULL,
+    quantity REAL NOT NULL,
+    price REAL NOT NULL,
+    order_id INTEGER,
+    status TEXT NOT NULL,
+    executed_quantity REAL NOT NULL,
+    paper INTEGER NOT NULL,
+    created_at_ms INTEGER NOT NULL,
+    updated_at_ms INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS orders_strategy_status ON orders (strategy, status);
+CREATE TABLE IF NOT EXISTS fills (
+    id INTEGER PRIMARY KEY,
+    strategy TEXT NOT NULL,
+    timestamp_ms INTEGER NOT NULL,
+    client_order_id TEXT NOT NULL,
+    symbol TEXT NOT NULL,
+    side TEXT NOT NULL,
+    quantity REAL NOT NULL,
+    price REAL NOT NULL,
+    fee REAL,
+    paper INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS fills_strategy_time ON fills (strategy, timestamp_ms);
+CREATE TABLE IF NOT EXISTS income (
+    strategy TEXT NOT NULL,
+    symbol TEXT NOT NULL,
+    tran_id INTEGER NOT NULL,
+    income_type TEXT NOT NULL,
+    income TEXT NOT NULL,
+    asset TEXT NOT NULL,
+    info TEXT NOT NULL,
+    time_ms INTEGER NOT NULL,
+    trade_id TEXT NOT NULL,
+    PRIMARY KEY (strategy, tran_id, income_type)
+);
+CREATE INDEX IF NOT EXISTS income_strategy_time ON income (strategy, symbol, time_ms);
+";
+
+/// `StateStore` on a SQLite file
+///
+/// Writes go through one connection behind a mutex; WAL journaling lets other processes
+/// (e.g. a notebook) read the file while the daemon writes.
+pub struct SqliteStore {
+    /// Connection to the database file
+    connection: Mutex<Connection>,
+}
+
+impl SqliteStore {
+    /// Opens the database file, creating it and its tables if needed
+    ///
+    /// # Arguments
+    /// * `path` - SQLite database file
+    pub fn open(path: &Path) -> Result<Self> {
+        let connection = Connection::open(path)
+            .with_context(|| format!("Failed to open state store {}", path.display()))?;
+        connection
+            .pragma_update(None, "journal_mode", "WAL")
+            .and_then(|()| connection.busy_timeout(std::time::Duration::from_secs(5)))
+            .and_then(|()| connection.execute_batch(SCHEMA))
+            .with_context(|| format!("Failed to initialize state store {}", path.display()))?;
+        Ok(Self {
+            connection: Mutex::new(connection),
+        })
+    }
+
+    /// Runs `f` on the connection
+    fn with_connection<T>(&self, f: impl FnOnce(&Connection) -> rusqlite::Result<T>) -> Result<T> {
+        let connection = self
+            .connection
+            .lock()
+            .map_err(|_| anyhow!("state store connection poisoned"))?;
+        Ok(f(&connection)?)
+    }
+}
+
+impl StateStore for SqliteStore {
+    fn record_snapshot(&self, strategy: &str, snapshot: &MonitoringSnapshot) -> Result<()> {
+        let json = serde_json::to_string(snapshot)?;
+        self.with_connection(|c| {
+            c.execute(
+                "INSERT INTO snapshots (strategy, recorded_at_ms, block_number, symbol,
+                     base_price_usdt, amm_base_amount, futures_position, base_delta,
+                     base_delta_ratio, total_value_usdt, snapshot_json)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+                params![
+                    strategy,
+                    now_ms(),
+                    snapshot.block_number as i64,
+                    snapshot.symbol,
+                    snapshot.base_price_usdt,
+                    snapshot.amm_base_amount,
+                    snapshot.futures_position,
+                    snapshot.base_delta,
+                    snapshot.base_delta_ratio,
+                    snapshot.total_value_usdt,
+                    json
+                ],
+            )
+        })?;
+        Ok(())
+    }
+
+    fn record_decision(&self, strategy: &str, decision: &DecisionRecord) -> Result<()> {
+        self.with_connection(|c| {
+            c.execute(
+                "INSERT INTO decisions (strategy, timestamp_ms, symbol, base_delta_ratio,
+                     base_delta, quantity, action, reason)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+                params![
+                    strategy,
+                    decision.timestamp_ms,
+                    decision.symbol,
+                    decision.base_delta_ratio,
+                    decision.base_delta,
+                    decision.quantity,
+                    decision.action.as_str(),
+                    decision.reason
+                ],
+            )
+        })?;
+        Ok(())
+    }
+
+    fn record_order(&self, strategy: &str, order: &OrderRecord) -> Result<()> {
+        self.with_connection(|c| {
+            c.execute(
+                "INSERT OR REPLACE INTO orders (client_order_id, strategy, symbol, side, quantity,
+                     price, order_id, status, executed_quantity, paper, created_at_ms,
+                     updated_at_ms)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
+                params![
+                    order.client_order_id,
+                    strategy,
+                    order.symbol,
+                    order.side.as_api_str(),
+                    order.quantity,
+                    order.price,
+                    order.order_id,
+                    order.status.as_str(),
+                    order.executed_quantity,
+                    order.paper,
+                    order.created_at_ms,
+                    now_ms()
+                ],
+            )
+        })?;
+        Ok(())
+    }
+
+    fn update_order(
+        &self,
+        strategy: &str,
+        client_order_id: &str,
+        status: OrderStatus,
+        order_id: Option<i64>,
+    ) -> Result<()> {
+        let updated = self.with_connection(|c| {
+            c.execute(
+                "UPDATE orders SET status = ?1, order_id = COALESCE(?2, order_id),
+                     updated_at_ms = ?3
+                 WHERE strategy = ?4 AND client_order_id = ?5",
+                params![
+                    status.as_str(),
+                    order_id,
+                    now_ms(),
+                    strategy,
+                    client_order_id
+                ],
+            )
+        })?;
+        if updated == 0 {
+            bail!("order {} of {} is not journaled", client_order_id, strategy);
+        }
+        Ok(())
+    }
+
+    fn record_fill(&self, strategy: &str, fill: &FillRecord) -> Result<()> {
+        let mut connection = self
+            .connection
+            .lock()
+            .map_err(|_| anyhow!("state store connection poisoned"))?;
+        let tx = connection.transaction()?;
+        tx.execute(
+            "INSERT INTO fills (strategy, timestamp_ms, client_order_id, symbol, side, quantity,
+                 price, fee, paper)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            params![
+                strategy,
+                fill.timestamp_ms,
+                fill.client_order_id,
+                fill.symbol,
+                fill.side.as_api_str(),
+                fill.quantity,
+                fill.price,
+                fill.fee,
+                fill.paper
+            ],
+        )?;
+        tx.execute(
+            "UPDATE orders SET executed_quantity = executed_quantity + ?1, updated_at_ms = ?2
+             WHERE strategy = ?3 AND client_order_id = ?4",
+            params![fill.quantity, now_ms(), strategy, fill.client_order_id],
+        )?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn record_income(&self, strategy: &str, incomes: &[Income]) -> Result<usize> {
+        let mut connection = self
+            .connection
+            .lock()
+            .map_err(|_| anyhow!("state store connection poisoned"))?;
+        let tx = connection.transaction()?;
+        let mut inserted = 0;
+        for income in incomes {
+            inserted += tx.execute(
+                "INSERT OR IGNORE INTO income (strategy, symbol, tran_id, income_type, income,
+                     asset, info, time_ms, trade_id)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+                params![
+                    strategy,
+                    income.symbol,
+                    income.tran_id,
+                    income.income_type,
+                    income.income,
+                    income.asset,
+                    income.info,
+                    income.time,
+                    income.trade_id
+                ],
+            )?;
+        }
+        tx.commit()?;
+        Ok(inserted)
+    }
+
+    fn last_income_time(&self, strategy: &str, symbol: &str) -> Result<Option<i64>> {
+        self.with_connection(|c| {
+            c.query_row(
+                "SELECT MAX(time_ms) FROM income WHERE strategy = ?1 AND symbol = ?2",
+                params![strategy, symbol],
+                |row| row.get(0),
+            )
+        })
+    }
+
+    fn active_orders(&self, strategy: &str) -> Result<Vec<OrderRecord>> {
+        let rows = self.with_connection(|c| {
+            let mut statement = c.prepare(
+                "SELECT client_order_id, symbol, side, quantity, price, order_id, status,
+                     executed_quantity, paper, created_at_ms
+                 FROM orders
+                 WHERE strategy = ?1 AND status IN ('pending', 'open', 'partially_filled')
+                 ORDER BY created_at_ms",
+            )?;
+            let rows = statement
+                .query_map(params![strategy], |row| {
+                    Ok((
+                        row.get::<_, String>(0)?,
+                        row.get::<_, String>(1)?,
+                        row.get::<_, String>(2)?,
+                        row.get::<_, f64>(3)?,
+                        row.get::<_, f64>(4)?,
+                        row.get::<_, Option<i64>>(5)?,
+                        row.get::<_, String>(6)?,
+                        row.get::<_, f64>(7)?,
+                        row.get::<_, bool>(8)?,
+                        row.get::<_, i64>(9)?,
+                    ))
+                })?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+            Ok(rows)
+        })?;
+        rows.into_iter()
+            .map(
+                |(
+                    client_order_id,
+                    symbol,
+                    side,
+                    quantity,
+                    price,
+                    order_id,
+                    status,
+                    executed_quantity,
+                    paper,
+                    created_at_ms,
+                )| {
+                    Ok(OrderRecord {
+                        side: parse_side(&side)?,
+                        status: OrderStatus::parse(&status)?,
+                        client_order_id,
+                        symbol,
+                        quantity,
+                        price,
+                        order_id,
+                        executed_quantity,
+                        paper,
+                        created_at_ms,
+                    })
+                },
+            )
+            .collect()
+    }
+}
+
+/// Parses a side stored as its API string
+fn parse_side(value: &str) -> Result<Side> {
+    match value {
+        "BUY" => Ok(Side::Buy),
+        "SELL" => Ok(Side::Sell),
+        other => bail!("unknown order side {}", other),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn store() -> SqliteStore {
+        SqliteStore::open(Path::new(":memory:")).unwrap()
+    }
+
+    fn order(client_order_id: &str, created_at_ms: i64) -> OrderRecord {
+        OrderRecord {
+            client_order_id: client_order_id.to_string(),
+            symbol: "ETHUSDT".to_string(),
+            side: Side::Sell,
+            quantity: 2.0,
+            price: 2500.5,
+            order_id: None,
+            status: OrderStatus::Pending,
+            executed_quantity: 0.0,
+            paper: false,
+            created_at_ms,
+        }
+    }
+
+    fn fill(client_order_id: &str, quantity: f64) -> FillRecord {
+        FillRecord {
+            timestamp_ms: 1_700_000_000_000,
+            client_order_id: client_order_id.to_string(),
+            symbol: "ETHUSDT".to_string(),
+            side: Side::Sell,
+            quantity,
+            price: 2500.5,
+            fee: None,
+            paper: false,
+        }
+    }
+
+    fn income(tran_id: i64, income_type: &str, time: i64) -> Income {
+        Income {
+            symbol: "ETHUSDT".to_string(),
+            income_type: income_type.to_string(),
+            income: "-0.12".to_string(),
+            asset: "USDT".to_string(),
+            info: String::new(),
+            time,
+            tran_id,
+            trade_id: String::new(),
+        }
+    }
+
+    fn fill_count(store: &SqliteStore) -> i64 {
+        store
+            .with_connection(|c| c.query_row("SELECT COUNT(*) FROM fills", [], |row| row.get(0)))
+            .unwrap()
+    }
+
+    #[test]
+    fn orders_round_trip_and_replace_by_client_order_id() {
+        let store = store();
+        store.record_order("eth", &order("lph-1", 10)).unwrap();
+        let journaled = store.active_orders("eth").unwrap();
+        assert_eq!(journaled.len(), 1);
+        let stored = &journaled[0];
+        assert_eq!(stored.client_order_id, "lph-1");
+        assert_eq!(stored.symbol, "ETHUSDT");
+        assert_eq!(stored.side, Side::Sell);
+        assert_eq!(stored.quantity, 2.0);
+        assert_eq!(stored.price, 2500.5);
+        assert_eq!(stored.order_id, None);
+        assert_eq!(stored.status, OrderStatus::Pending);
+        assert!(!stored.paper);
+        assert_eq!(stored.created_at_ms, 10);
+
+        // The same client order ID replaces the record instead of adding one
+        let replaced = OrderRecord {
+            side: Side::Buy,
+            order_id: Some(42),
+            status: OrderStatus::Open,
+            ..order("lph-1", 10)
+        };
+        store.record_order("eth", &replaced).unwrap();
+        let journaled = store.active_orders("eth").unwrap();
+        assert_eq!(journaled.len(), 1);
+        assert_eq!(journaled[0].side, Side::Buy);
+        assert_eq!(journaled[0].order_id, Some(42));
+        assert_eq!(journaled[0].status, OrderStatus::Open);
+    }
+
+    #[test]
+    fn update_keeps_the_order_id_unless_given() {
+        let store = store();
+        store.record_order("eth", &order("lph-1", 10)).unwrap();
+        store
+            .update_order("eth", "lph-1", OrderStatus::Open, Some(7))
+            .unwrap();
+        store
+            .update_order("eth", "lph-1", OrderStatus::PartiallyFilled, None)
+            .unwrap();
+        let stored = &store.active_orders("eth").unwrap()[0];
+        assert_eq!(stored.status, OrderStatus::PartiallyFilled);
+        assert_eq!(stored.order_id, Some(7));
+
+        // Another strategy's order is not journaled for this one
+        assert!(store
+            .update_order("btc", "lph-1", OrderStatus::Filled, None)
+            .is_err());
+    }
+
+    #[test]
+    fn fills_add_to_the_executed_quantity() {
+        let store = store();
+        store.record_order("eth", &order("lph-1", 10)).unwrap();
+        store.record_fill("eth", &fill("lph-1", 0.5)).unwrap();
+        store.record_fill("eth", &fill("lph-1", 0.75)).unwrap();
+        assert_eq!(
+            store.active_orders("eth").unwrap()[0].executed_quantity,
+            1.25
+        );
+        assert_eq!(fill_count(&store), 2);
+    }
+
+    #[test]
+    fn a_failed_executed_quantity_update_rolls_back_the_fill() {
+        let store = store();
+        store.record_order("eth", &order("lph-1", 10)).unwrap();
+        store
+            .with_connection(|c| {
+                c.execute_batch(
+                    "CREATE TRIGGER overfill BEFORE UPDATE OF executed_quantity ON orders
+                     WHEN NEW.executed_quantity > NEW.quantity
+                     BEGIN SELECT RAISE(ABORT, 'overfilled'); END;",
+                )
+            })
+            .unwrap();
+        store.record_fill("eth", &fill("lph-1", 1.5)).unwrap();
+        assert!(store.record_fill("eth", &fill("lph-1", 1.0)).is_err());
+
+        // Neither the second fill nor its quantity was stored
+        assert_eq!(fill_count(&store), 1);
+        assert_eq!(
+            store.active_orders("eth").unwrap()[0].executed_quantity,
+            1.5
+        );
+    }
+
+    #[test]
+    fn income_is_stored_once_per_transaction_and_type() {
+        let store = store();
+        let entries = [
+            income(1, "FUNDING_FEE", 100),
+            income(1, "COMMISSION", 100),
+            income(2, "FUNDING_FEE", 200),
+        ];
+        assert_eq!(store.record_income("eth", &entries).unwrap(), 3);
+        // Overlapping fetches only add the new entries
+        let next = [income(2, "FUNDING_FEE", 200), income(3, "FUNDING_FEE", 300)];
+        assert_eq!(store.record_income("eth", &next).unwrap(), 1);
+        // Keys are per strategy
+        assert_eq!(store.record_income("btc", &entries[..1]).unwrap(), 1);
+
+        assert_eq!(store.last_income_time("eth", "ETHUSDT").unwrap(), Some(300));
+        assert_eq!(store.last_income_time("eth", "BTCUSDT").unwrap(), None);
+        assert_eq!(store.last_income_time("sol", "ETHUSDT").unwrap(), None);
+    }
+
+    #[test]
+    fn active_orders_are_pending_or_resting_orders_of_the_strategy_oldest_first() {
+        let store = store();
+        for (id, created_at_ms, status) in [
+            ("open", 30, OrderStatus::Open),
+            ("pending", 10, OrderStatus::Pending),
+            ("partial", 20, OrderStatus::PartiallyFilled),
+            ("filled", 5, OrderStatus::Filled),
+            ("cancelled", 6, OrderStatus::Cancelled),
+            ("rejected", 7, OrderStatus::Rejected),
+        ] {
+            store
+                .record_order("eth", &order(id, created_at_ms))
+                .unwrap();
+            store.update_order("eth", id, status, None).unwrap();
+        }
+        store.record_order("btc", &order("other", 1)).unwrap();
+
+        let active: Vec<String> = store
+            .active_orders("eth")
+            .unwrap()
+            .into_iter()
+            .map(|order| order.client_order_id)
+            .collect();
+        assert_eq!(active, ["pending", "partial", "open"]);
+        assert_eq!(store.active_orders("btc").unwrap().len(), 1);
+        assert!(store.active_orders("sol").unwrap().is_empty());
+    }
+}
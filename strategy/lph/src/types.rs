@@ -1,24 +1,35 @@
 //! Shared types for LP Hedging strategy.
 
+use std::collections::BTreeMap;
+
+use alloy::primitives::{B256, U256};
 use serde::{Deserialize, Serialize};
 
+use crate::store::{DecisionAction, OrderStatus};
+
 /// Monitoring snapshot containing all computed metrics
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MonitoringSnapshot {
     /// Blockchain block number at which the on-chain LP position data was read
     pub block_number: u64,
+    /// Hash of the block the on-chain LP position data was read at
+    pub block_hash: B256,
     /// Futures symbol
     pub symbol: String,
-    /// Amount of BASE tokens in LP position
+    /// Amount of BASE tokens across the hedged LP positions
     pub amm_base_amount: f64,
-    /// Amount of USDT tokens in LP position
+    /// Amount of USDT tokens across the hedged LP positions
     pub amm_usdt_amount: f64,
     /// Amount of BASE that can be collected as fees from the LP position
     pub amm_collectable_base: f64,
     /// Amount of USDT that can be collected as fees from the LP position
     pub amm_collectable_usdt: f64,
-    /// Total value in USDT of collectable AMM fees (score)
+    /// Total value in USDT of collectable AMM fees and pending farm rewards (score)
     pub amm_collectable_value_usdt: f64,
+    /// Pending farm rewards of staked positions, in reward token units
+    pub amm_pending_reward: f64,
+    /// Value in USDT of the pending farm rewards (zero without a reward symbol)
+    pub amm_reward_value_usdt: f64,
     /// Net futures position in BASE units (positive = long, negative = short)
     pub futures_position: f64,
     /// Unrealized PnL of the futures position in USDT
@@ -35,6 +46,83 @@ pub struct MonitoringSnapshot {
     pub amm_total_value_usdt: f64,
     /// Total combined value in USDT (AMM value plus unrealized PnL)
     pub total_value_usdt: f64,
+    /// BASE price in USDT implied by the pool the LP position belongs to
+    pub amm_price_usdt: f64,
+    /// LP gamma at the pool price: change of `amm_base_amount` per 1 USDT of BASE price move
+    pub amm_base_gamma: f64,
+    /// Exposure ladder under the configured price shocks
+    pub price_shocks: Vec<PriceShock>,
+    /// Per-position breakdown of the aggregated AMM amounts
+    pub positions: Vec<PositionSnapshot>,
+    /// Simulated account in paper trading mode; the futures fields above then describe it
+    pub paper: Option<PaperAccountSnapshot>,
+}
+
+/// State of the simulated futures account in paper trading mode
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PaperAccountSnapshot {
+    /// Signed position in BASE units (negative = short)
+    pub position: f64,
+    /// Average entry price of the position
+    pub entry_price: f64,
+    /// Unrealized PnL at the mark price, in USDT
+    pub unrealized_pnl: f64,
+    /// PnL realized by reducing the position, in USDT
+    pub realized_pnl: f64,
+    /// Fees paid on fills, in USDT
+    pub fees_paid: f64,
+    /// Realized plus unrealized PnL minus fees, in USDT
+    pub net_pnl: f64,
+    /// Notional of every fill, in USDT
+    pub filled_notional: f64,
+    /// Number of fills
+    pub fills: u64,
+    /// Resting orders
+    pub open_orders: usize,
+}
+
+/// BASE/USDT view of a single LP position included in the snapshot
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PositionSnapshot {
+    /// The position NFT token ID
+    pub token_id: U256,
+    /// Lower tick of the position range
+    pub tick_lower: i32,
+    /// Upper tick of the position range
+    pub tick_upper: i32,
+    /// Whether the pool tick is inside the position range
+    pub in_range: bool,
+    /// BASE price in USDT implied by the position's pool
+    pub price_usdt: f64,
+    /// Amount of BASE tokens in the position
+    pub base_amount: f64,
+    /// Amount of USDT tokens in the position
+    pub usdt_amount: f64,
+    /// Amount of BASE that can be collected as fees
+    pub collectable_base: f64,
+    /// Amount of USDT that can be collected as fees
+    pub collectable_usdt: f64,
+    /// Whether the position is staked in a farm
+    pub staked: bool,
+    /// Pending farm rewards, in reward token units
+    pub pending_reward: f64,
+    /// Position gamma at `price_usdt`
+    pub gamma: f64,
+}
+
+/// Projected exposure under a relative BASE price shock
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PriceShock {
+    /// Relative price move (e.g. `-0.05` for -5%)
+    pub shock: f64,
+    /// Shocked BASE price in USDT
+    pub price: f64,
+    /// BASE amount held by the LP position at the shocked price
+    pub amm_base_amount: f64,
+    /// Net BASE exposure at the shocked price with the current futures position
+    pub base_delta: f64,
+    /// Change in combined AMM value and futures PnL in USDT
+    pub value_change_usdt: f64,
 }
 
 impl MonitoringSnapshot {
@@ -60,14 +148,112 @@ impl MonitoringSnapshot {
             self.base_delta_ratio * 100.0
         );
 
-        let line4 = format!(
+        let mut line4 = format!(
             "Reward: {:.4} USD = {:.4} {} + {:.4} USD",
             self.amm_collectable_value_usdt,
             self.amm_collectable_base,
             symbol,
             self.amm_collectable_usdt
         );
+        if self.amm_pending_reward > 0.0 {
+            line4.push_str(&format!(
+                " + {:.4} USD ({:.4} farm reward)",
+                self.amm_reward_value_usdt, self.amm_pending_reward
+            ));
+        }
+
+        let line5 = format!(
+            "Gamma: {:.4} {} per 1% move",
+            self.amm_base_gamma * self.amm_price_usdt * 0.01,
+            symbol
+        );
+
+        let mut lines = vec![line1, line2, line3, line4, line5];
+        if let Some(paper) = &self.paper {
+            lines.push(format!(
+                "PAPER: {:.4} {} @ {:.2}, PnL {:.4} USD (realized {:.4}, unrealized {:.4}, fees {:.4}), {} fills, {} open orders",
+                paper.position,
+                symbol,
+                paper.entry_price,
+                paper.net_pnl,
+                paper.realized_pnl,
+                paper.unrealized_pnl,
+                paper.fees_paid,
+                paper.fills,
+                paper.open_orders
+            ));
+        }
+        lines.join("\n")
+    }
+}
+
+/// Outcome of re-centering one LP position
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RebalanceReport {
+    /// Token ID of the out-of-range position that was closed and burned
+    pub closed_token_id: U256,
+    /// Token ID of the newly minted position; `None` if no replacement was minted and the
+    /// withdrawn tokens stay in the wallet
+    pub minted_token_id: Option<U256>,
+    /// Lower tick of the new range (of the closed position if it was not computed)
+    pub tick_lower: i32,
+    /// Upper tick of the new range (of the closed position if it was not computed)
+    pub tick_upper: i32,
+    /// Whether the withdrawn tokens were swapped before minting
+    pub swapped: bool,
+    /// Gas cost of the close, swap and mint transactions that were mined, in wei of the native token
+    pub gas_cost: U256,
+    /// Why the swap or mint after a successful close failed
+    pub mint_error: Option<String>,
+}
+
+/// Compounding action taken on a position's fees
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CompoundAction {
+    /// Fees were added back to the position
+    Reinvested,
+    /// Fees were collected to the treasury
+    Swept,
+}
+
+/// Outcome of compounding the fees of one LP position
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompoundReport {
+    /// The position NFT token ID
+    pub token_id: U256,
+    /// Action taken on the fees
+    pub action: CompoundAction,
+    /// Value of the collected fees in USDT at the time of the decision
+    pub fee_value_usdt: f64,
+    /// Estimated gas cost of the action in USDT used for the threshold
+    pub estimated_gas_usdt: f64,
+    /// Raw token0 amount collected
+    pub amount0: U256,
+    /// Raw token1 amount collected
+    pub amount1: U256,
+    /// Gas cost of the collect, swap and increase transactions, in wei of the native token
+    pub gas_cost: U256,
+}
+
+/// Activity counted by an `LPHStrategy` since it was created, for monitoring
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StrategyCounts {
+    /// Hedge decisions by `DecisionAction::as_str`
+    pub decisions: BTreeMap<&'static str, u64>,
+    /// Orders placed, by `OrderStatus::as_str` of the placement outcome (`pending` when unknown)
+    pub orders: BTreeMap<&'static str, u64>,
+    /// Snapshots refused because a hedged position did not sync
+    pub sync_errors: u64,
+}
+
+impl StrategyCounts {
+    /// Counts one decision
+    pub fn count_decision(&mut self, action: DecisionAction) {
+        *self.decisions.entry(action.as_str()).or_default() += 1;
+    }
 
-        [line1, line2, line3, line4].join("\n")
+    /// Counts one order by the status it had after placement
+    pub fn count_order(&mut self, status: OrderStatus) {
+        *self.orders.entry(status.as_str()).or_default() += 1;
     }
 }
@@ -0,0 +1,65 @@
+//! Fixed-bucket latency histogram shared by the clients that time their requests.
+//!
+//! Buckets follow the Prometheus convention: each bucket counts the observations
+//! less than or equal to its upper bound, and an implicit `+Inf` bucket holds the
+//! total count.
+
+use std::time::Duration;
+
+/// Upper bounds of the latency buckets, in seconds.
+pub const LATENCY_BUCKETS_SECS: [f64; 12] = [
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0,
+];
+
+/// Request latency distribution over [`LATENCY_BUCKETS_SECS`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LatencyHistogram {
+    /// Observations per bucket (not cumulative); the last entry counts those above every bound
+    counts: [u64; LATENCY_BUCKETS_SECS.len() + 1],
+    /// Sum of all observations, in seconds
+    sum_secs: f64,
+}
+
+impl LatencyHistogram {
+    /// Creates an empty histogram.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records one request latency.
+    pub fn observe(&mut self, latency: Duration) {
+        let secs = latency.as_secs_f64();
+        let bucket = LATENCY_BUCKETS_SECS
+            .iter()
+            .position(|bound| secs <= *bound)
+            .unwrap_or(LATENCY_BUCKETS_SECS.len());
+        self.counts[bucket] += 1;
+        self.sum_secs += secs;
+    }
+
+    /// Cumulative counts per bucket.
+    ///
+    /// # Returns
+    /// `(upper bound in seconds, observations <= bound)` for every finite bucket; the
+    /// `+Inf` bucket is [`count`](Self::count)
+    pub fn buckets(&self) -> Vec<(f64, u64)> {
+        LATENCY_BUCKETS_SECS
+            .iter()
+            .zip(&self.counts)
+            .scan(0u64, |cumulative, (bound, count)| {
+                *cumulative += count;
+                Some((*bound, *cumulative))
+            })
+            .collect()
+    }
+
+    /// Number of observations.
+    pub fn count(&self) -> u64 {
+        self.counts.iter().sum()
+    }
+
+    /// Sum of all observations, in seconds.
+    pub fn sum_secs(&self) -> f64 {
+        self.sum_secs
+    }
+}
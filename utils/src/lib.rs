@@ -1,14 +1,12 @@
 //! Shared utilities for the urban workspace.
 
-use alloy::primitives::U256;
+mod histogram;
+mod units;
 
-/// Converts a U256 value to f64, accounting for token decimals.
-///
-/// Values larger than `u128::MAX` are truncated; this is acceptable for f64 precision.
-pub fn u256_to_f64(value: U256, decimals: u32) -> f64 {
-    let value_u128 = value.to::<u128>();
-    let divisor = 10_u128.pow(decimals);
-    let whole_part = value_u128 / divisor;
-    let fractional_part = value_u128 % divisor;
-    whole_part as f64 + (fractional_part as f64 / divisor as f64)
-}
+pub use histogram::{LatencyHistogram, LATENCY_BUCKETS_SECS};
+
+pub use units::{
+    checked_i256_to_f64, checked_u256_to_f64, decimal_to_i256, decimal_to_u256, format_units,
+    format_units_signed, i256_to_decimal, i256_to_f64, parse_units, parse_units_signed,
+    u256_to_decimal, u256_to_f64,
+};
@@ -0,0 +1,329 @@
+//! Lossless conversions between raw token amounts and decimal representations.
+//!
+//! Formatting and parsing work on decimal digit strings, so they are exact for
+//! every `U256` / `I256` value and every decimals count. Conversions to `f64`
+//! round once to the nearest representable value; conversions to `Decimal`
+//! fail instead of losing precision.
+
+use alloy::primitives::{Sign, I256, U256};
+use anyhow::{anyhow, Result};
+use rust_decimal::Decimal;
+
+/// Largest integer below which every integer is exactly representable as f64 (2^53).
+const F64_EXACT_INTEGER_LIMIT: f64 = 9_007_199_254_740_992.0;
+
+/// Formats a raw amount as a decimal string with exactly `decimals` fractional digits.
+///
+/// `format_units(U256::from(1_500_000u64), 6)` returns `"1.500000"`.
+pub fn format_units(value: U256, decimals: u8) -> String {
+    let digits = value.to_string();
+    let decimals = decimals as usize;
+    if decimals == 0 {
+        return digits;
+    }
+    let digits = format!("{:0>width$}", digits, width = decimals + 1);
+    let (integer, fraction) = digits.split_at(digits.len() - decimals);
+    format!("{}.{}", integer, fraction)
+}
+
+/// Formats a signed raw amount as a decimal string with exactly `decimals` fractional digits.
+pub fn format_units_signed(value: I256, decimals: u8) -> String {
+    let formatted = format_units(value.unsigned_abs(), decimals);
+    if value.is_negative() {
+        format!("-{}", formatted)
+    } else {
+        formatted
+    }
+}
+
+/// Parses a decimal string into a raw amount with `decimals` fractional digits.
+///
+/// Accepts `"1"`, `"1.5"`, `".5"` and `"1."`. Returns an error if the string is not a
+/// plain decimal number, if it has non-zero digits beyond `decimals` (the value would
+/// be rounded), or if the result does not fit in `U256`.
+pub fn parse_units(value: &str, decimals: u8) -> Result<U256> {
+    let value = value.trim();
+    let (integer, fraction) = value.split_once('.').unwrap_or((value, ""));
+    if integer.is_empty() && fraction.is_empty() {
+        return Err(anyhow!("invalid decimal amount {:?}", value));
+    }
+    if !integer
+        .bytes()
+        .chain(fraction.bytes())
+        .all(|b| b.is_ascii_digit())
+    {
+        return Err(anyhow!("invalid decimal amount {:?}", value));
+    }
+
+    let decimals = decimals as usize;
+    let fraction = fraction.trim_end_matches('0');
+    if fraction.len() > decimals {
+        return Err(anyhow!(
+            "amount {:?} has more than {} fractional digits",
+            value,
+            decimals
+        ));
+    }
+
+    let digits = format!("{}{:0<width$}", integer, fraction, width = decimals);
+    let digits = digits.trim_start_matches('0');
+    if digits.is_empty() {
+        return Ok(U256::ZERO);
+    }
+    U256::from_str_radix(digits, 10)
+        .map_err(|e| anyhow!("amount {:?} does not fit in U256: {}", value, e))
+}
+
+/// Parses a signed decimal string into a raw amount with `decimals` fractional digits.
+///
+/// Accepts an optional leading `-` or `+`; otherwise behaves like [`parse_units`].
+pub fn parse_units_signed(value: &str, decimals: u8) -> Result<I256> {
+    let value = value.trim();
+    let (negative, magnitude) = match value.strip_prefix('-') {
+        Some(magnitude) => (true, magnitude),
+        None => (false, value.strip_prefix('+').unwrap_or(value)),
+    };
+    // The sign must be attached to the number; `parse_units` would trim the gap
+    if magnitude.starts_with(char::is_whitespace) {
+        return Err(anyhow!("invalid decimal amount {:?}", value));
+    }
+    let magnitude = parse_units(magnitude, decimals)?;
+    let sign = if negative {
+        Sign::Negative
+    } else {
+        Sign::Positive
+    };
+    I256::checked_from_sign_and_abs(sign, magnitude)
+        .ok_or_else(|| anyhow!("amount {:?} does not fit in I256", value))
+}
+
+/// Converts a U256 value to f64, accounting for token decimals.
+///
+/// The conversion is exact up to a single rounding to the nearest f64; large values
+/// are not truncated.
+pub fn u256_to_f64(value: U256, decimals: u8) -> f64 {
+    // A formatted decimal string always parses as f64
+    format_units(value, decimals).parse().unwrap_or(f64::NAN)
+}
+
+/// Converts a signed I256 value to f64, accounting for token decimals.
+pub fn i256_to_f64(value: I256, decimals: u8) -> f64 {
+    format_units_signed(value, decimals)
+        .parse()
+        .unwrap_or(f64::NAN)
+}
+
+/// Converts a U256 value to f64, returning `None` if whole units would lose precision.
+///
+/// Fractional digits are always rounded; `None` is returned when the integer part
+/// is 2^53 or larger and can no longer be represented exactly.
+pub fn checked_u256_to_f64(value: U256, decimals: u8) -> Option<f64> {
+    let converted = u256_to_f64(value, decimals);
+    (converted.abs() < F64_EXACT_INTEGER_LIMIT).then_some(converted)
+}
+
+/// Converts a signed I256 value to f64, returning `None` if whole units would lose precision.
+pub fn checked_i256_to_f64(value: I256, decimals: u8) -> Option<f64> {
+    let converted = i256_to_f64(value, decimals);
+    (converted.abs() < F64_EXACT_INTEGER_LIMIT).then_some(converted)
+}
+
+/// Converts a U256 value to `Decimal` exactly, or returns an error if it does not fit.
+///
+/// `Decimal` holds 28 significant fractional digits and a 96-bit mantissa; values that
+/// need more are rejected rather than rounded.
+pub fn u256_to_decimal(value: U256, decimals: u8) -> Result<Decimal> {
+    let formatted = format_units(value, decimals);
+    Decimal::from_str_exact(&formatted)
+        .map_err(|e| anyhow!("amount {} does not fit in Decimal: {}", formatted, e))
+}
+
+/// Converts a signed I256 value to `Decimal` exactly, or returns an error if it does not fit.
+pub fn i256_to_decimal(value: I256, decimals: u8) -> Result<Decimal> {
+    let formatted = format_units_signed(value, decimals);
+    Decimal::from_str_exact(&formatted)
+        .map_err(|e| anyhow!("amount {} does not fit in Decimal: {}", formatted, e))
+}
+
+/// Converts a non-negative `Decimal` into a raw U256 amount with `decimals` fractional digits.
+pub fn decimal_to_u256(value: Decimal, decimals: u8) -> Result<U256> {
+    if value.is_sign_negative() && !value.is_zero() {
+        return Err(anyhow!("amount {} is negative", value));
+    }
+    parse_units(&value.normalize().to_string(), decimals)
+}
+
+/// Converts a `Decimal` into a raw signed I256 amount with `decimals` fractional digits.
+pub fn decimal_to_i256(value: Decimal, decimals: u8) -> Result<I256> {
+    parse_units_signed(&value.normalize().to_string(), decimals)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    const DECIMALS: [u8; 7] = [0, 1, 6, 8, 18, 36, 80];
+
+    fn u256_strategy() -> impl Strategy<Value = U256> {
+        prop_oneof![
+            Just(U256::ZERO),
+            Just(U256::MAX),
+            any::<u64>().prop_map(U256::from),
+            any::<[u64; 4]>().prop_map(U256::from_limbs),
+        ]
+    }
+
+    fn i256_strategy() -> impl Strategy<Value = I256> {
+        prop_oneof![
+            Just(I256::ZERO),
+            Just(I256::MIN),
+            Just(I256::MAX),
+            any::<i64>().prop_map(|v| I256::try_from(v).unwrap()),
+            any::<[u64; 4]>().prop_map(|limbs| I256::from_raw(U256::from_limbs(limbs))),
+        ]
+    }
+
+    proptest! {
+        #[test]
+        fn u256_round_trips(value in u256_strategy(), decimals in prop::sample::select(&DECIMALS[..])) {
+            let formatted = format_units(value, decimals);
+            prop_assert_eq!(parse_units(&formatted, decimals).unwrap(), value);
+        }
+
+        #[test]
+        fn i256_round_trips(value in i256_strategy(), decimals in prop::sample::select(&DECIMALS[..])) {
+            let formatted = format_units_signed(value, decimals);
+            prop_assert_eq!(parse_units_signed(&formatted, decimals).unwrap(), value);
+        }
+
+        #[test]
+        fn surrounding_whitespace_is_ignored(value in u256_strategy(), decimals in prop::sample::select(&DECIMALS[..])) {
+            let padded = format!("  {}\t\n", format_units(value, decimals));
+            prop_assert_eq!(parse_units(&padded, decimals).unwrap(), value);
+        }
+    }
+
+    #[test]
+    fn formats_boundaries() {
+        assert_eq!(format_units(U256::ZERO, 0), "0");
+        assert_eq!(format_units(U256::ZERO, 6), "0.000000");
+        assert_eq!(format_units(U256::from(1_500_000u64), 6), "1.500000");
+        assert_eq!(format_units(U256::from(5u64), 3), "0.005");
+        assert_eq!(
+            format_units(U256::MAX, 18),
+            "115792089237316195423570985008687907853269984665640564039457.584007913129639935"
+        );
+        assert_eq!(
+            format_units_signed(I256::MIN, 0),
+            "-57896044618658097711785492504343953926634992332820282019728792003956564819968"
+        );
+        assert_eq!(
+            format_units_signed(I256::MAX, 0),
+            "57896044618658097711785492504343953926634992332820282019728792003956564819967"
+        );
+        assert_eq!(format_units_signed(I256::MINUS_ONE, 2), "-0.01");
+    }
+
+    #[test]
+    fn parses_partial_forms() {
+        assert_eq!(parse_units("1", 2).unwrap(), U256::from(100u64));
+        assert_eq!(parse_units("1.", 2).unwrap(), U256::from(100u64));
+        assert_eq!(parse_units(".5", 2).unwrap(), U256::from(50u64));
+        assert_eq!(parse_units("0001.50", 2).unwrap(), U256::from(150u64));
+        assert_eq!(parse_units("0", 18).unwrap(), U256::ZERO);
+    }
+
+    #[test]
+    fn rejects_too_many_fractional_digits() {
+        assert!(parse_units("1.234", 2).is_err());
+        assert!(parse_units("0.1", 0).is_err());
+        assert!(parse_units_signed("-1.001", 2).is_err());
+        // Trailing zeros do not round and are accepted
+        assert_eq!(parse_units("1.2300", 2).unwrap(), U256::from(123u64));
+    }
+
+    #[test]
+    fn handles_signs() {
+        assert!(parse_units("-1", 0).is_err());
+        assert!(parse_units("+1", 0).is_err());
+        assert_eq!(
+            parse_units_signed("+1.5", 1).unwrap(),
+            I256::try_from(15).unwrap()
+        );
+        assert_eq!(
+            parse_units_signed(" -1.5 ", 1).unwrap(),
+            I256::try_from(-15).unwrap()
+        );
+        assert_eq!(parse_units_signed("-0", 6).unwrap(), I256::ZERO);
+        assert!(parse_units_signed("--1", 0).is_err());
+        assert!(parse_units_signed("- 1", 0).is_err());
+        assert!(parse_units_signed("+-1", 0).is_err());
+    }
+
+    #[test]
+    fn rejects_malformed_input() {
+        for value in ["", " ", ".", "1.2.3", "1,5", "1e18", "0x10", "1 000", "abc"] {
+            assert!(parse_units(value, 18).is_err(), "{:?} parsed", value);
+        }
+    }
+
+    #[test]
+    fn rejects_overflow() {
+        let max = format_units(U256::MAX, 0);
+        assert!(parse_units(&format!("{}0", max), 0).is_err());
+        assert!(parse_units(&max, 1).is_err());
+        let min = format_units_signed(I256::MIN, 0);
+        assert_eq!(parse_units_signed(&min, 0).unwrap(), I256::MIN);
+        let max = format_units_signed(I256::MAX, 0);
+        assert!(parse_units_signed(&format!("{}8", &max[..max.len() - 1]), 0).is_err());
+        assert!(parse_units_signed(&format!("-{}", format_units(U256::MAX, 0)), 0).is_err());
+    }
+
+    #[test]
+    fn checked_f64_rejects_imprecise_integers() {
+        let limit = U256::from(1u64 << 53);
+        assert_eq!(
+            checked_u256_to_f64(limit - U256::from(1u64), 0),
+            Some(9_007_199_254_740_991.0)
+        );
+        assert_eq!(checked_u256_to_f64(limit, 0), None);
+        assert_eq!(checked_u256_to_f64(U256::MAX, 0), None);
+        assert_eq!(
+            checked_u256_to_f64(U256::MAX, 77),
+            Some(u256_to_f64(U256::MAX, 77))
+        );
+        assert_eq!(checked_i256_to_f64(I256::MIN, 0), None);
+        assert_eq!(checked_i256_to_f64(I256::MAX, 0), None);
+        assert_eq!(
+            checked_i256_to_f64(I256::try_from(-1_500_000i64).unwrap(), 6),
+            Some(-1.5)
+        );
+        // Unchecked conversions round instead of truncating
+        assert_eq!(u256_to_f64(U256::MAX, 0), 1.157920892373162e77);
+        assert_eq!(
+            u256_to_f64(U256::from(u128::MAX) + U256::from(1u64), 0),
+            2f64.powi(128)
+        );
+    }
+
+    #[test]
+    fn decimal_conversions_reject_overflow() {
+        let mantissa_max = U256::from(u128::from(u64::MAX) << 32 | u128::from(u32::MAX));
+        assert!(u256_to_decimal(mantissa_max, 0).is_ok());
+        assert!(u256_to_decimal(mantissa_max + U256::from(1u64), 0).is_err());
+        assert!(u256_to_decimal(U256::from(u128::MAX), 0).is_err());
+        assert!(u256_to_decimal(U256::MAX, 18).is_err());
+        assert!(i256_to_decimal(I256::MIN, 18).is_err());
+        assert_eq!(
+            u256_to_decimal(U256::from(1_500_000u64), 6).unwrap(),
+            Decimal::new(15, 1)
+        );
+        assert!(decimal_to_u256(Decimal::new(-1, 0), 0).is_err());
+        assert!(decimal_to_u256(Decimal::new(15, 1), 0).is_err());
+        assert_eq!(
+            decimal_to_i256(Decimal::new(-15, 1), 18).unwrap(),
+            parse_units_signed("-1.5", 18).unwrap()
+        );
+    }
+}